@@ -27,3 +27,18 @@ pub fn local_chain_spec() -> Result<ChainSpec, String> {
 	.with_genesis_config_preset_name(sp_genesis_builder::LOCAL_TESTNET_RUNTIME_PRESET)
 	.build())
 }
+
+/// Development chain with a few PoBA slots already finalized at genesis.
+pub fn staging_history_chain_spec() -> Result<ChainSpec, String> {
+	Ok(ChainSpec::builder(
+		WASM_BINARY.ok_or_else(|| "Development wasm not available".to_string())?,
+		None,
+	)
+	.with_name("Staging History")
+	.with_id("staging_history")
+	.with_chain_type(ChainType::Development)
+	.with_genesis_config_preset_name(
+		solochain_template_runtime::genesis_config_presets::STAGING_HISTORY_PRESET,
+	)
+	.build())
+}
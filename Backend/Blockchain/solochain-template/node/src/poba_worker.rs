@@ -7,19 +7,86 @@
 //!  - Each node should have a unique POBA_PROPOSER_ID (e.g. "alice", "bob").
 //!  - Only nodes with POBA_ROLE=finalizer will also call /poba/finalize-slot.
 //!
+//! Market snapshot:
+//!  - Each round fetches GET /poba/market/{slot}: the market the backend
+//!    published on chain as the slot's `MarketSnapshot` when the slot opened,
+//!    with its hash. The hash goes into the submit-proposal body, so a
+//!    proposal computed on any other market is rejected. A slot without a
+//!    published snapshot yet (HTTP 409) is retried on the next round.
+//!
+//! Proposal signing:
+//!  - With POBA_PROPOSER_SURI set (e.g. "//Alice"), each submit-proposal body
+//!    carries an sr25519 signature over `proposal_payload(slot, matches)`, so
+//!    the backend can check it against the key of the claimed proposer_id.
+//!
 //! Finalization lag behavior:
 //!  - Controlled by POBA_FINALIZE_LAG_SLOTS (u64):
 //!      0 (default) → finalize the *current* slot
 //!      1          → finalize slot-1 (gives more time for other proposers)
+//!
+//! Round timing:
+//!  - A round runs on every new best block, after waiting
+//!    POBA_BLOCK_DEBOUNCE_MS (default 0) for newer blocks to coalesce.
+//!  - If no block arrives within POBA_FALLBACK_SEC (default: the runtime's
+//!    block time), a round runs anyway.
+//!  - Either way the slot is read from the runtime's `PobaApi::current_slot`
+//!    at the best block (its timestamp in slot windows); a round is skipped
+//!    if that call fails.
+//!
+//! Logging:
+//!  - POBA_LOG_JSON=1 logs proposal/submit/finalize outcomes as one-line JSON
+//!    objects instead of the default human-readable lines.
+//!
+//! Dry run:
+//!  - POBA_DRY_RUN=1 runs the full compute path and logs the proposal, but
+//!    never calls submit-proposal or finalize-slot. The per-pair cost
+//!    breakdown of the chosen matches is logged at debug level.
+//!
+//! Scoring:
+//!  - A pair scores POBA_BASE_SCORE minus its distance/price penalty, floored
+//!    at 0. Prices above POBA_MAX_PRICE_CENTS (default 10_000_000) count as
+//!    that cap in the penalty; the agreed price itself is not changed.
+//!
+//! Market size:
+//!  - At most POBA_MAX_MARKET_REQUESTS / POBA_MAX_MARKET_OFFERS (default 1000
+//!    each) are matched per round. Larger markets are logged and cut to their
+//!    most urgent items: earliest window start, then window end, then uuid.
+//!  - Requests with a kind other than 0 (package) / 1 (passenger) and offers
+//!    with `types_mask` bits beyond those two are dropped with a warning.
+//!
+//! Objective:
+//!  - POBA_OBJECTIVE=min_cost (default) minimizes total cost, counting
+//!    POBA_SKIP_COST per unserved request; `max_coverage` first serves as many
+//!    requests as possible and only then minimizes cost.
+//!
+//! Pricing:
+//!  - POBA_PRICE_POLICY picks the agreed price of a pair: `midpoint` (default),
+//!    `offer_min`, `request_max` or `weighted`, which blends from the offer
+//!    minimum (POBA_PRICE_WEIGHT=0) to the request maximum (1); default 0.5.
+//!
+//! Health:
+//!  - POBA_HEALTH_ADDR (e.g. "0.0.0.0:9955") serves `/healthz` (process alive)
+//!    and `/readyz` (last backend fetch within POBA_READY_MAX_AGE_SEC, default
+//!    30, and no round running for longer than that). Unset → no server.
 
 use crate::service::FullClient;
-use std::{sync::Arc, time::Duration};
-use sc_client_api::HeaderBackend;
+use futures::{Stream, StreamExt};
+use std::{
+    future::Future,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use sc_client_api::{BlockchainEvents, HeaderBackend};
+use sp_runtime::traits::Header as _;
 use reqwest::Client as Http;
 use serde::{Deserialize, Serialize};
 use sp_api::ProvideRuntimeApi;
+use sp_core::{sr25519, Pair};
 use sp_runtime::traits::SaturatedConversion; // for best_number -> u64
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use log;
+use pallet_poba_runtime_api::PobaApi;
+use solochain_template_runtime::MILLI_SECS_PER_BLOCK;
 
 // ---------------------------- Market types ----------------------------
 
@@ -47,6 +114,23 @@ pub struct MarketOffer {
     pub window_start: u64,
     pub window_end: u64,
     pub types_mask: u32, // bit 0 = package, bit 1 = passenger
+    /// How many requests this offer can serve in one assignment.
+    #[serde(default = "default_capacity")]
+    pub capacity: u8,
+}
+
+fn default_capacity() -> u8 {
+    1
+}
+
+/// The market of a slot as published in its on-chain `MarketSnapshot`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SlotMarket {
+    pub slot: u64,
+    /// "0x"-hex hash stored in the slot's `MarketSnapshot`.
+    pub snapshot_hash: String,
+    pub requests: Vec<MarketRequest>,
+    pub offers: Vec<MarketOffer>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -62,15 +146,140 @@ pub struct SubmitProposalBody {
     pub slot: u64,
     pub total_score: i64,
     pub matches: Vec<MatchItem>,
+    /// `SlotMarket::snapshot_hash` of the market the proposal was computed on.
+    pub snapshot_hash: String,
+    /// `idempotency_key(slot, matches)`; lets the backend skip a retried
+    /// call it already submitted.
+    pub idempotency_key: String,
+    /// "0x"-hex sr25519 signature over `proposal_payload(slot, matches)` by
+    /// the proposer's key; omitted when POBA_PROPOSER_SURI is unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
 }
 
 // ---------------------------- Helpers ----------------------------
 
-/// Derive PoBA slot from the node's best block number.
-/// This keeps slot numbers small and easy to correlate with chain state.
-fn current_slot_from_client(client: &FullClient) -> u64 {
-    let info = client.info();
-    info.best_number.saturated_into::<u64>()
+/// Canonical bytes of a proposal: the slot and the sorted
+/// `request:offer:price:score` items, joined by '|'. The backend rebuilds the
+/// same string, so keep both sides in sync.
+fn proposal_payload(slot: u64, matches: &[MatchItem]) -> String {
+    let mut items: Vec<String> = matches
+        .iter()
+        .map(|m| {
+            let (price, score) = (m.agreed_price_cents, m.partial_score);
+            format!("{}:{}:{price}:{score}", m.request_uuid, m.offer_uuid)
+        })
+        .collect();
+    items.sort();
+    format!("{slot}|{}", items.join("|"))
+}
+
+/// Hex blake2-256 of `proposal_payload`: equal for the same proposal whatever
+/// the match order.
+fn idempotency_key(slot: u64, matches: &[MatchItem]) -> String {
+    let hash = sp_core::hashing::blake2_256(proposal_payload(slot, matches).as_bytes());
+    hash.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// "0x"-hex signature of `proposal_payload` by `signer`.
+fn sign_proposal(signer: &sr25519::Pair, slot: u64, matches: &[MatchItem]) -> String {
+    let sig = signer.sign(proposal_payload(slot, matches).as_bytes());
+    let bytes: &[u8] = sig.as_ref();
+    let hex: String = bytes.iter().map(|b| format!("{b:02x}")).collect();
+    format!("0x{hex}")
+}
+
+/// Current PoBA slot at the best block, as the runtime derives it
+/// (`PobaApi::current_slot`); `None` if the call fails.
+fn current_slot_from_runtime(client: &FullClient) -> Option<u64> {
+    client
+        .runtime_api()
+        .current_slot(client.info().best_hash)
+        .map_err(|e| log::warn!("PoBA worker: current_slot runtime call failed: {e}"))
+        .ok()
+}
+
+/// What started a matching round.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Trigger {
+    /// A new best block; carries the highest number seen while debouncing.
+    Block(u64),
+    /// No block arrived within the fallback period.
+    Timer,
+    /// The block stream ended.
+    Closed,
+}
+
+/// Wait for the next round: the first new best block (then keep taking blocks
+/// for `debounce` and report the latest), or `fallback` elapsing without any.
+async fn next_trigger<S>(blocks: &mut S, fallback: Duration, debounce: Duration) -> Trigger
+where
+    S: Stream<Item = u64> + Unpin,
+{
+    let mut latest = match tokio::time::timeout(fallback, blocks.next()).await {
+        Ok(Some(n)) => n,
+        Ok(None) => return Trigger::Closed,
+        Err(_) => return Trigger::Timer,
+    };
+
+    if !debounce.is_zero() {
+        let deadline = tokio::time::Instant::now() + debounce;
+        while let Ok(Some(n)) = tokio::time::timeout_at(deadline, blocks.next()).await {
+            latest = latest.max(n);
+        }
+    }
+
+    Trigger::Block(latest)
+}
+
+/// Outcome of a round step, logged by [`RoundEvent::emit`].
+///
+/// JSON field names are stable; dashboards depend on them.
+#[derive(Debug, Clone, Serialize)]
+struct RoundEvent<'a> {
+    /// `proposal_computed`, `submit` or `finalize`.
+    event: &'static str,
+    slot: u64,
+    total_score: Option<i64>,
+    matches: Option<usize>,
+    proposer_id: &'a str,
+    /// `ok`, `empty`, `http_<code>` or `error`.
+    status: String,
+}
+
+impl RoundEvent<'_> {
+    fn json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+
+    fn human(&self) -> String {
+        let mut line = format!(
+            "PoBA worker (proposer_id={}): {} slot={} status={}",
+            self.proposer_id, self.event, self.slot, self.status
+        );
+        if let Some(score) = self.total_score {
+            line.push_str(&format!(" total_score={score}"));
+        }
+        if let Some(n) = self.matches {
+            line.push_str(&format!(" matches={n}"));
+        }
+        line
+    }
+
+    /// Log at `level`, as JSON when `json` is set (POBA_LOG_JSON).
+    fn emit(&self, json: bool, level: log::Level) {
+        let line = if json { self.json() } else { self.human() };
+        log::log!(level, "{line}");
+    }
+}
+
+/// HTTP status as a `status` value.
+fn http_status(status: reqwest::StatusCode) -> String {
+    if status.is_success() {
+        "ok".to_string()
+    } else {
+        format!("http_{}", status.as_u16())
+    }
 }
 
 /// Helper to append `?proposer_id=...` to a base URL.
@@ -90,6 +299,15 @@ fn env_i64(name: &str, default: i64) -> i64 {
         .unwrap_or(default)
 }
 
+/// Fallback timer: `secs` when positive, otherwise one runtime block time.
+fn fallback_period(secs: i64) -> Duration {
+    if secs > 0 {
+        Duration::from_secs(secs as u64)
+    } else {
+        Duration::from_millis(MILLI_SECS_PER_BLOCK)
+    }
+}
+
 fn env_f64(name: &str, default: f64) -> f64 {
     std::env::var(name)
         .ok()
@@ -116,7 +334,222 @@ fn kind_to_bit(kind: u8) -> u32 {
     }
 }
 
+/// Every `types_mask` bit a known kind maps to.
+const KNOWN_TYPES_MASK: u32 = 0b11;
+
+/// Drop requests with an unknown kind and offers with unknown `types_mask`
+/// bits, logging each, so they are rejected loudly instead of matched by
+/// accident. The backend's market schema enforces the same bounds.
+fn drop_invalid_kinds(
+    slot: u64,
+    requests: &mut Vec<MarketRequest>,
+    offers: &mut Vec<MarketOffer>,
+) {
+    requests.retain(|r| {
+        let ok = kind_to_bit(r.kind) != 0;
+        if !ok {
+            log::warn!(
+                "PoBA worker: slot={} dropping request {}: unknown kind {}",
+                slot,
+                r.uuid_16,
+                r.kind
+            );
+        }
+        ok
+    });
+    offers.retain(|o| {
+        let ok = o.types_mask & !KNOWN_TYPES_MASK == 0;
+        if !ok {
+            log::warn!(
+                "PoBA worker: slot={} dropping offer {}: unknown types_mask bits {:#b}",
+                slot,
+                o.uuid_16,
+                o.types_mask & !KNOWN_TYPES_MASK
+            );
+        }
+        ok
+    });
+}
+
+/// Distance/price weights used to turn a feasible pair into a penalty.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct KindWeights {
+    alpha_per_km: f64,
+    beta_per_cent: f64,
+}
+
+/// Scoring weights per request kind (package / passenger).
+///
+/// Per-kind env vars (`POBA_ALPHA_PER_KM_PACKAGE`, `POBA_BETA_PER_CENT_PASSENGER`, ...)
+/// override the global `POBA_ALPHA_PER_KM` / `POBA_BETA_PER_CENT` when set.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct ScoringWeights {
+    package: KindWeights,
+    passenger: KindWeights,
+    /// Used for unknown kinds.
+    default: KindWeights,
+}
+
+impl ScoringWeights {
+    fn from_env() -> Self {
+        let default = KindWeights {
+            alpha_per_km: env_f64("POBA_ALPHA_PER_KM", 1000.0),
+            beta_per_cent: env_f64("POBA_BETA_PER_CENT", 1.0),
+        };
+        let for_suffix = |suffix: &str| KindWeights {
+            alpha_per_km: env_f64(&format!("POBA_ALPHA_PER_KM_{suffix}"), default.alpha_per_km),
+            beta_per_cent: env_f64(&format!("POBA_BETA_PER_CENT_{suffix}"), default.beta_per_cent),
+        };
+        Self { package: for_suffix("PACKAGE"), passenger: for_suffix("PASSENGER"), default }
+    }
+
+    fn for_kind(&self, kind: u8) -> KindWeights {
+        match kind {
+            0 => self.package,
+            1 => self.passenger,
+            _ => self.default,
+        }
+    }
+}
+
+/// What the assignment search optimizes (`POBA_OBJECTIVE`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Objective {
+    /// Lowest total cost, with `skip_cost` per unserved request.
+    MinCost,
+    /// Most served requests; cost only breaks ties.
+    MaxCoverage,
+}
+
+impl Objective {
+    fn from_env() -> Self {
+        let name = std::env::var("POBA_OBJECTIVE").unwrap_or_default();
+        Self::parse(&name).unwrap_or_else(|| {
+            log::warn!("PoBA worker: unknown POBA_OBJECTIVE={name:?}, using min_cost");
+            Self::MinCost
+        })
+    }
+
+    /// Objective named `name` (empty means the default).
+    fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "" | "min_cost" => Some(Self::MinCost),
+            "max_coverage" => Some(Self::MaxCoverage),
+            _ => None,
+        }
+    }
+
+    /// Search key after skipping one more request. Keys are
+    /// `(skipped requests, cost)` and compare lexicographically; under
+    /// `MinCost` the first part stays 0.
+    fn skip(self, (skipped, cost): (usize, i64), skip_cost: i64) -> (usize, i64) {
+        match self {
+            Self::MinCost => (skipped, cost.saturating_add(skip_cost)),
+            Self::MaxCoverage => (skipped + 1, cost),
+        }
+    }
+}
+
+/// How a feasible pair's agreed price is picked between the offer's minimum
+/// and the request's maximum (`POBA_PRICE_POLICY`).
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum PricePolicy {
+    /// Halfway between both limits.
+    Midpoint,
+    /// The offer's minimum, in favour of requesters.
+    OfferMin,
+    /// The request's maximum, in favour of couriers.
+    RequestMax,
+    /// `offer_min + weight * (request_max - offer_min)`, weight in `[0, 1]`.
+    Weighted(f64),
+}
+
+impl PricePolicy {
+    fn from_env() -> Self {
+        let name = std::env::var("POBA_PRICE_POLICY").unwrap_or_default();
+        Self::parse(&name, env_f64("POBA_PRICE_WEIGHT", 0.5)).unwrap_or_else(|| {
+            log::warn!("PoBA worker: unknown POBA_PRICE_POLICY={name:?}, using midpoint");
+            Self::Midpoint
+        })
+    }
+
+    /// Policy named `name` (empty means the default); `weight` is only used
+    /// by `weighted` and is clamped to `[0, 1]`.
+    fn parse(name: &str, weight: f64) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "" | "midpoint" => Some(Self::Midpoint),
+            "offer_min" => Some(Self::OfferMin),
+            "request_max" => Some(Self::RequestMax),
+            "weighted" => Some(Self::Weighted(weight.clamp(0.0, 1.0))),
+            _ => None,
+        }
+    }
+
+    /// Agreed price of a feasible pair. Requests without a maximum (0) pay
+    /// the offer's minimum under every policy.
+    fn agreed_cents(&self, off_min_cents: i64, req_max_cents: i64) -> i64 {
+        if req_max_cents <= 0 {
+            return off_min_cents;
+        }
+        match *self {
+            Self::Midpoint => (off_min_cents + req_max_cents) / 2,
+            Self::OfferMin => off_min_cents,
+            Self::RequestMax => req_max_cents,
+            Self::Weighted(weight) =>
+                off_min_cents + ((req_max_cents - off_min_cents) as f64 * weight).round() as i64,
+        }
+    }
+}
+
+/// Penalty and score of a feasible pair.
+///
+/// The penalty is saturated into `[0, i64::MAX]` (NaN counts as 0) and the
+/// score stays within `[0, base_score]` whatever the inputs.
+fn pair_score(
+    w: KindWeights,
+    d_total_km: f64,
+    p_cents: i64,
+    base_score: i64,
+    max_price_cents: i64,
+) -> (i64, i64) {
+    let priced = p_cents.clamp(0, max_price_cents.max(0)) as f64;
+    let raw = (w.alpha_per_km * d_total_km + w.beta_per_cent * priced).round();
+    let penalty = if raw.is_nan() || raw <= 0.0 {
+        0
+    } else if raw >= i64::MAX as f64 {
+        i64::MAX
+    } else {
+        raw as i64
+    };
+    let base_score = base_score.max(0);
+    (penalty, base_score.saturating_sub(penalty).max(0))
+}
+
+/// Sum the partial scores, dropping (and logging) any match that would
+/// overflow the total, so `total_score` always equals the sum of the kept
+/// matches.
+fn checked_total(slot: u64, matches: Vec<MatchItem>) -> (i64, Vec<MatchItem>) {
+    let mut total: i64 = 0;
+    let mut kept = Vec::with_capacity(matches.len());
+    for item in matches {
+        match total.checked_add(item.partial_score) {
+            Some(sum) => {
+                total = sum;
+                kept.push(item);
+            }
+            None => log::warn!(
+                "build_proposal(local): slot={} dropping request={}: total_score would overflow",
+                slot,
+                item.request_uuid
+            ),
+        }
+    }
+    (total, kept)
+}
+
 /// Haversine distance in KM between two geo points (micro-degrees).
+/// Reference for [`GeoPoint::distance_km`], which the matcher uses.
+#[cfg(test)]
 fn haversine_km(lat1_e6: i32, lon1_e6: i32, lat2_e6: i32, lon2_e6: i32) -> f64 {
     let to_rad = |x: i32| (x as f64 / 1_000_000.0) * std::f64::consts::PI / 180.0;
 
@@ -133,6 +566,33 @@ fn haversine_km(lat1_e6: i32, lon1_e6: i32, lat2_e6: i32, lon2_e6: i32) -> f64 {
     6371.0 * c
 }
 
+/// A geo point converted to radians once, so each pair only pays for the
+/// trigonometry that depends on both ends.
+#[derive(Debug, Clone, Copy)]
+struct GeoPoint {
+    lat: f64,
+    lon: f64,
+    cos_lat: f64,
+}
+
+impl GeoPoint {
+    fn from_e6(lat_e6: i32, lon_e6: i32) -> Self {
+        let to_rad = |x: i32| (x as f64 / 1_000_000.0) * std::f64::consts::PI / 180.0;
+        let lat = to_rad(lat_e6);
+        Self { lat, lon: to_rad(lon_e6), cos_lat: lat.cos() }
+    }
+
+    /// Haversine distance in KM.
+    fn distance_km(&self, other: &GeoPoint) -> f64 {
+        let dlat = other.lat - self.lat;
+        let dlon = other.lon - self.lon;
+        let a = (dlat / 2.0).sin().powi(2)
+            + self.cos_lat * other.cos_lat * (dlon / 2.0).sin().powi(2);
+        let c = 2.0 * a.sqrt().atan2((1.0 - a).sqrt());
+        6371.0 * c
+    }
+}
+
 /// Time-interval overlap check, as in the Python version.
 fn intervals_overlap_ms(
     a_start: u64,
@@ -160,12 +620,80 @@ fn intervals_overlap_ms(
 
 // ------------------------- Core matching logic -------------------------
 
+/// Market size fed to the matcher (`POBA_MAX_MARKET_REQUESTS`,
+/// `POBA_MAX_MARKET_OFFERS`).
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct MarketCaps {
+    requests: usize,
+    offers: usize,
+}
+
+impl MarketCaps {
+    fn from_env() -> Self {
+        Self {
+            requests: env_i64("POBA_MAX_MARKET_REQUESTS", 1_000).max(1) as usize,
+            offers: env_i64("POBA_MAX_MARKET_OFFERS", 1_000).max(1) as usize,
+        }
+    }
+
+    /// Cut an over-cap market down to its most urgent items, deterministically.
+    fn apply(&self, slot: u64, requests: &mut Vec<MarketRequest>, offers: &mut Vec<MarketOffer>) {
+        if requests.len() > self.requests {
+            log::warn!(
+                "PoBA worker: slot={} market has {} requests, matching the {} most urgent",
+                slot,
+                requests.len(),
+                self.requests
+            );
+            requests.sort_by(|a, b| {
+                (a.window_start, a.window_end, &a.uuid_16)
+                    .cmp(&(b.window_start, b.window_end, &b.uuid_16))
+            });
+            requests.truncate(self.requests);
+        }
+        if offers.len() > self.offers {
+            log::warn!(
+                "PoBA worker: slot={} market has {} offers, matching the {} most urgent",
+                slot,
+                offers.len(),
+                self.offers
+            );
+            offers.sort_by(|a, b| {
+                (a.window_start, a.window_end, &a.uuid_16)
+                    .cmp(&(b.window_start, b.window_end, &b.uuid_16))
+            });
+            offers.truncate(self.offers);
+        }
+    }
+}
+
 /// Compute assignment for given market (requests + offers) using a branch-and-bound
 /// search with the same cost/score model as in the Python version.
 fn compute_matches_for_market(
     slot: u64,
     requests: &[MarketRequest],
     offers: &[MarketOffer],
+) -> (i64, Vec<MatchItem>) {
+    compute_matches_with_weights(slot, requests, offers, &ScoringWeights::from_env())
+}
+
+/// Same as [`compute_matches_for_market`], with explicit per-kind scoring weights.
+fn compute_matches_with_weights(
+    slot: u64,
+    requests: &[MarketRequest],
+    offers: &[MarketOffer],
+    weights: &ScoringWeights,
+) -> (i64, Vec<MatchItem>) {
+    compute_matches_with(slot, requests, offers, weights, Objective::from_env())
+}
+
+/// Same as [`compute_matches_with_weights`], with an explicit search objective.
+fn compute_matches_with(
+    slot: u64,
+    requests: &[MarketRequest],
+    offers: &[MarketOffer],
+    weights: &ScoringWeights,
+    objective: Objective,
 ) -> (i64, Vec<MatchItem>) {
     let n = requests.len();
     let m = offers.len();
@@ -182,9 +710,8 @@ fn compute_matches_for_market(
 
     // ---------------- Scoring parameters ----------------
     let base_score: i64 = env_i64("POBA_BASE_SCORE", 1_000_000);
-    let alpha_per_km: f64 = env_f64("POBA_ALPHA_PER_KM", 1000.0);
-    let beta_per_cent: f64 = env_f64("POBA_BETA_PER_CENT", 1.0);
     let skip_cost: i64 = env_i64("POBA_SKIP_COST", 100_000_000);
+    let max_price_cents: i64 = env_i64("POBA_MAX_PRICE_CENTS", 10_000_000);
 
     let max_start_km_env: f64 = env_f64("POBA_MAX_START_KM", 0.0);
     let max_end_km_env: f64 = env_f64("POBA_MAX_END_KM", 0.0);
@@ -212,6 +739,8 @@ fn compute_matches_for_market(
     let early_slack_ms: i64 = (env_f64("POBA_EARLY_SLACK_SEC", 0.0) * 1000.0) as i64;
     let late_slack_ms: i64 = (env_f64("POBA_LATE_SLACK_SEC", 0.0) * 1000.0) as i64;
 
+    let price_policy = PricePolicy::from_env();
+
     // ---------------- Debug counters ----------------
     #[derive(Debug)]
     struct DebugCounts {
@@ -237,9 +766,21 @@ fn compute_matches_for_market(
     let mut cost: Vec<Vec<i64>> = vec![vec![inf; m]; n];
     let mut partial_score: Vec<Vec<i64>> = vec![vec![0; m]; n];
     let mut price_agreed: Vec<Vec<i64>> = vec![vec![0; m]; n];
+    let mut distance_km: Vec<Vec<f64>> = vec![vec![0.0; m]; n];
+
+    // Endpoints in radians, computed once per market instead of once per pair.
+    let req_points: Vec<(GeoPoint, GeoPoint)> = requests
+        .iter()
+        .map(|r| (GeoPoint::from_e6(r.from_lat, r.from_lon), GeoPoint::from_e6(r.to_lat, r.to_lon)))
+        .collect();
+    let off_points: Vec<(GeoPoint, GeoPoint)> = offers
+        .iter()
+        .map(|o| (GeoPoint::from_e6(o.from_lat, o.from_lon), GeoPoint::from_e6(o.to_lat, o.to_lon)))
+        .collect();
 
     for (i, r) in requests.iter().enumerate() {
         let r_bit = kind_to_bit(r.kind);
+        let w = weights.for_kind(r.kind);
 
         for (j, o) in offers.iter().enumerate() {
             debug.total_pairs += 1;
@@ -303,8 +844,8 @@ fn compute_matches_for_market(
                 (0.0, 0.0)
             } else {
                 (
-                    haversine_km(r_fl, r_fn, o_fl, o_fn),
-                    haversine_km(r_tl, r_tn, o_tl, o_tn),
+                    req_points[i].0.distance_km(&off_points[j].0),
+                    req_points[i].1.distance_km(&off_points[j].1),
                 )
             };
 
@@ -329,45 +870,46 @@ fn compute_matches_for_market(
                 }
             }
 
-            // 4) Agreed price policy (midpoint or min_price)
-            let agreed_cents = if req_max_cents > 0 {
-                (off_min_cents + req_max_cents) / 2
-            } else {
-                off_min_cents
-            };
+            // 4) Agreed price policy
+            let agreed_cents = price_policy.agreed_cents(off_min_cents, req_max_cents);
             let p_cents = std::cmp::max(1, agreed_cents);
 
             // 5) Scoring / penalty
-            let penalty =
-                (alpha_per_km * d_total + beta_per_cent * p_cents as f64).round() as i64;
-            let score = std::cmp::max(0, base_score - penalty);
+            let (penalty, score) = pair_score(w, d_total, p_cents, base_score, max_price_cents);
 
-            cost[i][j] = penalty;
+            // Below `inf`, so the pair stays feasible whatever its penalty.
+            cost[i][j] = penalty.min(inf - 1);
             partial_score[i][j] = score;
             price_agreed[i][j] = p_cents;
+            distance_km[i][j] = d_total;
             debug.feasible_pairs += 1;
         }
     }
 
     // ---------------- Branch & Bound search (IDA*-like) ----------------
     //
-    // state: index i (request index), used_mask (offers already taken), acc_cost.
-    // Only the cheapest combination (with skip_cost) is kept.
+    // state: index i (request index), remaining (capacity left per offer),
+    // acc = (skipped requests, cost) as keyed by `objective`.
+    // Only the best combination by that key is kept.
 
-    let mut best_cost: i64 = inf;
+    // A capacity of 0 from an older backend means the single-request default.
+    let mut remaining: Vec<u8> = offers.iter().map(|o| o.capacity.max(1)).collect();
+
+    let mut best_cost: (usize, i64) = (usize::MAX, inf);
     let mut best_assign: Vec<Option<usize>> = vec![None; n];
     let mut current_assign: Vec<Option<usize>> = vec![None; n];
 
     fn dfs(
         i: usize,
-        used_mask: u64,
-        acc_cost: i64,
+        remaining: &mut [u8],
+        acc_cost: (usize, i64),
         n: usize,
         m: usize,
         cost: &Vec<Vec<i64>>,
         skip_cost: i64,
+        objective: Objective,
         inf: i64,
-        best_cost: &mut i64,
+        best_cost: &mut (usize, i64),
         current_assign: &mut Vec<Option<usize>>,
         best_assign: &mut Vec<Option<usize>>,
     ) {
@@ -386,47 +928,51 @@ fn compute_matches_for_market(
 
         // 1) Try real matches first (prefer them over skip when possible)
         for j in 0..m {
-            if ((used_mask >> j) & 1) == 1 {
+            if remaining[j] == 0 {
                 continue;
             }
             let c_ij = cost[i][j];
             if c_ij >= inf {
                 continue;
             }
-            let new_cost = acc_cost + c_ij;
+            let new_cost = (acc_cost.0, acc_cost.1.saturating_add(c_ij));
             if new_cost >= *best_cost {
                 continue;
             }
 
             current_assign[i] = Some(j);
+            remaining[j] -= 1;
             dfs(
                 i + 1,
-                used_mask | (1 << j),
+                remaining,
                 new_cost,
                 n,
                 m,
                 cost,
                 skip_cost,
+                objective,
                 inf,
                 best_cost,
                 current_assign,
                 best_assign,
             );
+            remaining[j] += 1;
             current_assign[i] = None;
         }
 
         // 2) Option to skip this request
-        let new_cost = acc_cost + skip_cost;
+        let new_cost = objective.skip(acc_cost, skip_cost);
         if new_cost < *best_cost {
             current_assign[i] = None;
             dfs(
                 i + 1,
-                used_mask,
+                remaining,
                 new_cost,
                 n,
                 m,
                 cost,
                 skip_cost,
+                objective,
                 inf,
                 best_cost,
                 current_assign,
@@ -437,19 +983,20 @@ fn compute_matches_for_market(
 
     dfs(
         0,
-        0,
-        0,
+        &mut remaining,
+        (0, 0),
         n,
         m,
         &cost,
         skip_cost,
+        objective,
         inf,
         &mut best_cost,
         &mut current_assign,
         &mut best_assign,
     );
 
-    if best_cost >= inf {
+    if best_cost.1 >= inf {
         log::info!(
             "build_proposal(local): slot={} no feasible assignment debug={:?}",
             slot,
@@ -460,7 +1007,6 @@ fn compute_matches_for_market(
 
     // ---------------- Rebuild matches + total_score ----------------
     let mut matches: Vec<MatchItem> = Vec::new();
-    let mut total_score: i64 = 0;
 
     for i in 0..n {
         if let Some(j) = best_assign[i] {
@@ -469,6 +1015,16 @@ fn compute_matches_for_market(
 
             let agreed_cents = price_agreed[i][j];
             let sc = partial_score[i][j];
+            log::debug!(
+                "build_proposal(local): slot={} pair request={} offer={} km={:.3} price_cents={} penalty={} score={}",
+                slot,
+                r.uuid_16,
+                o.uuid_16,
+                distance_km[i][j],
+                agreed_cents,
+                cost[i][j],
+                sc,
+            );
 
             matches.push(MatchItem {
                 request_uuid: r.uuid_16.clone(),
@@ -476,18 +1032,18 @@ fn compute_matches_for_market(
                 agreed_price_cents: agreed_cents as u32,
                 partial_score: sc,
             });
-
-            total_score += sc;
         }
     }
+    let (total_score, matches) = checked_total(slot, matches);
 
     log::info!(
         "build_proposal(local): slot={} total_score={} matches={} \
-         (skip_cost={}, require_time_overlap={}, min_overlap_ms={}, \
+         (objective={:?}, skip_cost={}, require_time_overlap={}, min_overlap_ms={}, \
          early_slack_ms={}, late_slack_ms={}, debug={:?})",
         slot,
         total_score,
         matches.len(),
+        objective,
         skip_cost,
         require_time_overlap,
         min_overlap_ms,
@@ -499,6 +1055,88 @@ fn compute_matches_for_market(
     (total_score, matches)
 }
 
+// ------------------------------ Health ------------------------------
+
+#[derive(Debug, Default)]
+struct HealthState {
+    last_fetch_ok: Option<Instant>,
+    /// Set while a round is running.
+    round_started: Option<Instant>,
+}
+
+/// Progress markers the round loop updates and the health server reads.
+#[derive(Debug, Clone, Default)]
+struct Health(Arc<Mutex<HealthState>>);
+
+impl Health {
+    fn fetch_ok(&self, now: Instant) {
+        self.0.lock().unwrap_or_else(|e| e.into_inner()).last_fetch_ok = Some(now);
+    }
+
+    fn round_started(&self, now: Instant) {
+        self.0.lock().unwrap_or_else(|e| e.into_inner()).round_started = Some(now);
+    }
+
+    fn round_finished(&self) {
+        self.0.lock().unwrap_or_else(|e| e.into_inner()).round_started = None;
+    }
+
+    /// Backend fetched within `max_age` and no round stuck for longer.
+    fn is_ready(&self, now: Instant, max_age: Duration) -> bool {
+        let state = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        let fresh = state
+            .last_fetch_ok
+            .is_some_and(|t| now.saturating_duration_since(t) <= max_age);
+        let stuck = state
+            .round_started
+            .is_some_and(|t| now.saturating_duration_since(t) > max_age);
+        fresh && !stuck
+    }
+}
+
+/// Status line and body for one health request (`GET /healthz HTTP/1.1 ...`).
+fn health_response(
+    request: &[u8],
+    health: &Health,
+    now: Instant,
+    max_age: Duration,
+) -> (&'static str, &'static str) {
+    let path = std::str::from_utf8(request)
+        .ok()
+        .and_then(|r| r.split_whitespace().nth(1))
+        .unwrap_or("");
+    match path {
+        "/healthz" => ("200 OK", "ok"),
+        "/readyz" if health.is_ready(now, max_age) => ("200 OK", "ready"),
+        "/readyz" => ("503 Service Unavailable", "not ready"),
+        _ => ("404 Not Found", "not found"),
+    }
+}
+
+/// Answer health probes on `listener` until the task is dropped.
+async fn serve_health(listener: tokio::net::TcpListener, health: Health, max_age: Duration) {
+    loop {
+        let mut sock = match listener.accept().await {
+            Ok((sock, _)) => sock,
+            Err(e) => {
+                log::debug!("PoBA worker: health accept failed: {e}");
+                continue;
+            }
+        };
+        let health = health.clone();
+        tokio::spawn(async move {
+            let mut buf = [0u8; 1024];
+            let n = sock.read(&mut buf).await.unwrap_or(0);
+            let (status, body) = health_response(&buf[..n], &health, Instant::now(), max_age);
+            let resp = format!(
+                "HTTP/1.1 {status}\r\nContent-Type: text/plain\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            );
+            let _ = sock.write_all(resp.as_bytes()).await;
+        });
+    }
+}
+
 // ------------------------------ Worker loop ------------------------------
 
 pub async fn run(
@@ -508,8 +1146,12 @@ pub async fn run(
     // Currently not using the keystore, but we keep the parameter for future use.
     _keystore: impl Send + Sync + 'static,
     backend_url: String,
+    // Resolves when the node shuts down; the loop exits at the next round boundary.
+    shutdown: impl Future<Output = ()> + Send,
 ) {
-    let http = Http::new();
+    // Bound every backend call so an in-flight round cannot hang shutdown.
+    let http_timeout = Duration::from_secs(env_i64("POBA_HTTP_TIMEOUT_SEC", 10).max(1) as u64);
+    let http = Http::builder().timeout(http_timeout).build().unwrap_or_else(|_| Http::new());
 
     // ROLE: proposer / finalizer
     let role = std::env::var("POBA_ROLE").unwrap_or_else(|_| "proposer".to_string());
@@ -519,63 +1161,243 @@ pub async fn run(
     let proposer_id =
         std::env::var("POBA_PROPOSER_ID").unwrap_or_else(|_| "node".to_string());
 
+    // Optional key that signs each proposal on behalf of proposer_id.
+    let signer = match std::env::var("POBA_PROPOSER_SURI") {
+        Ok(suri) => match sr25519::Pair::from_string(&suri, None) {
+            Ok(pair) => Some(pair),
+            Err(e) => {
+                log::warn!(
+                    "PoBA worker: invalid POBA_PROPOSER_SURI ({e:?}); proposals go unsigned"
+                );
+                None
+            },
+        },
+        Err(_) => None,
+    };
+
+    let dry_run = env_bool("POBA_DRY_RUN", false);
+
     log::info!(
-        "PoBA worker started with role={} proposer_id={} backend_url={}",
+        "PoBA worker started with role={} proposer_id={} backend_url={}{}",
         role,
         proposer_id,
-        backend_url
+        backend_url,
+        if dry_run { " (dry run: nothing is submitted)" } else { "" }
     );
 
-    // Last slot we attempted to finalize (to avoid hammering the same slot)
-    let mut last_finalized_slot_local: u64 = 0;
+    // Rounds follow new best blocks; the fallback timer covers stalls.
+    let fallback = fallback_period(env_i64("POBA_FALLBACK_SEC", 0));
+    let debounce = Duration::from_millis(env_i64("POBA_BLOCK_DEBOUNCE_MS", 0).max(0) as u64);
+    let mut blocks = client
+        .import_notification_stream()
+        .filter(|n| futures::future::ready(n.is_new_best))
+        .map(|n| (*n.header.number()).saturated_into::<u64>());
+
+    let health = Health::default();
+    let health_server = match std::env::var("POBA_HEALTH_ADDR") {
+        Ok(addr) if !addr.is_empty() => match tokio::net::TcpListener::bind(&addr).await {
+            Ok(listener) => {
+                let max_age =
+                    Duration::from_secs(env_i64("POBA_READY_MAX_AGE_SEC", 30).max(1) as u64);
+                log::info!("PoBA worker: health endpoints on {}", addr);
+                Some(tokio::spawn(serve_health(listener, health.clone(), max_age)))
+            }
+            Err(e) => {
+                log::warn!("PoBA worker: cannot bind health address {}: {e}", addr);
+                None
+            }
+        },
+        _ => None,
+    };
+
+    let mut worker = Worker {
+        current_slot: Box::new(move || current_slot_from_runtime(&client)),
+        http,
+        backend_url,
+        role,
+        proposer_id,
+        signer,
+        is_finalizer,
+        log_json: env_bool("POBA_LOG_JSON", false),
+        dry_run,
+        health,
+        // Last slot we attempted to finalize (to avoid hammering the same slot)
+        last_finalized_slot_local: 0,
+    };
+
+    let rounds = drive(&mut blocks, fallback, debounce, shutdown, &mut worker).await;
+    if let Some(server) = health_server {
+        server.abort();
+    }
+
+    log::info!(
+        "PoBA worker stopped (role={}, proposer_id={}): rounds={}, last_finalized_local={}",
+        worker.role,
+        worker.proposer_id,
+        rounds,
+        worker.last_finalized_slot_local,
+    );
+}
+
+/// State carried between matching rounds.
+struct Worker {
+    /// Slot at the client's best block, `None` if the runtime can't tell.
+    current_slot: Box<dyn Fn() -> Option<u64> + Send + Sync>,
+    http: Http,
+    backend_url: String,
+    role: String,
+    proposer_id: String,
+    /// Signs submitted proposals when POBA_PROPOSER_SURI is set.
+    signer: Option<sr25519::Pair>,
+    is_finalizer: bool,
+    log_json: bool,
+    dry_run: bool,
+    health: Health,
+    last_finalized_slot_local: u64,
+}
+
+/// One matching round, run for each trigger of [`drive`].
+trait RoundRunner {
+    /// `notified_block` is the triggering block number, `None` on fallback.
+    async fn round(&mut self, notified_block: Option<u64>);
+}
+
+/// Run rounds on every trigger until `shutdown` resolves; returns the number
+/// of rounds run. A started round always runs to completion, shutdown is only
+/// observed while waiting for the next trigger.
+async fn drive<S, R>(
+    blocks: &mut S,
+    fallback: Duration,
+    debounce: Duration,
+    shutdown: impl Future<Output = ()>,
+    runner: &mut R,
+) -> u64
+where
+    S: Stream<Item = u64> + Unpin,
+    R: RoundRunner,
+{
+    let mut shutdown = std::pin::pin!(shutdown);
+    let mut rounds = 0;
 
     loop {
-        // 1) Pull open market from backend
-        let req_url = format!("{}/poba/requests-open", backend_url);
-        let off_url = format!("{}/poba/offers-active", backend_url);
-
-        let (requests, offers): (Vec<MarketRequest>, Vec<MarketOffer>) = match (
-            http.get(&req_url).send().await,
-            http.get(&off_url).send().await,
-        ) {
-            (Ok(r1), Ok(r2)) => {
-                let rs: Vec<MarketRequest> = r1.json().await.unwrap_or_default();
-                let os: Vec<MarketOffer> = r2.json().await.unwrap_or_default();
-                (rs, os)
+        let trigger = tokio::select! {
+            biased;
+            _ = &mut shutdown => break,
+            t = next_trigger(blocks, fallback, debounce) => t,
+        };
+
+        let notified_block = match trigger {
+            Trigger::Block(n) => Some(n),
+            Trigger::Timer => None,
+            Trigger::Closed => {
+                log::warn!("PoBA worker: block import stream closed, using fallback timer only");
+                tokio::select! {
+                    biased;
+                    _ = &mut shutdown => break,
+                    _ = tokio::time::sleep(fallback) => None,
+                }
+            }
+        };
+
+        runner.round(notified_block).await;
+        rounds += 1;
+    }
+
+    rounds
+}
+
+impl RoundRunner for Worker {
+    async fn round(&mut self, notified_block: Option<u64>) {
+        self.health.round_started(Instant::now());
+        self.run_round(notified_block).await;
+        self.health.round_finished();
+    }
+}
+
+impl Worker {
+    async fn run_round(&mut self, notified_block: Option<u64>) {
+        let http = &self.http;
+        let backend_url = self.backend_url.as_str();
+        let role = self.role.as_str();
+        let proposer_id = self.proposer_id.as_str();
+        let is_finalizer = self.is_finalizer;
+        let log_json = self.log_json;
+
+        // 1) Slot from the runtime; the block only triggers the round
+        let trigger = match notified_block {
+            Some(_) => "new best block",
+            None => "fallback timer",
+        };
+        let Some(slot) = (self.current_slot)() else {
+            log::warn!("PoBA worker: no current slot from the runtime, skipping round ({trigger})");
+            return;
+        };
+        log::info!(
+            "PoBA worker (role={}, proposer_id={}): using slot {} ({})",
+            role,
+            proposer_id,
+            slot,
+            trigger
+        );
+
+        // 2) Pull the slot's snapshot market from backend
+        let market_url = format!("{}/poba/market/{}", backend_url, slot);
+        let market = match http.get(&market_url).send().await {
+            Ok(r) if r.status() == reqwest::StatusCode::CONFLICT => {
+                self.health.fetch_ok(Instant::now());
+                log::debug!("PoBA worker: no market snapshot for slot {} yet", slot);
+                return;
             }
-            _ => {
+            Ok(r) if r.status().is_success() => match r.json::<SlotMarket>().await {
+                Ok(market) => {
+                    self.health.fetch_ok(Instant::now());
+                    market
+                }
+                Err(e) => {
+                    log::warn!("PoBA worker: bad market for slot {}: {e}", slot);
+                    return;
+                }
+            },
+            Ok(r) => {
+                log::warn!("PoBA worker: market for slot {} failed: {}", slot, r.status());
+                return;
+            }
+            Err(_) => {
                 log::warn!("PoBA worker: backend not reachable at {}", backend_url);
-                tokio::time::sleep(Duration::from_secs(3)).await;
-                continue;
+                return;
             }
         };
+        let SlotMarket { snapshot_hash, mut requests, mut offers, .. } = market;
 
         if requests.is_empty() || offers.is_empty() {
             // No market – nothing to match
-            tokio::time::sleep(Duration::from_secs(3)).await;
-            continue;
+            return;
         }
 
-        // 2) Compute slot from chain and run local assignment (IDA*-like)
-        let slot = current_slot_from_client(&client);
-        log::info!(
-            "PoBA worker (role={}, proposer_id={}): using slot {} (from best block number)",
-            role,
-            proposer_id,
-            slot
-        );
+        // Local assignment (IDA*-like)
+        drop_invalid_kinds(slot, &mut requests, &mut offers);
+        MarketCaps::from_env().apply(slot, &mut requests, &mut offers);
 
         let (total_score, matches) =
             compute_matches_for_market(slot, &requests, &offers);
 
-        if matches.is_empty() {
-            log::info!(
-                "PoBA worker (role={}, proposer_id={}): no matches for slot {}, skipping submit",
-                role,
-                proposer_id,
-                slot
-            );
-        } else {
+        RoundEvent {
+            event: "proposal_computed",
+            slot,
+            total_score: Some(total_score),
+            matches: Some(matches.len()),
+            proposer_id,
+            // No matches: nothing to submit this round.
+            status: if matches.is_empty() { "empty" } else { "ok" }.to_string(),
+        }
+        .emit(log_json, log::Level::Info);
+
+        if self.dry_run {
+            // Same slot and proposal as a live round; the chain is left alone.
+            return;
+        }
+
+        if !matches.is_empty() {
             // 3) Ask backend to submit signed extrinsic (submit_proposal)
             let submit_url_base = format!("{}/poba/submit-proposal", backend_url);
             let submit_url = with_proposer_id(&submit_url_base, &proposer_id);
@@ -583,37 +1405,37 @@ pub async fn run(
             let body = SubmitProposalBody {
                 slot,
                 total_score,
+                snapshot_hash,
+                idempotency_key: idempotency_key(slot, &matches),
+                signature: self.signer.as_ref().map(|k| sign_proposal(k, slot, &matches)),
                 matches: matches.clone(),
             };
 
-            match http.post(&submit_url).json(&body).send().await {
+            let (status, level) = match http.post(&submit_url).json(&body).send().await {
                 Ok(r) => {
                     let status = r.status();
                     if !status.is_success() {
                         let txt = r.text().await.unwrap_or_default();
-                        log::warn!(
-                            "PoBA worker (proposer_id={}): submit-proposal HTTP status={} body={}",
-                            proposer_id,
-                            status,
-                            txt
-                        );
+                        log::debug!("PoBA worker: submit-proposal body={}", txt);
+                        (http_status(status), log::Level::Warn)
                     } else {
-                        log::info!(
-                            "PoBA worker (role={}, proposer_id={}): submit-proposal HTTP {} for slot {}",
-                            role,
-                            proposer_id,
-                            status,
-                            slot
-                        );
+                        (http_status(status), log::Level::Info)
                     }
                 }
                 Err(e) => {
-                    log::warn!(
-                        "PoBA worker (proposer_id={}): submit-proposal HTTP failed: {e}",
-                        proposer_id
-                    );
+                    log::debug!("PoBA worker: submit-proposal HTTP failed: {e}");
+                    ("error".to_string(), log::Level::Warn)
                 }
+            };
+            RoundEvent {
+                event: "submit",
+                slot,
+                total_score: Some(total_score),
+                matches: Some(matches.len()),
+                proposer_id,
+                status,
             }
+            .emit(log_json, level);
         }
 
         // 4) Optionally ask backend to finalize a slot
@@ -629,7 +1451,7 @@ pub async fn run(
 
             let finalize_slot = slot.saturating_sub(lag_slots);
 
-            if finalize_slot > 0 && finalize_slot > last_finalized_slot_local {
+            if finalize_slot > 0 && finalize_slot > self.last_finalized_slot_local {
                 let finalize_url_base = format!("{}/poba/finalize-slot", backend_url);
                 let finalize_url = with_proposer_id(&finalize_url_base, &proposer_id);
 
@@ -641,7 +1463,7 @@ pub async fn run(
                     lag_slots,
                 );
 
-                match http
+                let (status, level) = match http
                     .post(&finalize_url)
                     .json(&serde_json::json!({ "slot": finalize_slot }))
                     .send()
@@ -650,41 +1472,34 @@ pub async fn run(
                     Ok(resp) => {
                         let status = resp.status();
                         let body_txt = resp.text().await.unwrap_or_default();
-                        if status.is_success() {
-                            log::info!(
-                                "PoBA worker (finalizer, proposer_id={}): finalize-slot OK for slot {} (status={}, body={})",
-                                proposer_id,
-                                finalize_slot,
-                                status,
-                                body_txt
-                            );
-                        } else {
-                            log::warn!(
-                                "PoBA worker (finalizer, proposer_id={}): finalize-slot HTTP {} for slot {} body={}",
-                                proposer_id,
-                                status,
-                                finalize_slot,
-                                body_txt
-                            );
-                        }
-                        last_finalized_slot_local = finalize_slot;
+                        log::debug!("PoBA worker: finalize-slot body={}", body_txt);
+                        self.last_finalized_slot_local = finalize_slot;
+                        let level =
+                            if status.is_success() { log::Level::Info } else { log::Level::Warn };
+                        (http_status(status), level)
                     }
                     Err(e) => {
-                        log::warn!(
-                            "PoBA worker (finalizer, proposer_id={}): finalize-slot request failed for slot {}: {e}",
-                            proposer_id,
-                            finalize_slot
-                        );
-                        // We do NOT update last_finalized_slot_local, so we can retry on next loop.
+                        log::debug!("PoBA worker: finalize-slot request failed: {e}");
+                        // We do NOT update last_finalized_slot_local, so we can retry on the next round.
+                        ("error".to_string(), log::Level::Warn)
                     }
+                };
+                RoundEvent {
+                    event: "finalize",
+                    slot: finalize_slot,
+                    total_score: None,
+                    matches: None,
+                    proposer_id,
+                    status,
                 }
+                .emit(log_json, level);
             } else {
                 log::debug!(
                     "PoBA worker (finalizer, proposer_id={}): no finalize action (slot={}, finalize_slot={}, last_finalized_local={}, lag={})",
                     proposer_id,
                     slot,
                     finalize_slot,
-                    last_finalized_slot_local,
+                    self.last_finalized_slot_local,
                     lag_slots,
                 );
             }
@@ -695,8 +1510,711 @@ pub async fn run(
                 proposer_id
             );
         }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(uuid: &str, kind: u8) -> MarketRequest {
+        MarketRequest {
+            uuid_16: uuid.into(),
+            from_lat: 32_080_000,
+            from_lon: 34_780_000,
+            to_lat: 32_100_000,
+            to_lon: 34_800_000,
+            max_price_cents: 2_000,
+            kind,
+            // Zero bounds fail the default overlap requirement.
+            window_start: 1,
+            window_end: 3_600_000,
+        }
+    }
+
+    fn offer(uuid: &str) -> MarketOffer {
+        MarketOffer {
+            uuid_16: uuid.into(),
+            min_price_cents: 1_000,
+            from_lat: 32_090_000,
+            from_lon: 34_790_000,
+            to_lat: 32_110_000,
+            to_lon: 34_810_000,
+            // Zero bounds fail the default overlap requirement.
+            window_start: 1,
+            window_end: 3_600_000,
+            types_mask: 0b11,
+            capacity: 1,
+        }
+    }
+
+    #[test]
+    fn per_kind_weights_change_score_for_same_geometry() {
+        let weights = ScoringWeights {
+            package: KindWeights { alpha_per_km: 1_000.0, beta_per_cent: 1.0 },
+            passenger: KindWeights { alpha_per_km: 5_000.0, beta_per_cent: 1.0 },
+            default: KindWeights { alpha_per_km: 1_000.0, beta_per_cent: 1.0 },
+        };
+
+        let (_, package) =
+            compute_matches_with_weights(1, &[request("r-pkg", 0)], &[offer("o")], &weights);
+        let (_, passenger) =
+            compute_matches_with_weights(1, &[request("r-pax", 1)], &[offer("o")], &weights);
+
+        assert_eq!(package.len(), 1);
+        assert_eq!(passenger.len(), 1);
+        assert_eq!(package[0].agreed_price_cents, passenger[0].agreed_price_cents);
+        assert!(passenger[0].partial_score < package[0].partial_score);
+    }
+
+    #[test]
+    fn computed_proposal_logs_as_stable_json() {
+        let (total_score, matches) = compute_matches_with_weights(
+            9,
+            &[request("r", 0)],
+            &[offer("o")],
+            &ScoringWeights::from_env(),
+        );
+        let event = RoundEvent {
+            event: "proposal_computed",
+            slot: 9,
+            total_score: Some(total_score),
+            matches: Some(matches.len()),
+            proposer_id: "alice",
+            status: "ok".to_string(),
+        };
+
+        let line = event.json();
+        assert!(!line.contains('\n'));
+        let value: serde_json::Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!({
+                "event": "proposal_computed",
+                "slot": 9,
+                "total_score": total_score,
+                "matches": 1,
+                "proposer_id": "alice",
+                "status": "ok",
+            })
+        );
+    }
+
+    #[test]
+    fn precomputed_points_match_haversine() {
+        let coords = [
+            (32_080_000, 34_780_000),
+            (32_100_000, 34_800_000),
+            (31_768_000, 35_213_000),
+            (-33_868_000, 151_209_000),
+            (0, 179_999_999),
+        ];
+        for &(a_lat, a_lon) in &coords {
+            for &(b_lat, b_lon) in &coords {
+                let direct = haversine_km(a_lat, a_lon, b_lat, b_lon);
+                let cached =
+                    GeoPoint::from_e6(a_lat, a_lon).distance_km(&GeoPoint::from_e6(b_lat, b_lon));
+                assert!((direct - cached).abs() < 1e-9, "{direct} vs {cached}");
+            }
+        }
+    }
+
+    /// Timing of the pair distances of a 20x20 market (400 pairs, two
+    /// distances each), per-pair conversion vs precomputed points. Run with
+    /// `cargo test -p solochain-template-node precomputed_points_timing --
+    /// --ignored --nocapture`.
+    #[test]
+    #[ignore = "timing measurement, not a check"]
+    fn precomputed_points_timing() {
+        use std::hint::black_box;
+
+        const SIDE: i32 = 20;
+        const ROUNDS: u32 = 200;
+        // Endpoints spread over ~50 km around Tel Aviv.
+        let point = |k: i32| (32_000_000 + k * 23_000, 34_700_000 + k * 17_000);
+        let ends: Vec<((i32, i32), (i32, i32))> =
+            (0..SIDE).map(|k| (point(k), point(SIDE - k))).collect();
+
+        let t0 = Instant::now();
+        let mut direct = 0.0;
+        for _ in 0..ROUNDS {
+            for (rf, rt) in &ends {
+                for (of, ot) in &ends {
+                    direct += haversine_km(rf.0, rf.1, of.0, of.1);
+                    direct += haversine_km(rt.0, rt.1, ot.0, ot.1);
+                }
+            }
+        }
+        let direct_time = t0.elapsed();
+
+        let t0 = Instant::now();
+        let mut cached = 0.0;
+        for _ in 0..ROUNDS {
+            let points: Vec<(GeoPoint, GeoPoint)> = ends
+                .iter()
+                .map(|(f, t)| (GeoPoint::from_e6(f.0, f.1), GeoPoint::from_e6(t.0, t.1)))
+                .collect();
+            for (rf, rt) in &points {
+                for (of, ot) in &points {
+                    cached += rf.distance_km(of);
+                    cached += rt.distance_km(ot);
+                }
+            }
+        }
+        let cached_time = t0.elapsed();
+
+        assert!((black_box(direct) - black_box(cached)).abs() < 1e-6);
+        let pairs = (SIDE * SIDE) as u32 * ROUNDS;
+        println!(
+            "{pairs} pairs: per-pair conversion {:?} ({:?}/pair), precomputed {:?} ({:?}/pair)",
+            direct_time,
+            direct_time / pairs,
+            cached_time,
+            cached_time / pairs,
+        );
+    }
+
+    #[test]
+    fn offer_serves_up_to_its_capacity() {
+        let weights = ScoringWeights::from_env();
+        let requests = [request("r1", 0), request("r2", 0)];
+
+        let single = offer("o");
+        let (_, matches) = compute_matches_with_weights(1, &requests, &[single.clone()], &weights);
+        assert_eq!(matches.len(), 1);
+
+        let double = MarketOffer { capacity: 2, ..single };
+        let (total, matches) = compute_matches_with_weights(1, &requests, &[double], &weights);
+        assert_eq!(matches.len(), 2);
+        assert!(matches.iter().all(|m| m.offer_uuid == "o"));
+        assert_eq!(total, matches.iter().map(|m| m.partial_score).sum::<i64>());
+    }
+
+    #[test]
+    fn missing_capacity_defaults_to_one() {
+        let json = r#"{"uuid_16":"o","min_price_cents":1,"from_lat":0,"from_lon":0,
+            "to_lat":0,"to_lon":0,"window_start":0,"window_end":1,"types_mask":1}"#;
+        let offer: MarketOffer = serde_json::from_str(json).unwrap();
+        assert_eq!(offer.capacity, 1);
+    }
+
+    #[tokio::test]
+    async fn debounce_reports_latest_block() {
+        let (tx, mut rx) = futures::channel::mpsc::unbounded();
+        for n in [5, 7, 6] {
+            tx.unbounded_send(n).unwrap();
+        }
+
+        let trigger = next_trigger(&mut rx, Duration::from_secs(5), Duration::from_millis(20)).await;
+        assert_eq!(trigger, Trigger::Block(7));
+    }
+
+    #[tokio::test]
+    async fn no_debounce_triggers_on_each_block() {
+        let (tx, mut rx) = futures::channel::mpsc::unbounded();
+        tx.unbounded_send(5).unwrap();
+        tx.unbounded_send(6).unwrap();
+
+        assert_eq!(next_trigger(&mut rx, Duration::from_secs(5), Duration::ZERO).await, Trigger::Block(5));
+        assert_eq!(next_trigger(&mut rx, Duration::from_secs(5), Duration::ZERO).await, Trigger::Block(6));
+    }
+
+    #[tokio::test]
+    async fn fallback_fires_without_blocks_and_closed_stream_is_reported() {
+        let (tx, mut rx) = futures::channel::mpsc::unbounded::<u64>();
+        assert_eq!(
+            next_trigger(&mut rx, Duration::from_millis(20), Duration::ZERO).await,
+            Trigger::Timer
+        );
+
+        drop(tx);
+        assert_eq!(
+            next_trigger(&mut rx, Duration::from_millis(20), Duration::ZERO).await,
+            Trigger::Closed
+        );
+    }
+
+    #[test]
+    fn fallback_defaults_to_runtime_block_time() {
+        assert_eq!(fallback_period(0), Duration::from_millis(MILLI_SECS_PER_BLOCK));
+        assert_eq!(fallback_period(-3), Duration::from_millis(MILLI_SECS_PER_BLOCK));
+        assert_eq!(fallback_period(2), Duration::from_secs(2));
+    }
+
+    /// Records rounds and fires the shutdown signal from inside round `stop_after`.
+    struct Recorder {
+        seen: Vec<Option<u64>>,
+        completed: usize,
+        stop_after: usize,
+        stop: Option<futures::channel::oneshot::Sender<()>>,
+    }
+
+    impl RoundRunner for Recorder {
+        async fn round(&mut self, notified_block: Option<u64>) {
+            self.seen.push(notified_block);
+            if self.seen.len() == self.stop_after {
+                if let Some(tx) = self.stop.take() {
+                    let _ = tx.send(());
+                }
+            }
+            // Work still in flight when the signal fires must finish.
+            tokio::time::sleep(Duration::from_millis(5)).await;
+            self.completed += 1;
+        }
+    }
+
+    #[tokio::test]
+    async fn shutdown_signal_stops_loop_after_in_flight_round() {
+        let (blocks_tx, mut blocks) = futures::channel::mpsc::unbounded();
+        for n in [1, 2, 3] {
+            blocks_tx.unbounded_send(n).unwrap();
+        }
+        let (stop_tx, stop_rx) = futures::channel::oneshot::channel::<()>();
+        let mut recorder =
+            Recorder { seen: Vec::new(), completed: 0, stop_after: 2, stop: Some(stop_tx) };
+
+        let rounds = drive(
+            &mut blocks,
+            Duration::from_secs(5),
+            Duration::ZERO,
+            async move {
+                let _ = stop_rx.await;
+            },
+            &mut recorder,
+        )
+        .await;
+
+        assert_eq!(rounds, 2);
+        assert_eq!(recorder.seen, vec![Some(1), Some(2)]);
+        assert_eq!(recorder.completed, 2);
+    }
+
+    #[tokio::test]
+    async fn shutdown_interrupts_wait_for_next_trigger() {
+        let (_blocks_tx, mut blocks) = futures::channel::mpsc::unbounded::<u64>();
+        let mut recorder = Recorder { seen: Vec::new(), completed: 0, stop_after: 0, stop: None };
+
+        // Fallback is far away; shutdown must not wait for it.
+        let rounds = drive(
+            &mut blocks,
+            Duration::from_secs(60),
+            Duration::ZERO,
+            tokio::time::sleep(Duration::from_millis(10)),
+            &mut recorder,
+        )
+        .await;
+
+        assert_eq!(rounds, 0);
+        assert!(recorder.seen.is_empty());
+    }
+
+    #[test]
+    fn readiness_tracks_fetches_and_stuck_rounds() {
+        let health = Health::default();
+        let max_age = Duration::from_secs(30);
+        let t0 = Instant::now();
+        // Nothing fetched yet.
+        assert!(!health.is_ready(t0, max_age));
+
+        health.fetch_ok(t0);
+        assert!(health.is_ready(t0 + max_age, max_age));
+        assert!(!health.is_ready(t0 + max_age + Duration::from_secs(1), max_age));
+
+        // A round that never finishes makes the worker unready.
+        health.round_started(t0);
+        assert!(health.is_ready(t0 + Duration::from_secs(10), max_age));
+        health.fetch_ok(t0 + Duration::from_secs(40));
+        assert!(!health.is_ready(t0 + Duration::from_secs(40), max_age));
+        health.round_finished();
+        assert!(health.is_ready(t0 + Duration::from_secs(40), max_age));
+    }
+
+    #[tokio::test]
+    async fn health_endpoints_report_status() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let health = Health::default();
+        let server = tokio::spawn(serve_health(listener, health.clone(), Duration::from_secs(30)));
+
+        let get = |path: &str| {
+            let url = format!("http://{addr}{path}");
+            async move { Http::new().get(url).send().await.unwrap().status().as_u16() }
+        };
+        assert_eq!(get("/healthz").await, 200);
+        assert_eq!(get("/readyz").await, 503);
+        assert_eq!(get("/nope").await, 404);
+
+        health.fetch_ok(Instant::now());
+        assert_eq!(get("/readyz").await, 200);
 
-        // Sleep for ~half a slot (block time ~6s → 3s here).
-        tokio::time::sleep(Duration::from_secs(3)).await;
+        server.abort();
+    }
+
+    /// Snapshot hash the stub backend publishes for every slot.
+    const STUB_SNAPSHOT: &str =
+        "0x0707070707070707070707070707070707070707070707070707070707070707";
+
+    /// Read one HTTP request: its head and, per Content-Length, its body.
+    async fn read_request(sock: &mut tokio::net::TcpStream) -> (String, String) {
+        let mut data = Vec::new();
+        let mut buf = [0u8; 4096];
+        loop {
+            let n = sock.read(&mut buf).await.unwrap_or(0);
+            data.extend_from_slice(&buf[..n]);
+            let text = String::from_utf8_lossy(&data).to_string();
+            if let Some((head, body)) = text.split_once("\r\n\r\n") {
+                let len = head
+                    .lines()
+                    .filter_map(|l| l.split_once(':'))
+                    .find(|(k, _)| k.eq_ignore_ascii_case("content-length"))
+                    .and_then(|(_, v)| v.trim().parse::<usize>().ok())
+                    .unwrap_or(0);
+                if n == 0 || body.len() >= len {
+                    return (head.to_string(), body.to_string());
+                }
+            } else if n == 0 {
+                return (text, String::new());
+            }
+        }
+    }
+
+    /// Backend stub serving one open request and offer as every slot's
+    /// snapshot market; records "METHOD path" of every call it receives,
+    /// followed by " body" when the call has one.
+    async fn mock_backend() -> (String, Arc<Mutex<Vec<String>>>) {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let seen = Arc::new(Mutex::new(Vec::new()));
+
+        let log = seen.clone();
+        tokio::spawn(async move {
+            loop {
+                let (mut sock, _) = listener.accept().await.unwrap();
+                let (head, body) = read_request(&mut sock).await;
+                let mut parts = head.split_whitespace();
+                let (method, path) = (parts.next().unwrap_or(""), parts.next().unwrap_or(""));
+                log.lock().unwrap().push(if body.is_empty() {
+                    format!("{method} {path}")
+                } else {
+                    format!("{method} {path} {body}")
+                });
+                let body = match path.strip_prefix("/poba/market/") {
+                    Some(slot) => serde_json::to_string(&SlotMarket {
+                        slot: slot.parse().unwrap_or(0),
+                        snapshot_hash: STUB_SNAPSHOT.into(),
+                        requests: vec![request("r", 0)],
+                        offers: vec![offer("o")],
+                    })
+                    .unwrap(),
+                    None => "{}".to_string(),
+                };
+                let resp = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                    body.len()
+                );
+                let _ = sock.write_all(resp.as_bytes()).await;
+            }
+        });
+        (url, seen)
+    }
+
+    fn test_worker(backend_url: String, dry_run: bool) -> Worker {
+        Worker {
+            current_slot: Box::new(|| Some(5)),
+            http: Http::new(),
+            backend_url,
+            role: "finalizer".into(),
+            proposer_id: "alice".into(),
+            signer: None,
+            is_finalizer: true,
+            log_json: false,
+            dry_run,
+            health: Health::default(),
+            last_finalized_slot_local: 0,
+        }
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn dry_run_never_submits_or_finalizes() {
+        let (url, seen) = mock_backend().await;
+        let mut worker = test_worker(url, true);
+        worker.round(Some(5)).await;
+        assert_eq!(*seen.lock().unwrap(), vec!["GET /poba/market/5"]);
+        assert_eq!(worker.last_finalized_slot_local, 0);
+
+        // The same market in a live round reaches both endpoints.
+        let (url, seen) = mock_backend().await;
+        let mut worker = test_worker(url, false);
+        worker.round(Some(5)).await;
+        let seen = seen.lock().unwrap();
+        assert!(seen.iter().any(|c| c.starts_with("POST /poba/submit-proposal")));
+        assert!(seen.iter().any(|c| c.starts_with("POST /poba/finalize-slot")));
+        assert_eq!(worker.last_finalized_slot_local, 5);
+    }
+
+    #[tokio::test]
+    async fn proposal_references_the_slot_snapshot() {
+        let (url, seen) = mock_backend().await;
+        let mut worker = test_worker(url, false);
+        worker.round(Some(5)).await;
+
+        let seen = seen.lock().unwrap();
+        let submit = seen
+            .iter()
+            .find_map(|c| c.strip_prefix("POST /poba/submit-proposal?proposer_id=alice "))
+            .expect("proposal submitted");
+        let body: SubmitProposalBody = serde_json::from_str(submit).unwrap();
+        assert_eq!(body.slot, 5);
+        assert_eq!(body.snapshot_hash, STUB_SNAPSHOT);
+    }
+
+    #[tokio::test]
+    async fn slot_comes_from_the_runtime_not_the_trigger() {
+        let (url, seen) = mock_backend().await;
+        let mut worker = test_worker(url, true);
+        worker.round(Some(9)).await;
+        worker.round(None).await;
+        assert_eq!(*seen.lock().unwrap(), vec!["GET /poba/market/5", "GET /poba/market/5"]);
+
+        // Without a runtime slot the round never reaches the backend.
+        let (url, seen) = mock_backend().await;
+        let mut worker = test_worker(url, true);
+        worker.current_slot = Box::new(|| None);
+        worker.round(Some(9)).await;
+        assert!(seen.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn unknown_kind_uses_default_weights() {
+        let default = KindWeights { alpha_per_km: 7.0, beta_per_cent: 2.0 };
+        let weights = ScoringWeights {
+            package: KindWeights { alpha_per_km: 1.0, beta_per_cent: 1.0 },
+            passenger: KindWeights { alpha_per_km: 3.0, beta_per_cent: 1.0 },
+            default,
+        };
+        assert_eq!(weights.for_kind(1).alpha_per_km, 3.0);
+        assert_eq!(weights.for_kind(9), default);
+    }
+
+    #[test]
+    fn extreme_pairs_score_within_base() {
+        let base = 1_000_000;
+        let w = KindWeights { alpha_per_km: 1_000.0, beta_per_cent: 1.0 };
+        let huge = KindWeights { alpha_per_km: f64::MAX, beta_per_cent: f64::MAX };
+        let negative = KindWeights { alpha_per_km: -1e300, beta_per_cent: -1.0 };
+
+        for (w, km, price) in [
+            (w, 0.0, 0),
+            (w, 20_000.0, u32::MAX as i64),
+            (w, f64::INFINITY, 1_000),
+            (w, f64::NAN, 1_000),
+            (huge, 1e308, i64::MAX),
+            (negative, 1e300, 1_000),
+        ] {
+            let (penalty, score) = pair_score(w, km, price, base, 10_000_000);
+            assert!(penalty >= 0, "penalty {penalty} for km={km} price={price}");
+            assert!((0..=base).contains(&score), "score {score} for km={km} price={price}");
+        }
+
+        assert_eq!(pair_score(huge, 1e308, 1, base, 10_000_000), (i64::MAX, 0));
+        // The price term is capped: only the first 1_000 cents count.
+        assert_eq!(pair_score(w, 0.0, 5_000, base, 1_000), (1_000, base - 1_000));
+        // A negative base score is treated as 0.
+        assert_eq!(pair_score(w, 0.0, 10, -5, 1_000).1, 0);
+    }
+
+    #[test]
+    fn total_score_never_overflows() {
+        let item = |uuid: &str, partial_score| MatchItem {
+            request_uuid: uuid.into(),
+            offer_uuid: "o".into(),
+            agreed_price_cents: 1,
+            partial_score,
+        };
+        let (total, kept) = checked_total(
+            1,
+            vec![item("a", i64::MAX - 10), item("b", 20), item("c", 10), item("d", 1)],
+        );
+
+        assert_eq!(total, i64::MAX);
+        let kept: Vec<_> = kept.iter().map(|m| m.request_uuid.as_str()).collect();
+        assert_eq!(kept, ["a", "c"]);
+    }
+
+    #[test]
+    fn extreme_market_keeps_scores_in_range() {
+        let mut far = request("far", 0);
+        far.max_price_cents = u32::MAX;
+        far.to_lat = -89_000_000;
+        far.to_lon = -179_000_000;
+        let mut pricey = offer("o");
+        pricey.min_price_cents = u32::MAX - 1;
+        pricey.capacity = 2;
+        let weights = ScoringWeights {
+            package: KindWeights { alpha_per_km: 1e18, beta_per_cent: 1e18 },
+            passenger: KindWeights { alpha_per_km: 1e18, beta_per_cent: 1e18 },
+            default: KindWeights { alpha_per_km: 1e18, beta_per_cent: 1e18 },
+        };
+
+        // Saturated penalties must not overflow the search's running cost.
+        let (total, matches) =
+            compute_matches_with_weights(1, &[far, request("near", 0)], &[pricey], &weights);
+        for m in &matches {
+            assert!((0..=1_000_000).contains(&m.partial_score));
+        }
+        assert_eq!(total, matches.iter().map(|m| m.partial_score).sum::<i64>());
+    }
+
+    #[test]
+    fn objectives_trade_coverage_against_cost() {
+        // Serving a passenger costs more than skipping it (default skip cost).
+        let cheap = KindWeights { alpha_per_km: 1_000.0, beta_per_cent: 1.0 };
+        let weights = ScoringWeights {
+            package: cheap,
+            passenger: KindWeights { alpha_per_km: 1_000.0, beta_per_cent: 1_000_000.0 },
+            default: cheap,
+        };
+        let requests = [request("r-pkg", 0), request("r-pax", 1)];
+        let offers = [MarketOffer { capacity: 2, ..offer("o") }];
+
+        let (_, matches) = compute_matches_with(1, &requests, &offers, &weights, Objective::MinCost);
+        let served: Vec<_> = matches.iter().map(|m| m.request_uuid.as_str()).collect();
+        assert_eq!(served, ["r-pkg"]);
+
+        let (_, matches) =
+            compute_matches_with(1, &requests, &offers, &weights, Objective::MaxCoverage);
+        assert_eq!(matches.len(), 2);
+
+        // Equal coverage: the cheaper offer wins.
+        let pricey = MarketOffer { uuid_16: "o-pricey".into(), min_price_cents: 1_900, ..offer("o") };
+        let (_, matches) = compute_matches_with(
+            1,
+            &requests[..1],
+            &[pricey, offer("o")],
+            &weights,
+            Objective::MaxCoverage,
+        );
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].offer_uuid, "o");
+
+        assert_eq!(Objective::parse(""), Some(Objective::MinCost));
+        assert_eq!(Objective::parse("Max_Coverage"), Some(Objective::MaxCoverage));
+        assert_eq!(Objective::parse("fastest"), None);
+    }
+
+    #[test]
+    fn idempotency_key_ignores_match_order() {
+        let item = |r: &str, o: &str| MatchItem {
+            request_uuid: r.into(),
+            offer_uuid: o.into(),
+            agreed_price_cents: 1_500,
+            partial_score: 10,
+        };
+        let (a, b) = (item("r1", "o1"), item("r2", "o1"));
+
+        let key = idempotency_key(7, &[a.clone(), b.clone()]);
+        assert_eq!(key.len(), 64);
+        assert_eq!(key, idempotency_key(7, &[b.clone(), a.clone()]));
+        assert_ne!(key, idempotency_key(8, &[a.clone(), b]));
+        assert_ne!(key, idempotency_key(7, &[a]));
+    }
+
+    #[test]
+    fn proposal_signature_binds_the_proposer_key() {
+        let item = |r: &str| MatchItem {
+            request_uuid: r.into(),
+            offer_uuid: "o1".into(),
+            agreed_price_cents: 1_500,
+            partial_score: 10,
+        };
+        let matches = vec![item("r1"), item("r2")];
+        let alice = sr25519::Pair::from_string("//Alice", None).unwrap();
+        let bob = sr25519::Pair::from_string("//Bob", None).unwrap();
+        let verify = |sig: &str, slot: u64, matches: &[MatchItem]| {
+            let bytes: Vec<u8> = (2..sig.len())
+                .step_by(2)
+                .map(|i| u8::from_str_radix(&sig[i..i + 2], 16).unwrap())
+                .collect();
+            let sig = sr25519::Signature::from_raw(bytes.try_into().unwrap());
+            sr25519::Pair::verify(&sig, proposal_payload(slot, matches), &alice.public())
+        };
+
+        let valid = sign_proposal(&alice, 7, &matches);
+        assert!(verify(&valid, 7, &matches));
+        // Same signature holds whatever the match order.
+        assert!(verify(&valid, 7, &[item("r2"), item("r1")]));
+
+        // Forged: Bob signs while claiming to be Alice, or the proposal is altered.
+        assert!(!verify(&sign_proposal(&bob, 7, &matches), 7, &matches));
+        assert!(!verify(&valid, 8, &matches));
+        assert!(!verify(&valid, 7, &matches[..1]));
+    }
+
+    #[test]
+    fn invalid_kind_and_types_mask_are_dropped() {
+        let mut requests =
+            vec![request("r-package", 0), request("r-passenger", 1), request("r-bad", 2)];
+        let mut offers = vec![
+            offer("o-ok"),
+            MarketOffer { types_mask: 0b100, ..offer("o-unknown-bit") },
+            MarketOffer { types_mask: 0b101, ..offer("o-mixed") },
+        ];
+
+        drop_invalid_kinds(1, &mut requests, &mut offers);
+        let kept: Vec<_> = requests.iter().map(|r| r.uuid_16.as_str()).collect();
+        assert_eq!(kept, ["r-package", "r-passenger"]);
+        let kept: Vec<_> = offers.iter().map(|o| o.uuid_16.as_str()).collect();
+        assert_eq!(kept, ["o-ok"]);
+    }
+
+    #[test]
+    fn over_cap_market_keeps_most_urgent_items() {
+        let at = |uuid: &str, start: u64| MarketRequest { window_start: start, ..request(uuid, 0) };
+        let mut requests =
+            vec![at("r-late", 300), at("r-b", 100), at("r-a", 100), at("r-mid", 200)];
+        let mut offers = vec![
+            MarketOffer { window_start: 50, ..offer("o-late") },
+            offer("o-early"),
+        ];
+
+        let caps = MarketCaps { requests: 2, offers: 1 };
+        caps.apply(1, &mut requests, &mut offers);
+        let kept: Vec<_> = requests.iter().map(|r| r.uuid_16.as_str()).collect();
+        assert_eq!(kept, ["r-a", "r-b"]);
+        assert_eq!(offers.len(), 1);
+        assert_eq!(offers[0].uuid_16, "o-early");
+
+        // Only the capped subset reaches the matcher.
+        let (_, matches) =
+            compute_matches_with_weights(1, &requests, &offers, &ScoringWeights::from_env());
+        assert_eq!(matches.len(), 1);
+        assert!(kept.contains(&matches[0].request_uuid.as_str()));
+        assert_eq!(matches[0].offer_uuid, "o-early");
+
+        // Within the caps nothing moves.
+        let mut small = vec![at("r-late", 300), at("r-a", 100)];
+        MarketCaps { requests: 2, offers: 1 }.apply(1, &mut small, &mut Vec::new());
+        assert_eq!(small[0].uuid_16, "r-late");
+    }
+
+    #[test]
+    fn price_policies_pick_expected_agreed_price() {
+        let (off_min, req_max) = (1_000, 2_000);
+        let agreed = |name: &str, weight: f64| {
+            PricePolicy::parse(name, weight).unwrap().agreed_cents(off_min, req_max)
+        };
+
+        assert_eq!(agreed("", 0.0), 1_500);
+        assert_eq!(agreed("midpoint", 0.0), 1_500);
+        assert_eq!(agreed("offer_min", 0.0), 1_000);
+        assert_eq!(agreed("request_max", 0.0), 2_000);
+        assert_eq!(agreed("Weighted", 0.25), 1_250);
+        assert_eq!(agreed("weighted", 0.0), 1_000);
+        // Weights outside [0, 1] are clamped.
+        assert_eq!(agreed("weighted", 3.0), 2_000);
+
+        // Without a request maximum only the offer minimum is known.
+        assert_eq!(PricePolicy::RequestMax.agreed_cents(off_min, 0), 1_000);
+        assert_eq!(PricePolicy::parse("highest", 0.5), None);
+    }
+}
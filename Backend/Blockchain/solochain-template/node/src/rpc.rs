@@ -7,12 +7,15 @@
 
 use std::sync::Arc;
 
-use jsonrpsee::RpcModule;
+use jsonrpsee::{core::RpcResult, proc_macros::rpc, types::ErrorObject, RpcModule};
+use pallet_poba_runtime_api::{PobaApi, Proposal};
 use sc_transaction_pool_api::TransactionPool;
-use solochain_template_runtime::{opaque::Block, AccountId, Balance, Nonce};
+use serde::{Deserialize, Serialize};
+use solochain_template_runtime::{opaque::Block, AccountId, Balance, Hash, Nonce, PoBAMaxMatches};
 use sp_api::ProvideRuntimeApi;
 use sp_block_builder::BlockBuilder;
 use sp_blockchain::{Error as BlockChainError, HeaderBackend, HeaderMetadata};
+use sp_runtime::traits::Get;
 
 /// Full client dependencies.
 pub struct FullDeps<C, P> {
@@ -33,6 +36,7 @@ where
 	C::Api: substrate_frame_rpc_system::AccountNonceApi<Block, AccountId, Nonce>,
 	C::Api: pallet_transaction_payment_rpc::TransactionPaymentRuntimeApi<Block, Balance>,
 	C::Api: BlockBuilder<Block>,
+	C::Api: PobaApi<Block, PoBAMaxMatches, AccountId>,
 	P: TransactionPool + 'static,
 {
 	use pallet_transaction_payment_rpc::{TransactionPayment, TransactionPaymentApiServer};
@@ -42,7 +46,8 @@ where
 	let FullDeps { client, pool } = deps;
 
 	module.merge(System::new(client.clone(), pool).into_rpc())?;
-	module.merge(TransactionPayment::new(client).into_rpc())?;
+	module.merge(TransactionPayment::new(client.clone()).into_rpc())?;
+	module.merge(Poba::new(client).into_rpc())?;
 
 	// Extend this RPC with a custom API by using the following syntax.
 	// `YourRpcStruct` should have a reference to a client, which is needed
@@ -57,4 +62,121 @@ where
 	// module.merge(ChainSpec::new(chain_name, genesis_hash, properties).into_rpc())?;
 
 	Ok(module)
-}
\ No newline at end of file
+}
+
+/// A PoBA match as served over RPC, with UUIDs as 32-char lowercase hex.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MatchJson {
+	/// Request UUID (hex, no dashes).
+	pub request_uuid: String,
+	/// Offer UUID (hex, no dashes).
+	pub offer_uuid: String,
+	/// Agreed price in cents.
+	pub agreed_price_cents: u32,
+	/// Score contribution of this match.
+	pub partial_score: i64,
+}
+
+/// A PoBA proposal as served over RPC.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProposalJson {
+	/// Slot the proposal belongs to.
+	pub slot: u64,
+	/// Total score claimed by the proposer.
+	pub total_score: i64,
+	/// Matches in submission order.
+	pub matches: Vec<MatchJson>,
+}
+
+fn uuid_hex(uuid: &[u8; 16]) -> String {
+	uuid.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+impl ProposalJson {
+	fn new<S: Get<u32>>(slot: u64, proposal: Proposal<S>) -> Self {
+		let matches = proposal
+			.matches
+			.iter()
+			.map(|m| MatchJson {
+				request_uuid: uuid_hex(&m.request_uuid),
+				offer_uuid: uuid_hex(&m.offer_uuid),
+				agreed_price_cents: m.agreed_price_cents,
+				partial_score: m.partial_score,
+			})
+			.collect();
+		Self { slot, total_score: proposal.total_score, matches }
+	}
+}
+
+/// PoBA RPC methods.
+#[rpc(server)]
+pub trait PobaRpcApi {
+	/// Winning proposal of a finalized slot, at `at` or the best block.
+	#[method(name = "poba_getFinalizedProposal")]
+	fn get_finalized_proposal(&self, slot: u64, at: Option<Hash>) -> RpcResult<Option<ProposalJson>>;
+}
+
+/// Serves [`PobaRpcApiServer`] through the [`PobaApi`] runtime API.
+pub struct Poba<C> {
+	client: Arc<C>,
+}
+
+impl<C> Poba<C> {
+	/// Create a new PoBA RPC handler.
+	pub fn new(client: Arc<C>) -> Self {
+		Self { client }
+	}
+}
+
+impl<C> PobaRpcApiServer for Poba<C>
+where
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + Send + Sync + 'static,
+	C::Api: PobaApi<Block, PoBAMaxMatches, AccountId>,
+{
+	fn get_finalized_proposal(&self, slot: u64, at: Option<Hash>) -> RpcResult<Option<ProposalJson>> {
+		let at = at.unwrap_or_else(|| self.client.info().best_hash);
+		let proposal = self.client.runtime_api().finalized_proposal(at, slot).map_err(|e| {
+			ErrorObject::owned(1, "Unable to query PoBA proposal", Some(e.to_string()))
+		})?;
+		Ok(proposal.map(|p| ProposalJson::new(slot, p)))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use pallet_poba_runtime_api::Match;
+
+	#[test]
+	fn finalized_proposal_serializes_hex_uuids() {
+		let mut request_uuid = [0u8; 16];
+		request_uuid[0] = 0xab;
+		request_uuid[15] = 0x01;
+		let proposal = Proposal::<PoBAMaxMatches> {
+			total_score: 42,
+			matches: vec![Match {
+				request_uuid,
+				offer_uuid: [0xff; 16],
+				agreed_price_cents: 1_500,
+				partial_score: 42,
+			}]
+			.try_into()
+			.unwrap(),
+		};
+
+		let json = serde_json::to_value(ProposalJson::new(7, proposal)).unwrap();
+		assert_eq!(
+			json,
+			serde_json::json!({
+				"slot": 7,
+				"total_score": 42,
+				"matches": [{
+					"request_uuid": "ab000000000000000000000000000001",
+					"offer_uuid": "ffffffffffffffffffffffffffffffff",
+					"agreed_price_cents": 1500,
+					"partial_score": 42,
+				}],
+			})
+		);
+	}
+}
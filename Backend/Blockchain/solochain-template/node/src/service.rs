@@ -302,6 +302,11 @@ pub fn new_full<
 		let pool_for_worker = transaction_pool.clone();
 		let keystore = keystore_container.keystore().clone();
 
+		// The sender lives as long as the task manager; dropping it on node
+		// shutdown tells the worker to stop after its current round.
+		let (poba_shutdown_tx, poba_shutdown_rx) = futures::channel::oneshot::channel::<()>();
+		task_manager.keep_alive(poba_shutdown_tx);
+
 		handle.spawn(
 			"poba-worker",
 			Some("poba"),
@@ -310,6 +315,9 @@ pub fn new_full<
 				pool_for_worker,
 				keystore,
 				poba_backend_url,
+				async move {
+					let _ = poba_shutdown_rx.await;
+				},
 			)
 			.boxed(),
 		);
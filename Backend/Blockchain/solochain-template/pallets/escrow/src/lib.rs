@@ -3,11 +3,27 @@
 // Basic imports
 use codec::{Decode, Encode, MaxEncodedLen};
 use frame_support::pallet_prelude::*;
+use frame_support::traits::{
+    schedule::{self, v3::Named as ScheduleNamed, DispatchTime},
+    Bounded, BoundedInline,
+};
 use frame_system::pallet_prelude::*;
 use frame_system::pallet_prelude::BlockNumberFor; // ✅
 use scale_info::TypeInfo;
 use sp_runtime::RuntimeDebug;
-use sp_runtime::traits::AtLeast32BitUnsigned;
+use sp_runtime::traits::{AtLeast32BitUnsigned, BlakeTwo256, Hash, Saturating, Zero};
+use sp_runtime::Permill;
+use sp_std::vec::Vec;
+
+/// Prefix of the scheduler task name of an escrow's timeout.
+const TIMEOUT_TASK_PREFIX: &[u8] = b"escrow/timeout";
+
+pub mod migrations;
+
+#[cfg(test)]
+mod mock;
+#[cfg(test)]
+mod tests;
 
 // --------------------------- Domain Types ---------------------------
 
@@ -18,6 +34,9 @@ pub type OfferUuid   = [u8; 16];
 // Escrow identifier on-chain (independent from DB UUID)
 pub type EscrowId = u64;
 
+/// Highest star rating a payer can give a driver (lowest is 1).
+pub const MAX_STARS: u8 = 5;
+
 /// Delivery / assignment state machine on-chain.
 #[derive(Clone, PartialEq, Eq, Encode, Decode, MaxEncodedLen, TypeInfo, RuntimeDebug)]
 pub enum DeliveryStatus {
@@ -29,14 +48,136 @@ pub enum DeliveryStatus {
     DeliveredByCourier,
     /// Receiver (or payer) confirmed the delivery/arrival.
     ConfirmedByReceiver,
-    /// Payment was released automatically after timeout.
+    /// Payment was released automatically after timeout of a delivered escrow.
     TimeoutReleased,
     /// Assignment was cancelled (optional, not yet wired to flows).
     Cancelled,
-    /// Assignment failed (optional, not yet wired to flows).
+    /// Assignment failed: timed out before delivery (payer refunded) or
+    /// voided by an admin.
     Failed,
 }
 
+impl DeliveryStatus {
+    /// Final statuses allow no further transitions.
+    pub fn is_final(&self) -> bool {
+        matches!(
+            self,
+            DeliveryStatus::ConfirmedByReceiver
+                | DeliveryStatus::TimeoutReleased
+                | DeliveryStatus::Cancelled
+                | DeliveryStatus::Failed
+        )
+    }
+}
+
+/// Who acts on an escrow.
+#[derive(Clone, Copy, PartialEq, Eq, Encode, Decode, MaxEncodedLen, TypeInfo, RuntimeDebug)]
+pub enum EscrowRole {
+    /// The escrow's driver.
+    Driver,
+    /// The escrow's payer / receiver.
+    Payer,
+    /// `AdminOrigin`.
+    Admin,
+    /// Any signed account (the backend, timeout keepers).
+    Anyone,
+}
+
+/// Status-changing actions on an escrow: the single transition table the
+/// extrinsics and `allowed_transitions` share.
+#[derive(Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+pub enum EscrowAction {
+    /// `mark_picked_up`.
+    PickUp,
+    /// `mark_delivered`.
+    Deliver,
+    /// `confirm_received`.
+    Confirm,
+    /// `release_partial` releasing the rest of the amount.
+    ReleasePartial,
+    /// `release_escrow`.
+    Release,
+    /// `force_timeout_release(_batch)` of a delivered escrow, once due.
+    TimeoutRelease,
+    /// `force_timeout_release(_batch)` of an undelivered escrow, once due.
+    TimeoutRefund,
+    /// `admin_void_request`.
+    Void,
+}
+
+impl EscrowAction {
+    pub const ALL: [EscrowAction; 8] = [
+        EscrowAction::PickUp,
+        EscrowAction::Deliver,
+        EscrowAction::Confirm,
+        EscrowAction::ReleasePartial,
+        EscrowAction::Release,
+        EscrowAction::TimeoutRelease,
+        EscrowAction::TimeoutRefund,
+        EscrowAction::Void,
+    ];
+
+    /// Role allowed to take this action.
+    pub fn role(self) -> EscrowRole {
+        match self {
+            EscrowAction::PickUp | EscrowAction::Deliver => EscrowRole::Driver,
+            EscrowAction::Confirm | EscrowAction::ReleasePartial => EscrowRole::Payer,
+            EscrowAction::Release |
+            EscrowAction::TimeoutRelease |
+            EscrowAction::TimeoutRefund => EscrowRole::Anyone,
+            EscrowAction::Void => EscrowRole::Admin,
+        }
+    }
+
+    /// Status reached by taking this action in `from`, if allowed.
+    pub fn apply(self, from: &DeliveryStatus) -> Option<DeliveryStatus> {
+        if from.is_final() {
+            return None;
+        }
+        match (self, from) {
+            (EscrowAction::PickUp, DeliveryStatus::Created) =>
+                Some(DeliveryStatus::PickedUpByCourier),
+            (EscrowAction::Deliver, DeliveryStatus::PickedUpByCourier) =>
+                Some(DeliveryStatus::DeliveredByCourier),
+            (EscrowAction::Confirm, DeliveryStatus::DeliveredByCourier) =>
+                Some(DeliveryStatus::ConfirmedByReceiver),
+            (EscrowAction::PickUp | EscrowAction::Deliver | EscrowAction::Confirm, _) => None,
+            (EscrowAction::ReleasePartial | EscrowAction::Release, _) =>
+                Some(DeliveryStatus::ConfirmedByReceiver),
+            (EscrowAction::TimeoutRelease, DeliveryStatus::DeliveredByCourier) =>
+                Some(DeliveryStatus::TimeoutReleased),
+            (EscrowAction::TimeoutRelease, _) => None,
+            // Nothing was delivered: the payer gets the rest back.
+            (EscrowAction::TimeoutRefund, DeliveryStatus::DeliveredByCourier) => None,
+            (EscrowAction::TimeoutRefund, _) => Some(DeliveryStatus::Failed),
+            (EscrowAction::Void, _) => Some(DeliveryStatus::Failed),
+        }
+    }
+}
+
+/// Why an escrow was not created for a finalized match.
+#[derive(Clone, PartialEq, Eq, Encode, Decode, MaxEncodedLen, TypeInfo, RuntimeDebug)]
+pub enum MatchSkipReason {
+    /// The request already has an escrow.
+    RequestAlreadyAssigned,
+    /// Driver/payer could not be resolved for the (request, offer) pair.
+    UnknownParties,
+    /// The agreed amount is zero.
+    ZeroAmount,
+}
+
+/// Resolves the driver and payer accounts behind a matched (request, offer).
+pub trait MatchParties<AccountId> {
+    /// Returns `(driver, payer)` if both are known.
+    fn parties(request_uuid: &RequestUuid, offer_uuid: &OfferUuid) -> Option<(AccountId, AccountId)>;
+}
+
+impl<AccountId> MatchParties<AccountId> for () {
+    fn parties(_: &RequestUuid, _: &OfferUuid) -> Option<(AccountId, AccountId)> {
+        None
+    }
+}
+
 /// On-chain escrow record for a single assignment.
 /// Note: this is *logical* escrow. Actual money is handled off-chain.
 #[derive(Clone, PartialEq, Eq, Encode, Decode, MaxEncodedLen, TypeInfo, RuntimeDebug)]
@@ -47,6 +188,10 @@ pub struct AssignmentEscrow<AccountId, Balance, BlockNumber> {
     pub payer:        AccountId,
     /// Monetary amount for this assignment (e.g., in cents or chain base units).
     pub amount:       Balance,
+    /// Part of `amount` already released to the driver (see `release_partial`).
+    pub released_so_far: Balance,
+    /// Payer top-up held for the early-delivery bonus (`EarlyBonusBps` of `amount`).
+    pub bonus_top_up: Balance,
     pub status:       DeliveryStatus,
     pub created_at:   BlockNumber,
     pub deadline:     BlockNumber,
@@ -72,9 +217,55 @@ pub mod pallet {
         /// type of the runtime (Header::Number).
         #[pallet::constant]
         type ConfirmationTimeoutBlocks: Get<BlockNumberFor<Self>>;
+
+        /// Resolves driver/payer for escrows created from finalized PoBA matches.
+        type MatchParties: MatchParties<Self::AccountId>;
+
+        /// Maximum number of escrow ids in one `force_timeout_release_batch`.
+        #[pallet::constant]
+        type MaxBatchSize: Get<u32>;
+
+        /// Early-delivery bonus in basis points of the amount, topped up by the
+        /// payer at creation. Zero disables the bonus.
+        #[pallet::constant]
+        type EarlyBonusBps: Get<u32>;
+
+        /// `confirm_received` pays the bonus only when it comes more than this
+        /// many blocks before the deadline.
+        #[pallet::constant]
+        type EarlyBonusMarginBlocks: Get<BlockNumberFor<Self>>;
+
+        /// Origin allowed to void escrows during incident recovery.
+        type AdminOrigin: EnsureOrigin<Self::RuntimeOrigin>;
+
+        /// Whether `create_escrow` requires `MatchParties` to know the
+        /// (request, offer) pair with the given driver and payer.
+        #[pallet::constant]
+        type RequireMarketConsistency: Get<bool>;
+
+        /// Runtime call type, for the `force_timeout_release` handed to
+        /// `Scheduler`.
+        type RuntimeCall: From<Call<Self>>
+            + Encode
+            + IsType<<Self as frame_system::Config>::RuntimeCall>;
+
+        /// Origin type of `Scheduler`; timeouts are scheduled as `Root`.
+        type PalletsOrigin: From<frame_system::RawOrigin<Self::AccountId>>;
+
+        /// Runs `force_timeout_release` at the deadline of each escrow;
+        /// cancelled when the escrow is settled otherwise.
+        type Scheduler: ScheduleNamed<
+            BlockNumberFor<Self>,
+            <Self as Config>::RuntimeCall,
+            Self::PalletsOrigin,
+        >;
     }
 
+    /// v1: `released_so_far` and `bonus_top_up` in `AssignmentEscrow`.
+    const STORAGE_VERSION: StorageVersion = StorageVersion::new(1);
+
     #[pallet::pallet]
+    #[pallet::storage_version(STORAGE_VERSION)]
     pub struct Pallet<T>(_);
 
     // -------- Storage --------
@@ -110,6 +301,23 @@ pub mod pallet {
     pub type RequestToEscrow<T: Config> =
         StorageMap<_, Blake2_128Concat, RequestUuid, EscrowId, OptionQuery>;
 
+    /// Running `(sum_of_stars, ratings_count)` per driver.
+    #[pallet::storage]
+    #[pallet::getter(fn driver_ratings)]
+    pub type DriverRatings<T: Config> =
+        StorageMap<_, Blake2_128Concat, T::AccountId, (u64, u32), ValueQuery>;
+
+    /// Escrows whose payer already rated the driver.
+    #[pallet::storage]
+    pub type RatedEscrows<T: Config> = StorageMap<_, Blake2_128Concat, EscrowId, (), OptionQuery>;
+
+    /// Escrows whose PoBA match was revoked after finalization, with the
+    /// slot. Flagged escrows keep running; resolving them is left to support.
+    #[pallet::storage]
+    #[pallet::getter(fn flagged_escrow)]
+    pub type FlaggedEscrows<T: Config> =
+        StorageMap<_, Blake2_128Concat, EscrowId, u64 /*slot*/, OptionQuery>;
+
     // -------- Events --------
 
     #[pallet::event]
@@ -125,13 +333,19 @@ pub mod pallet {
             amount: T::Balance,
             deadline: BlockNumberFor<T>,
         },
-        /// Courier marked pickup.
+        /// Courier marked pickup at block `at`.
         PickedUp {
             escrow_id: EscrowId,
+            driver: T::AccountId,
+            amount: T::Balance,
+            at: BlockNumberFor<T>,
         },
-        /// Courier marked delivery.
+        /// Courier marked delivery at block `at`.
         Delivered {
             escrow_id: EscrowId,
+            driver: T::AccountId,
+            amount: T::Balance,
+            at: BlockNumberFor<T>,
         },
         /// Receiver confirmed delivery.
         ReceiverConfirmed {
@@ -142,6 +356,53 @@ pub mod pallet {
             escrow_id: EscrowId,
             amount: T::Balance,
         },
+        /// An undelivered escrow timed out: `amount` (what was not released
+        /// yet) goes back to the payer.
+        PaymentRefunded {
+            escrow_id: EscrowId,
+            amount: T::Balance,
+        },
+        /// Early confirmation released the payer's bonus top-up to the driver,
+        /// on top of `PaymentReleased`.
+        EarlyBonusReleased {
+            escrow_id: EscrowId,
+            bonus: T::Balance,
+        },
+        /// No escrow was created for a match of a finalized slot.
+        MatchEscrowSkipped {
+            slot: u64,
+            request_uuid: RequestUuid,
+            offer_uuid: OfferUuid,
+            reason: MatchSkipReason,
+        },
+        /// Part of an escrow was released to the driver; the escrow stays open
+        /// until `released_so_far` reaches its amount.
+        PartialPaymentReleased {
+            escrow_id: EscrowId,
+            amount: T::Balance,
+            released_so_far: T::Balance,
+        },
+        /// The payer of a confirmed escrow rated its driver.
+        RatingSubmitted {
+            driver: T::AccountId,
+            stars: u8,
+        },
+        /// A keeper batch settled these timed-out escrows: `released` to
+        /// their drivers, `refunded` to their payers.
+        BatchTimeoutReleased {
+            released: Vec<EscrowId>,
+            refunded: Vec<EscrowId>,
+        },
+        /// The PoBA finalization of `slot` that created this escrow was revoked.
+        EscrowFlagged {
+            escrow_id: EscrowId,
+            slot: u64,
+        },
+        /// An admin voided this escrow; its request can be escrowed again.
+        EscrowVoided {
+            escrow_id: EscrowId,
+            request_uuid: RequestUuid,
+        },
     }
 
     // -------- Errors --------
@@ -164,11 +425,90 @@ pub mod pallet {
         ZeroAmountNotAllowed,
         /// Too early to force timeout-based payment release.
         TimeoutNotReached,
+        /// More ids than `MaxBatchSize` in one batch.
+        BatchTooLarge,
+        /// Partial release larger than what is left of the escrow.
+        AmountExceedsRemaining,
+        /// Stars must be between 1 and `MAX_STARS`.
+        InvalidRating,
+        /// This escrow's driver was already rated.
+        AlreadyRated,
+        /// The PoBA match behind this escrow's offer was revoked.
+        OfferNoLongerActive,
+        /// The escrow of this request was created for a different offer.
+        OfferMismatch,
+        /// `MatchParties` does not know this (request, offer) with this
+        /// driver and payer.
+        MarketMismatch,
     }
 
     // -------- Helpers --------
 
     impl<T: Config> Pallet<T> {
+        /// Settle a due, non-final escrow by timeout: delivered escrows go to
+        /// `TimeoutReleased` (driver paid), earlier ones to `Failed` (payer
+        /// refunded). Returns the unreleased rest and whether it was refunded.
+        fn timeout_release(
+            escrow_id: EscrowId,
+            now: BlockNumberFor<T>,
+        ) -> Result<(T::Balance, bool), DispatchError> {
+            Escrows::<T>::try_mutate(escrow_id, |maybe| {
+                let escrow = maybe.as_mut().ok_or(Error::<T>::EscrowNotFound)?;
+
+                ensure!(!Self::is_final_status(&escrow.status), Error::<T>::EscrowAlreadyFinal);
+
+                // Only allow timeout if current block >= deadline.
+                ensure!(now >= escrow.deadline, Error::<T>::TimeoutNotReached);
+
+                if EscrowAction::TimeoutRelease.apply(&escrow.status).is_some() {
+                    Self::transition(escrow, EscrowAction::TimeoutRelease)?;
+                    Ok((Self::take_remaining(escrow), false))
+                } else {
+                    Self::transition(escrow, EscrowAction::TimeoutRefund)?;
+                    Ok((escrow.amount.saturating_sub(escrow.released_so_far), true))
+                }
+            })
+        }
+
+        /// Scheduler task name of the timeout of `escrow_id`.
+        pub fn timeout_task(escrow_id: EscrowId) -> schedule::TaskName {
+            BlakeTwo256::hash_of(&(TIMEOUT_TASK_PREFIX, escrow_id)).into()
+        }
+
+        /// Schedule `force_timeout_release(escrow_id)` for `deadline`.
+        ///
+        /// Best effort: if the scheduler refuses (e.g. the block is full),
+        /// keepers can still release the escrow by hand.
+        fn schedule_timeout(escrow_id: EscrowId, deadline: BlockNumberFor<T>) {
+            let call: <T as Config>::RuntimeCall =
+                Call::<T>::force_timeout_release { escrow_id }.into();
+            let Ok(call) = BoundedInline::try_from(call.encode()) else {
+                return;
+            };
+            let _ = T::Scheduler::schedule_named(
+                Self::timeout_task(escrow_id),
+                DispatchTime::At(deadline),
+                None,
+                schedule::HARD_DEADLINE,
+                frame_system::RawOrigin::Root.into(),
+                Bounded::Inline(call),
+            );
+        }
+
+        /// Drop the scheduled timeout of `escrow_id`, if there is one.
+        fn cancel_timeout(escrow_id: EscrowId) {
+            let _ = T::Scheduler::cancel_named(Self::timeout_task(escrow_id));
+        }
+
+        /// Mark the rest of the escrow as released; returns that rest.
+        fn take_remaining(
+            escrow: &mut AssignmentEscrow<T::AccountId, T::Balance, BlockNumberFor<T>>,
+        ) -> T::Balance {
+            let rest = escrow.amount.saturating_sub(escrow.released_so_far);
+            escrow.released_so_far = escrow.amount;
+            rest
+        }
+
         /// Helper to get next EscrowId and increment the counter.
         fn next_id() -> EscrowId {
             let id = NextEscrowId::<T>::get();
@@ -176,41 +516,14 @@ pub mod pallet {
             id
         }
 
-        /// Returns true if status is already final: no more transitions allowed.
-        fn is_final_status(status: &DeliveryStatus) -> bool {
-            matches!(
-                status,
-                DeliveryStatus::ConfirmedByReceiver
-                    | DeliveryStatus::TimeoutReleased
-                    | DeliveryStatus::Cancelled
-                    | DeliveryStatus::Failed
-            )
-        }
-    }
-
-    // -------- Calls --------
-
-    #[pallet::call]
-    impl<T: Config> Pallet<T> {
-        /// Create a new escrow entry for a given (request, offer).
-        ///
-        /// In practice this should be called by a trusted backend / authority
-        /// right after PoBA finalize and DB assignment creation.
-        ///
-        /// NOTE: For now we only require a signed origin; origin-level
-        /// authorization policy (which account is allowed to call) can be
-        /// tightened later if needed.
-        #[pallet::weight(10_000)]
-        pub fn create_escrow(
-            origin: OriginFor<T>,
+        /// Create the escrow record and index it by request.
+        fn do_create_escrow(
             request_uuid: RequestUuid,
             offer_uuid:   OfferUuid,
             driver:       T::AccountId,
             payer:        T::AccountId,
             amount:       T::Balance,
         ) -> DispatchResult {
-            let _who = ensure_signed(origin)?;
-
             // Prevent multiple active escrows for same request.
             ensure!(
                 RequestToEscrow::<T>::get(&request_uuid).is_none(),
@@ -222,7 +535,8 @@ pub mod pallet {
 
             // Use runtime block number type
             let now: BlockNumberFor<T> = frame_system::Pallet::<T>::block_number();
-            let deadline = now + T::ConfirmationTimeoutBlocks::get();
+            // Saturate instead of overflowing on very large block numbers.
+            let deadline = now.saturating_add(T::ConfirmationTimeoutBlocks::get());
 
             let escrow_id = Self::next_id();
 
@@ -236,6 +550,8 @@ pub mod pallet {
                 driver: driver.clone(),
                 payer: payer.clone(),
                 amount,
+                released_so_far: Zero::zero(),
+                bonus_top_up: Permill::from_rational(T::EarlyBonusBps::get(), 10_000u32) * amount,
                 status: DeliveryStatus::Created,
                 created_at: now,
                 deadline,
@@ -243,6 +559,10 @@ pub mod pallet {
 
             Escrows::<T>::insert(escrow_id, record);
             RequestToEscrow::<T>::insert(request_uuid, escrow_id);
+            // A zero timeout is already due: keepers release it right away.
+            if deadline > now {
+                Self::schedule_timeout(escrow_id, deadline);
+            }
 
             Self::deposit_event(Event::EscrowCreated {
                 escrow_id,
@@ -257,6 +577,147 @@ pub mod pallet {
             Ok(())
         }
 
+        /// Create the escrow for one match of a finalized PoBA slot.
+        ///
+        /// Never fails: matches that cannot get an escrow are reported with
+        /// `MatchEscrowSkipped` so the rest of the slot still goes through.
+        pub fn create_for_match(
+            slot: u64,
+            request_uuid: RequestUuid,
+            offer_uuid: OfferUuid,
+            amount: T::Balance,
+        ) {
+            let skip = |reason| {
+                Self::deposit_event(Event::MatchEscrowSkipped {
+                    slot,
+                    request_uuid,
+                    offer_uuid,
+                    reason,
+                })
+            };
+
+            if RequestToEscrow::<T>::contains_key(&request_uuid) {
+                return skip(MatchSkipReason::RequestAlreadyAssigned);
+            }
+            if amount.is_zero() {
+                return skip(MatchSkipReason::ZeroAmount);
+            }
+            let Some((driver, payer)) = T::MatchParties::parties(&request_uuid, &offer_uuid) else {
+                return skip(MatchSkipReason::UnknownParties);
+            };
+
+            // Checked above, so creation cannot fail.
+            let _ = Self::do_create_escrow(request_uuid, offer_uuid, driver, payer, amount);
+        }
+
+        /// Flag the escrow of a request whose PoBA finalization in `slot` was
+        /// revoked. Requests without an escrow are ignored.
+        pub fn flag_revoked_match(slot: u64, request_uuid: RequestUuid) {
+            if let Some(escrow_id) = RequestToEscrow::<T>::get(request_uuid) {
+                FlaggedEscrows::<T>::insert(escrow_id, slot);
+                Self::deposit_event(Event::EscrowFlagged { escrow_id, slot });
+            }
+        }
+
+        /// Average stars of `driver` in hundredths (e.g. 450 = 4.5), if rated.
+        pub fn average_rating(driver: &T::AccountId) -> Option<u64> {
+            let (sum, count) = DriverRatings::<T>::get(driver);
+            if count == 0 {
+                return None;
+            }
+            Some(sum.saturating_mul(100) / u64::from(count))
+        }
+
+        /// Returns true if status is already final: no more transitions allowed.
+        fn is_final_status(status: &DeliveryStatus) -> bool {
+            status.is_final()
+        }
+
+        /// Apply `action` to `escrow`'s status, or fail with
+        /// `InvalidStatusTransition`.
+        fn transition(
+            escrow: &mut AssignmentEscrow<T::AccountId, T::Balance, BlockNumberFor<T>>,
+            action: EscrowAction,
+        ) -> DispatchResult {
+            escrow.status =
+                action.apply(&escrow.status).ok_or(Error::<T>::InvalidStatusTransition)?;
+            Ok(())
+        }
+
+        /// Statuses `role` can move escrow `escrow_id` to from its current one
+        /// (empty for unknown or final escrows). Deadlines are not checked.
+        pub fn allowed_transitions(escrow_id: EscrowId, role: EscrowRole) -> Vec<DeliveryStatus> {
+            let Some(escrow) = Escrows::<T>::get(escrow_id) else {
+                return Vec::new();
+            };
+            let mut next = Vec::new();
+            for action in EscrowAction::ALL.into_iter().filter(|a| a.role() == role) {
+                if let Some(status) = action.apply(&escrow.status) {
+                    if !next.contains(&status) {
+                        next.push(status);
+                    }
+                }
+            }
+            next
+        }
+
+        /// Whether `who` may send `call` without a transaction fee: a courier
+        /// lifecycle call (`mark_picked_up`, `mark_delivered`,
+        /// `confirm_received`) by the escrow's own driver/payer, whose
+        /// transition is allowed from the current status. Anything else pays.
+        pub fn is_feeless(who: &T::AccountId, call: &Call<T>) -> bool {
+            let (escrow_id, action) = match call {
+                Call::mark_picked_up { escrow_id } => (escrow_id, EscrowAction::PickUp),
+                Call::mark_delivered { escrow_id } => (escrow_id, EscrowAction::Deliver),
+                Call::confirm_received { escrow_id } => (escrow_id, EscrowAction::Confirm),
+                _ => return false,
+            };
+            let Some(escrow) = Escrows::<T>::get(escrow_id) else {
+                return false;
+            };
+            let party = match action.role() {
+                EscrowRole::Driver => &escrow.driver,
+                EscrowRole::Payer => &escrow.payer,
+                EscrowRole::Admin | EscrowRole::Anyone => return false,
+            };
+            party == who && action.apply(&escrow.status).is_some()
+        }
+    }
+
+    // -------- Calls --------
+
+    #[pallet::call]
+    impl<T: Config> Pallet<T> {
+        /// Create a new escrow entry for a given (request, offer).
+        ///
+        /// In practice this should be called by a trusted backend / authority
+        /// right after PoBA finalize and DB assignment creation.
+        ///
+        /// NOTE: For now we only require a signed origin; origin-level
+        /// authorization policy (which account is allowed to call) can be
+        /// tightened later if needed.
+        #[pallet::weight(10_000)]
+        pub fn create_escrow(
+            origin: OriginFor<T>,
+            request_uuid: RequestUuid,
+            offer_uuid:   OfferUuid,
+            driver:       T::AccountId,
+            payer:        T::AccountId,
+            amount:       T::Balance,
+        ) -> DispatchResult {
+            let _who = ensure_signed(origin)?;
+
+            if T::RequireMarketConsistency::get() {
+                ensure!(
+                    T::MatchParties::parties(&request_uuid, &offer_uuid) ==
+                        Some((driver.clone(), payer.clone())),
+                    Error::<T>::MarketMismatch
+                );
+            }
+
+            Self::do_create_escrow(request_uuid, offer_uuid, driver, payer, amount)
+        }
+
         /// Courier marks pickup.
         #[pallet::weight(10_000)]
         pub fn mark_picked_up(
@@ -265,7 +726,7 @@ pub mod pallet {
         ) -> DispatchResult {
             let who = ensure_signed(origin)?;
 
-            Escrows::<T>::try_mutate(escrow_id, |maybe| -> DispatchResult {
+            let amount = Escrows::<T>::try_mutate(escrow_id, |maybe| -> Result<_, DispatchError> {
                 let escrow = maybe.as_mut().ok_or(Error::<T>::EscrowNotFound)?;
 
                 // Final states cannot be modified anymore.
@@ -275,17 +736,12 @@ pub mod pallet {
                 ensure!(who == escrow.driver, Error::<T>::NotDriver);
 
                 // Valid transition: Created -> PickedUpByCourier only.
-                match escrow.status {
-                    DeliveryStatus::Created => {
-                        escrow.status = DeliveryStatus::PickedUpByCourier;
-                    }
-                    _ => return Err(Error::<T>::InvalidStatusTransition.into()),
-                }
-
-                Ok(())
+                Self::transition(escrow, EscrowAction::PickUp)?;
+                Ok(escrow.amount)
             })?;
 
-            Self::deposit_event(Event::PickedUp { escrow_id });
+            let at = frame_system::Pallet::<T>::block_number();
+            Self::deposit_event(Event::PickedUp { escrow_id, driver: who, amount, at });
             Ok(())
         }
 
@@ -297,24 +753,28 @@ pub mod pallet {
         ) -> DispatchResult {
             let who = ensure_signed(origin)?;
 
-            Escrows::<T>::try_mutate(escrow_id, |maybe| -> DispatchResult {
-                let escrow = maybe.as_mut().ok_or(Error::<T>::EscrowNotFound)?;
-
-                ensure!(!Self::is_final_status(&escrow.status), Error::<T>::EscrowAlreadyFinal);
-                ensure!(who == escrow.driver, Error::<T>::NotDriver);
-
-                // Valid transition: PickedUpByCourier -> DeliveredByCourier.
-                match escrow.status {
-                    DeliveryStatus::PickedUpByCourier => {
-                        escrow.status = DeliveryStatus::DeliveredByCourier;
-                    }
-                    _ => return Err(Error::<T>::InvalidStatusTransition.into()),
-                }
-
-                Ok(())
-            })?;
-
-            Self::deposit_event(Event::Delivered { escrow_id });
+            let (amount, deadline) =
+                Escrows::<T>::try_mutate(escrow_id, |maybe| -> Result<_, DispatchError> {
+                    let escrow = maybe.as_mut().ok_or(Error::<T>::EscrowNotFound)?;
+
+                    ensure!(
+                        !Self::is_final_status(&escrow.status),
+                        Error::<T>::EscrowAlreadyFinal
+                    );
+                    ensure!(who == escrow.driver, Error::<T>::NotDriver);
+
+                    // Valid transition: PickedUpByCourier -> DeliveredByCourier.
+                    Self::transition(escrow, EscrowAction::Deliver)?;
+                    Ok((escrow.amount, escrow.deadline))
+                })?;
+
+            let at = frame_system::Pallet::<T>::block_number();
+            // Scheduled at creation; retried here in case that failed (a task
+            // still scheduled is kept as is).
+            if deadline > at {
+                Self::schedule_timeout(escrow_id, deadline);
+            }
+            Self::deposit_event(Event::Delivered { escrow_id, driver: who, amount, at });
             Ok(())
         }
 
@@ -330,6 +790,8 @@ pub mod pallet {
             let who = ensure_signed(origin)?;
 
             let mut amount_to_release: T::Balance = T::Balance::from(0u32);
+            let mut bonus: T::Balance = Zero::zero();
+            let now: BlockNumberFor<T> = frame_system::Pallet::<T>::block_number();
 
             Escrows::<T>::try_mutate(escrow_id, |maybe| -> DispatchResult {
                 let escrow = maybe.as_mut().ok_or(Error::<T>::EscrowNotFound)?;
@@ -338,27 +800,79 @@ pub mod pallet {
                 ensure!(who == escrow.payer, Error::<T>::NotPayer);
 
                 // Valid transition: DeliveredByCourier -> ConfirmedByReceiver.
-                match escrow.status {
-                    DeliveryStatus::DeliveredByCourier => {
-                        escrow.status = DeliveryStatus::ConfirmedByReceiver;
-                    }
-                    _ => return Err(Error::<T>::InvalidStatusTransition.into()),
-                }
+                Self::transition(escrow, EscrowAction::Confirm)?;
 
-                amount_to_release = escrow.amount;
+                amount_to_release = Self::take_remaining(escrow);
+                // Late confirmations leave the top-up with the payer.
+                if now < escrow.deadline.saturating_sub(T::EarlyBonusMarginBlocks::get()) {
+                    bonus = escrow.bonus_top_up;
+                }
 
                 Ok(())
             })?;
+            Self::cancel_timeout(escrow_id);
 
             Self::deposit_event(Event::PaymentReleased {
                 escrow_id,
                 amount: amount_to_release,
             });
+            if !bonus.is_zero() {
+                Self::deposit_event(Event::EarlyBonusReleased { escrow_id, bonus });
+            }
             Self::deposit_event(Event::ReceiverConfirmed { escrow_id });
 
             Ok(())
         }
 
+        /// Payer releases part of the escrow to the driver (split deliveries,
+        /// e.g. one share at pickup and the rest at confirmation).
+        ///
+        /// Releasing the last of the amount confirms the escrow like
+        /// `confirm_received`; later full releases only pay what is left.
+        #[pallet::weight(10_000)]
+        pub fn release_partial(
+            origin: OriginFor<T>,
+            escrow_id: EscrowId,
+            amount: T::Balance,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+            ensure!(!amount.is_zero(), Error::<T>::ZeroAmountNotAllowed);
+
+            let (released_so_far, fully_released) =
+                Escrows::<T>::try_mutate(escrow_id, |maybe| -> Result<_, DispatchError> {
+                    let escrow = maybe.as_mut().ok_or(Error::<T>::EscrowNotFound)?;
+
+                    ensure!(
+                        !Self::is_final_status(&escrow.status),
+                        Error::<T>::EscrowAlreadyFinal
+                    );
+                    ensure!(who == escrow.payer, Error::<T>::NotPayer);
+
+                    let remaining = escrow.amount.saturating_sub(escrow.released_so_far);
+                    ensure!(amount <= remaining, Error::<T>::AmountExceedsRemaining);
+
+                    escrow.released_so_far = escrow.released_so_far.saturating_add(amount);
+                    let fully_released = escrow.released_so_far == escrow.amount;
+                    if fully_released {
+                        Self::transition(escrow, EscrowAction::ReleasePartial)?;
+                    }
+
+                    Ok((escrow.released_so_far, fully_released))
+                })?;
+
+            Self::deposit_event(Event::PartialPaymentReleased {
+                escrow_id,
+                amount,
+                released_so_far,
+            });
+            if fully_released {
+                Self::cancel_timeout(escrow_id);
+                Self::deposit_event(Event::ReceiverConfirmed { escrow_id });
+            }
+
+            Ok(())
+        }
+
         /// Backend-style release based on (request_uuid, offer_uuid).
         ///
         /// This is the extrinsic that the backend calls from
@@ -369,6 +883,7 @@ pub mod pallet {
         /// It:
         ///   - Locates the escrow via `RequestToEscrow`.
         ///   - Verifies the stored offer_uuid matches.
+        ///   - Rejects escrows flagged by a revoked PoBA finalization.
         ///   - Ensures the escrow is not in a final status.
         ///   - Sets status = ConfirmedByReceiver.
         ///   - Emits PaymentReleased + ReceiverConfirmed.
@@ -389,7 +904,14 @@ pub mod pallet {
                 let escrow = maybe.as_mut().ok_or(Error::<T>::EscrowNotFound)?;
 
                 // Sanity: ensure the offer matches the one we expect.
-                ensure!(escrow.offer_uuid == offer_uuid, Error::<T>::EscrowNotFound);
+                ensure!(escrow.offer_uuid == offer_uuid, Error::<T>::OfferMismatch);
+
+                // The offer is no longer legitimately matched once its slot
+                // finalization was revoked.
+                ensure!(
+                    !FlaggedEscrows::<T>::contains_key(escrow_id),
+                    Error::<T>::OfferNoLongerActive
+                );
 
                 // Do not allow double release or further transitions from final states.
                 ensure!(
@@ -398,11 +920,12 @@ pub mod pallet {
                 );
 
                 // Mark as confirmed by receiver and prepare amount for the event.
-                escrow.status = DeliveryStatus::ConfirmedByReceiver;
-                amount_to_release = escrow.amount;
+                Self::transition(escrow, EscrowAction::Release)?;
+                amount_to_release = Self::take_remaining(escrow);
 
                 Ok(())
             })?;
+            Self::cancel_timeout(escrow_id);
 
             Self::deposit_event(Event::PaymentReleased {
                 escrow_id,
@@ -413,38 +936,127 @@ pub mod pallet {
             Ok(())
         }
 
-        /// Force payment release after timeout if receiver did not confirm.
+        /// Settle an escrow after timeout if receiver did not confirm.
+        ///
+        /// Delivered escrows are released to the driver; escrows still
+        /// `Created` or `PickedUpByCourier` are refunded to the payer and fail.
         ///
         /// Can be called by anyone; the on-chain guard is purely by block number
-        /// and current status.
+        /// and current status. Every escrow also gets it dispatched as `Root`
+        /// by `Scheduler` at its deadline.
         #[pallet::weight(10_000)]
         pub fn force_timeout_release(
             origin: OriginFor<T>,
             escrow_id: EscrowId,
+        ) -> DispatchResult {
+            let who = frame_system::ensure_signed_or_root(origin)?;
+
+            let now: BlockNumberFor<T> = frame_system::Pallet::<T>::block_number();
+            let (amount, refunded) = Self::timeout_release(escrow_id, now)?;
+            // As `Root` this is the scheduled task itself, which is not
+            // cancellable while it runs.
+            if who.is_some() {
+                Self::cancel_timeout(escrow_id);
+            }
+
+            if refunded {
+                Self::deposit_event(Event::PaymentRefunded { escrow_id, amount });
+            } else {
+                Self::deposit_event(Event::PaymentReleased { escrow_id, amount });
+            }
+
+            Ok(())
+        }
+
+        /// Payer rates the driver of a confirmed escrow, once per escrow.
+        #[pallet::weight(10_000)]
+        pub fn submit_rating(
+            origin: OriginFor<T>,
+            escrow_id: EscrowId,
+            stars: u8,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+            ensure!((1..=MAX_STARS).contains(&stars), Error::<T>::InvalidRating);
+
+            let escrow = Escrows::<T>::get(escrow_id).ok_or(Error::<T>::EscrowNotFound)?;
+            ensure!(who == escrow.payer, Error::<T>::NotPayer);
+            ensure!(
+                escrow.status == DeliveryStatus::ConfirmedByReceiver,
+                Error::<T>::InvalidStatusTransition
+            );
+            ensure!(!RatedEscrows::<T>::contains_key(escrow_id), Error::<T>::AlreadyRated);
+
+            RatedEscrows::<T>::insert(escrow_id, ());
+            DriverRatings::<T>::mutate(&escrow.driver, |(sum, count)| {
+                *sum = sum.saturating_add(u64::from(stars));
+                *count = count.saturating_add(1);
+            });
+
+            Self::deposit_event(Event::RatingSubmitted { driver: escrow.driver, stars });
+            Ok(())
+        }
+
+        /// Timeout-release several escrows in one call (keeper bots).
+        ///
+        /// Ids that are unknown, not yet due or already final are skipped;
+        /// one `BatchTimeoutReleased` lists the ids actually released or
+        /// refunded, as in `force_timeout_release`.
+        #[pallet::weight(10_000u64.saturating_add(5_000u64.saturating_mul(ids.len() as u64)))]
+        pub fn force_timeout_release_batch(
+            origin: OriginFor<T>,
+            ids: Vec<EscrowId>,
         ) -> DispatchResult {
             let _who = ensure_signed(origin)?;
+            ensure!(ids.len() as u32 <= T::MaxBatchSize::get(), Error::<T>::BatchTooLarge);
 
-            let mut amount_to_release: T::Balance = T::Balance::from(0u32);
             let now: BlockNumberFor<T> = frame_system::Pallet::<T>::block_number();
+            let (mut released, mut refunded) = (Vec::new(), Vec::new());
+            for id in ids {
+                match Self::timeout_release(id, now) {
+                    Ok((_, was_refunded)) => {
+                        Self::cancel_timeout(id);
+                        if was_refunded {
+                            refunded.push(id);
+                        } else {
+                            released.push(id);
+                        }
+                    },
+                    Err(_) => {},
+                }
+            }
 
-            Escrows::<T>::try_mutate(escrow_id, |maybe| -> DispatchResult {
-                let escrow = maybe.as_mut().ok_or(Error::<T>::EscrowNotFound)?;
+            Self::deposit_event(Event::BatchTimeoutReleased { released, refunded });
 
-                ensure!(!Self::is_final_status(&escrow.status), Error::<T>::EscrowAlreadyFinal);
+            Ok(())
+        }
 
-                // Only allow timeout if current block >= deadline.
-                ensure!(now >= escrow.deadline, Error::<T>::TimeoutNotReached);
+        /// Void the escrow of `request_uuid` (incident recovery).
+        ///
+        /// The escrow moves to `Failed` and the request is unlinked, so a new
+        /// escrow can be created for it. Nothing is refunded on-chain: escrow
+        /// here is logical and the backend reverses the payment itself.
+        #[pallet::weight(10_000)]
+        pub fn admin_void_request(
+            origin: OriginFor<T>,
+            request_uuid: RequestUuid,
+        ) -> DispatchResult {
+            T::AdminOrigin::ensure_origin(origin)?;
 
-                escrow.status = DeliveryStatus::TimeoutReleased;
-                amount_to_release = escrow.amount;
+            let escrow_id =
+                RequestToEscrow::<T>::get(&request_uuid).ok_or(Error::<T>::EscrowNotFound)?;
 
-                Ok(())
+            Escrows::<T>::try_mutate(escrow_id, |maybe| -> DispatchResult {
+                let escrow = maybe.as_mut().ok_or(Error::<T>::EscrowNotFound)?;
+                ensure!(
+                    !Self::is_final_status(&escrow.status),
+                    Error::<T>::EscrowAlreadyFinal
+                );
+                Self::transition(escrow, EscrowAction::Void)
             })?;
+            RequestToEscrow::<T>::remove(&request_uuid);
+            Self::cancel_timeout(escrow_id);
 
-            Self::deposit_event(Event::PaymentReleased {
-                escrow_id,
-                amount: amount_to_release,
-            });
+            Self::deposit_event(Event::EscrowVoided { escrow_id, request_uuid });
 
             Ok(())
         }
@@ -453,3 +1065,25 @@ pub mod pallet {
 
 // Re-export for `impl pallet_escrow::Config for Runtime`
 pub use pallet::*;
+
+// --------------------------- Runtime API ---------------------------
+
+sp_api::decl_runtime_apis! {
+    /// Read access to current escrow state, so clients do not have to
+    /// rebuild it from events.
+    pub trait EscrowApi<AccountId, Balance, BlockNumber>
+    where
+        AccountId: codec::Codec,
+        Balance: codec::Codec,
+        BlockNumber: codec::Codec,
+    {
+        /// The escrow record stored under `escrow_id`, if any.
+        fn escrow(escrow_id: EscrowId) -> Option<AssignmentEscrow<AccountId, Balance, BlockNumber>>;
+
+        /// The escrow created for `request_uuid`, if any.
+        fn escrow_for_request(request_uuid: RequestUuid) -> Option<EscrowId>;
+
+        /// Statuses `role` can move `escrow_id` to from its current status.
+        fn allowed_transitions(escrow_id: EscrowId, role: EscrowRole) -> Vec<DeliveryStatus>;
+    }
+}
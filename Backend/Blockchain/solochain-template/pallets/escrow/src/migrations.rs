@@ -0,0 +1,80 @@
+//! Storage migrations of the escrow pallet.
+
+use super::*;
+use frame_support::{migrations::VersionedMigration, traits::UncheckedOnRuntimeUpgrade};
+
+/// v1: `AssignmentEscrow` gained `released_so_far` and `bonus_top_up`.
+pub mod v1 {
+    use super::*;
+
+    /// `AssignmentEscrow` as stored before v1.
+    #[derive(Decode)]
+    pub struct OldAssignmentEscrow<AccountId, Balance, BlockNumber> {
+        pub request_uuid: RequestUuid,
+        pub offer_uuid:   OfferUuid,
+        pub driver:       AccountId,
+        pub payer:        AccountId,
+        pub amount:       Balance,
+        pub status:       DeliveryStatus,
+        pub created_at:   BlockNumber,
+        pub deadline:     BlockNumber,
+    }
+
+    impl<AccountId, Balance: Zero, BlockNumber> OldAssignmentEscrow<AccountId, Balance, BlockNumber> {
+        /// Nothing was released early and no bonus was topped up before v1.
+        fn upgrade(self) -> AssignmentEscrow<AccountId, Balance, BlockNumber> {
+            AssignmentEscrow {
+                request_uuid: self.request_uuid,
+                offer_uuid: self.offer_uuid,
+                driver: self.driver,
+                payer: self.payer,
+                amount: self.amount,
+                released_so_far: Zero::zero(),
+                bonus_top_up: Zero::zero(),
+                status: self.status,
+                created_at: self.created_at,
+                deadline: self.deadline,
+            }
+        }
+    }
+
+    /// Rewrite every stored escrow in the v1 layout.
+    pub struct UncheckedMigrateToV1<T>(core::marker::PhantomData<T>);
+
+    impl<T: Config> UncheckedOnRuntimeUpgrade for UncheckedMigrateToV1<T> {
+        fn on_runtime_upgrade() -> Weight {
+            let mut translated = 0u64;
+            Escrows::<T>::translate::<
+                OldAssignmentEscrow<T::AccountId, T::Balance, BlockNumberFor<T>>,
+                _,
+            >(|_, old| {
+                translated.saturating_inc();
+                Some(old.upgrade())
+            });
+            T::DbWeight::get().reads_writes(translated, translated)
+        }
+
+        #[cfg(feature = "try-runtime")]
+        fn pre_upgrade() -> Result<Vec<u8>, sp_runtime::TryRuntimeError> {
+            Ok((Escrows::<T>::iter_keys().count() as u64).encode())
+        }
+
+        #[cfg(feature = "try-runtime")]
+        fn post_upgrade(state: Vec<u8>) -> Result<(), sp_runtime::TryRuntimeError> {
+            let before = u64::decode(&mut &state[..]).map_err(|_| "bad pre_upgrade state")?;
+            // `iter_values` skips entries that do not decode in the new layout.
+            let after = Escrows::<T>::iter_values().count() as u64;
+            ensure!(before == after, "escrows lost by the v1 migration");
+            Ok(())
+        }
+    }
+
+    /// [`UncheckedMigrateToV1`], run only while the on-chain version is 0.
+    pub type MigrateV0ToV1<T> = VersionedMigration<
+        0,
+        1,
+        UncheckedMigrateToV1<T>,
+        Pallet<T>,
+        <T as frame_system::Config>::DbWeight,
+    >;
+}
@@ -0,0 +1,131 @@
+use crate as pallet_escrow;
+use frame_support::{
+    derive_impl, parameter_types,
+    traits::{ConstU32, EqualPrivilegeOnly, Hooks},
+    weights::Weight,
+};
+use sp_runtime::BuildStorage;
+
+type Block = frame_system::mocking::MockBlock<Test>;
+
+#[frame_support::runtime]
+mod runtime {
+    // The main runtime
+    #[runtime::runtime]
+    // Runtime Types to be generated
+    #[runtime::derive(
+        RuntimeCall,
+        RuntimeEvent,
+        RuntimeError,
+        RuntimeOrigin,
+        RuntimeFreezeReason,
+        RuntimeHoldReason,
+        RuntimeSlashReason,
+        RuntimeLockId,
+        RuntimeTask,
+        RuntimeViewFunction
+    )]
+    pub struct Test;
+
+    #[runtime::pallet_index(0)]
+    pub type System = frame_system::Pallet<Test>;
+
+    #[runtime::pallet_index(1)]
+    pub type Escrow = pallet_escrow::Pallet<Test>;
+
+    #[runtime::pallet_index(2)]
+    pub type Scheduler = pallet_scheduler::Pallet<Test>;
+}
+
+#[derive_impl(frame_system::config_preludes::TestDefaultConfig)]
+impl frame_system::Config for Test {
+    type Block = Block;
+}
+
+parameter_types! {
+    /// Room for every timeout scheduled in a test block.
+    pub MaximumSchedulerWeight: Weight = Weight::from_parts(1_000_000_000_000, u64::MAX);
+}
+
+impl pallet_scheduler::Config for Test {
+    type RuntimeEvent = RuntimeEvent;
+    type RuntimeOrigin = RuntimeOrigin;
+    type PalletsOrigin = OriginCaller;
+    type RuntimeCall = RuntimeCall;
+    type MaximumWeight = MaximumSchedulerWeight;
+    type ScheduleOrigin = frame_system::EnsureRoot<u64>;
+    type MaxScheduledPerBlock = ConstU32<16>;
+    type WeightInfo = ();
+    type OriginPrivilegeCmp = EqualPrivilegeOnly;
+    type Preimages = ();
+}
+
+/// Escrow ids accepted by one `force_timeout_release_batch`.
+pub const MAX_BATCH: u32 = 4;
+/// Blocks until an escrow can be released by timeout.
+pub const TIMEOUT: u64 = 10;
+
+parameter_types! {
+    /// Confirmation timeout; `TIMEOUT` unless a test overrides it.
+    pub static ConfirmationTimeout: u64 = TIMEOUT;
+    /// Early-delivery bonus: 5% of the amount.
+    pub static EarlyBonusBps: u32 = 500;
+    /// Confirmations need this many blocks to spare for the bonus.
+    pub static EarlyBonusMargin: u64 = 4;
+    /// Market consistency check in `create_escrow`; off unless a test sets it.
+    pub static RequireMarketConsistency: bool = false;
+}
+
+impl pallet_escrow::Config for Test {
+    type RuntimeEvent = RuntimeEvent;
+    type Balance = u64;
+    type ConfirmationTimeoutBlocks = ConfirmationTimeout;
+    type MatchParties = TestMatchParties;
+    type MaxBatchSize = ConstU32<MAX_BATCH>;
+    type EarlyBonusBps = EarlyBonusBps;
+    type EarlyBonusMarginBlocks = EarlyBonusMargin;
+    type AdminOrigin = frame_system::EnsureRoot<u64>;
+    type RequireMarketConsistency = RequireMarketConsistency;
+    type RuntimeCall = RuntimeCall;
+    type PalletsOrigin = OriginCaller;
+    type Scheduler = Scheduler;
+}
+
+/// Market known to `MatchParties`: requests `uuid(b)` with `b < UNKNOWN`,
+/// any offer, all driven by `DRIVER` and paid by `PAYER`.
+pub struct TestMatchParties;
+
+/// First request uuid byte the market does not know.
+pub const UNKNOWN: u8 = 100;
+
+impl pallet_escrow::MatchParties<u64> for TestMatchParties {
+    fn parties(request: &[u8; 16], _offer: &[u8; 16]) -> Option<(u64, u64)> {
+        (request[0] < UNKNOWN).then_some((DRIVER, PAYER))
+    }
+}
+
+pub const DRIVER: u64 = 1;
+pub const PAYER: u64 = 2;
+pub const BACKEND: u64 = 3;
+
+/// 16-byte UUID filled with `b`.
+pub fn uuid(b: u8) -> [u8; 16] {
+    [b; 16]
+}
+
+/// Advance to block `n`, running the scheduler in each new block.
+pub fn run_to_block(n: u64) {
+    while System::block_number() < n {
+        let next = System::block_number() + 1;
+        System::set_block_number(next);
+        Scheduler::on_initialize(next);
+    }
+}
+
+// Build genesis storage according to the mock runtime.
+pub fn new_test_ext() -> sp_io::TestExternalities {
+    let t = frame_system::GenesisConfig::<Test>::default().build_storage().unwrap();
+    let mut ext = sp_io::TestExternalities::new(t);
+    ext.execute_with(|| System::set_block_number(1));
+    ext
+}
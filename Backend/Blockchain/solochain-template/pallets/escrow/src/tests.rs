@@ -0,0 +1,730 @@
+use crate::{mock::*, AssignmentEscrow, DeliveryStatus, EscrowRole, Error, Event, MAX_STARS};
+use frame_support::{
+    assert_noop, assert_ok,
+    traits::{schedule::v3::Named, OnRuntimeUpgrade, StorageVersion},
+};
+
+#[test]
+fn created_escrow_is_readable_by_id_and_request() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(Escrow::create_escrow(
+            RuntimeOrigin::signed(BACKEND),
+            uuid(1),
+            uuid(2),
+            DRIVER,
+            PAYER,
+            500
+        ));
+
+        // Same getters back the EscrowApi runtime API.
+        assert_eq!(Escrow::request_to_escrow(uuid(1)), Some(0));
+        assert_eq!(
+            Escrow::escrows(0),
+            Some(AssignmentEscrow {
+                request_uuid: uuid(1),
+                offer_uuid: uuid(2),
+                driver: DRIVER,
+                payer: PAYER,
+                amount: 500,
+                released_so_far: 0,
+                bonus_top_up: 25,
+                status: DeliveryStatus::Created,
+                created_at: 1,
+                deadline: 1 + TIMEOUT,
+            })
+        );
+        assert_eq!(Escrow::escrows(1), None);
+        assert_eq!(Escrow::request_to_escrow(uuid(9)), None);
+    });
+}
+
+#[test]
+fn deadline_saturates_near_max_block_number() {
+    new_test_ext().execute_with(|| {
+        ConfirmationTimeout::set(u64::MAX / 2);
+        System::set_block_number(u64::MAX - 5);
+
+        assert_ok!(Escrow::create_escrow(
+            RuntimeOrigin::signed(BACKEND),
+            uuid(1),
+            uuid(2),
+            DRIVER,
+            PAYER,
+            500
+        ));
+        assert_eq!(Escrow::escrows(0).unwrap().deadline, u64::MAX);
+    });
+}
+
+#[test]
+fn batch_timeout_release_skips_ineligible_escrows() {
+    new_test_ext().execute_with(|| {
+        // 0 and 1 fall due at 1 + TIMEOUT; 2 is created later.
+        for b in 1..=2 {
+            assert_ok!(Escrow::create_escrow(
+                RuntimeOrigin::signed(BACKEND),
+                uuid(b),
+                uuid(b),
+                DRIVER,
+                PAYER,
+                100
+            ));
+        }
+        System::set_block_number(5);
+        assert_ok!(Escrow::create_escrow(
+            RuntimeOrigin::signed(BACKEND),
+            uuid(3),
+            uuid(3),
+            DRIVER,
+            PAYER,
+            100
+        ));
+
+        System::set_block_number(1 + TIMEOUT);
+        // 1 is already final before the batch runs.
+        assert_ok!(Escrow::force_timeout_release(RuntimeOrigin::signed(BACKEND), 1));
+
+        // 0 due, 1 final, 2 not yet due, 7 unknown.
+        assert_ok!(Escrow::force_timeout_release_batch(
+            RuntimeOrigin::signed(BACKEND),
+            vec![0, 1, 2, 7]
+        ));
+        // 0 was never delivered, so it is refunded.
+        System::assert_last_event(
+            Event::BatchTimeoutReleased { released: vec![], refunded: vec![0] }.into(),
+        );
+
+        assert_eq!(Escrow::escrows(0).unwrap().status, DeliveryStatus::Failed);
+        assert_eq!(Escrow::escrows(2).unwrap().status, DeliveryStatus::Created);
+    });
+}
+
+#[test]
+fn batch_timeout_release_is_bounded() {
+    new_test_ext().execute_with(|| {
+        let ids: Vec<_> = (0..MAX_BATCH as u64 + 1).collect();
+        assert_noop!(
+            Escrow::force_timeout_release_batch(RuntimeOrigin::signed(BACKEND), ids),
+            Error::<Test>::BatchTooLarge
+        );
+    });
+}
+
+#[test]
+fn partial_releases_accumulate_until_fully_released() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(Escrow::create_escrow(
+            RuntimeOrigin::signed(BACKEND),
+            uuid(1),
+            uuid(2),
+            DRIVER,
+            PAYER,
+            500
+        ));
+
+        // Pickup share.
+        assert_noop!(
+            Escrow::release_partial(RuntimeOrigin::signed(DRIVER), 0, 200),
+            Error::<Test>::NotPayer
+        );
+        assert_ok!(Escrow::release_partial(RuntimeOrigin::signed(PAYER), 0, 200));
+        System::assert_last_event(
+            Event::PartialPaymentReleased { escrow_id: 0, amount: 200, released_so_far: 200 }
+                .into(),
+        );
+        let escrow = Escrow::escrows(0).unwrap();
+        assert_eq!(escrow.released_so_far, 200);
+        assert_eq!(escrow.status, DeliveryStatus::Created);
+
+        assert_noop!(
+            Escrow::release_partial(RuntimeOrigin::signed(PAYER), 0, 301),
+            Error::<Test>::AmountExceedsRemaining
+        );
+
+        // Confirmation share closes the escrow.
+        assert_ok!(Escrow::release_partial(RuntimeOrigin::signed(PAYER), 0, 300));
+        System::assert_has_event(
+            Event::PartialPaymentReleased { escrow_id: 0, amount: 300, released_so_far: 500 }
+                .into(),
+        );
+        System::assert_last_event(Event::ReceiverConfirmed { escrow_id: 0 }.into());
+        let escrow = Escrow::escrows(0).unwrap();
+        assert_eq!(escrow.released_so_far, 500);
+        assert_eq!(escrow.status, DeliveryStatus::ConfirmedByReceiver);
+
+        assert_noop!(
+            Escrow::release_partial(RuntimeOrigin::signed(PAYER), 0, 1),
+            Error::<Test>::EscrowAlreadyFinal
+        );
+    });
+}
+
+#[test]
+fn timeout_after_partial_release_pays_only_the_rest() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(Escrow::create_escrow(
+            RuntimeOrigin::signed(BACKEND),
+            uuid(1),
+            uuid(2),
+            DRIVER,
+            PAYER,
+            500
+        ));
+        assert_ok!(Escrow::release_partial(RuntimeOrigin::signed(PAYER), 0, 200));
+        assert_ok!(Escrow::mark_picked_up(RuntimeOrigin::signed(DRIVER), 0));
+        assert_ok!(Escrow::mark_delivered(RuntimeOrigin::signed(DRIVER), 0));
+
+        System::set_block_number(1 + TIMEOUT);
+        assert_ok!(Escrow::force_timeout_release(RuntimeOrigin::signed(BACKEND), 0));
+        System::assert_last_event(Event::PaymentReleased { escrow_id: 0, amount: 300 }.into());
+        assert_eq!(Escrow::escrows(0).unwrap().released_so_far, 500);
+    });
+}
+
+/// Create escrow `b` (request/offer `uuid(b)`, amount 100) for `DRIVER` and `PAYER`.
+fn created_escrow(b: u8) -> u64 {
+    assert_ok!(Escrow::create_escrow(
+        RuntimeOrigin::signed(BACKEND),
+        uuid(b),
+        uuid(b),
+        DRIVER,
+        PAYER,
+        100
+    ));
+    Escrow::request_to_escrow(uuid(b)).unwrap()
+}
+
+/// Create escrow `b` (request/offer `uuid(b)`, amount 100) for `DRIVER` and confirm it.
+fn confirmed_escrow(b: u8) -> u64 {
+    confirmed_escrow_at(b, System::block_number())
+}
+
+/// Like `confirmed_escrow`, confirming at block `confirm_at`.
+fn confirmed_escrow_at(b: u8, confirm_at: u64) -> u64 {
+    let id = created_escrow(b);
+    assert_ok!(Escrow::mark_picked_up(RuntimeOrigin::signed(DRIVER), id));
+    assert_ok!(Escrow::mark_delivered(RuntimeOrigin::signed(DRIVER), id));
+    System::set_block_number(confirm_at);
+    assert_ok!(Escrow::confirm_received(RuntimeOrigin::signed(PAYER), id));
+    id
+}
+
+#[test]
+fn payer_rates_driver_once_after_confirmation() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(Escrow::create_escrow(
+            RuntimeOrigin::signed(BACKEND),
+            uuid(9),
+            uuid(9),
+            DRIVER,
+            PAYER,
+            100
+        ));
+        // Not confirmed yet.
+        assert_noop!(
+            Escrow::submit_rating(RuntimeOrigin::signed(PAYER), 0, 5),
+            Error::<Test>::InvalidStatusTransition
+        );
+
+        let id = confirmed_escrow(1);
+        for stars in [0, MAX_STARS + 1] {
+            assert_noop!(
+                Escrow::submit_rating(RuntimeOrigin::signed(PAYER), id, stars),
+                Error::<Test>::InvalidRating
+            );
+        }
+        assert_noop!(
+            Escrow::submit_rating(RuntimeOrigin::signed(DRIVER), id, 5),
+            Error::<Test>::NotPayer
+        );
+
+        assert_ok!(Escrow::submit_rating(RuntimeOrigin::signed(PAYER), id, 4));
+        System::assert_last_event(Event::RatingSubmitted { driver: DRIVER, stars: 4 }.into());
+        assert_eq!(Escrow::driver_ratings(DRIVER), (4, 1));
+
+        assert_noop!(
+            Escrow::submit_rating(RuntimeOrigin::signed(PAYER), id, 5),
+            Error::<Test>::AlreadyRated
+        );
+    });
+}
+
+#[test]
+fn average_rating_over_several_escrows() {
+    new_test_ext().execute_with(|| {
+        assert_eq!(Escrow::average_rating(&DRIVER), None);
+
+        for (b, stars) in [(1, 5), (2, 4), (3, 4)] {
+            let id = confirmed_escrow(b);
+            assert_ok!(Escrow::submit_rating(RuntimeOrigin::signed(PAYER), id, stars));
+        }
+
+        assert_eq!(Escrow::driver_ratings(DRIVER), (13, 3));
+        // 13 / 3 = 4.33 stars.
+        assert_eq!(Escrow::average_rating(&DRIVER), Some(433));
+    });
+}
+
+#[test]
+fn early_confirmation_pays_bonus() {
+    new_test_ext().execute_with(|| {
+        // Created at 1: deadline 11, bonus only before 11 - 4 = 7.
+        let id = confirmed_escrow_at(1, 6);
+        System::assert_has_event(Event::PaymentReleased { escrow_id: id, amount: 100 }.into());
+        System::assert_has_event(Event::EarlyBonusReleased { escrow_id: id, bonus: 5 }.into());
+    });
+}
+
+#[test]
+fn late_confirmation_pays_no_bonus() {
+    new_test_ext().execute_with(|| {
+        let id = confirmed_escrow_at(1, 7);
+        System::assert_has_event(Event::PaymentReleased { escrow_id: id, amount: 100 }.into());
+        assert!(!System::events().iter().any(|r| matches!(
+            r.event,
+            RuntimeEvent::Escrow(Event::EarlyBonusReleased { .. })
+        )));
+    });
+}
+
+#[test]
+fn happy_path_releases_payment_on_confirmation() {
+    new_test_ext().execute_with(|| {
+        let id = created_escrow(1);
+        assert_ok!(Escrow::mark_picked_up(RuntimeOrigin::signed(DRIVER), id));
+        System::assert_last_event(
+            Event::PickedUp { escrow_id: id, driver: DRIVER, amount: 100, at: 1 }.into(),
+        );
+        System::set_block_number(4);
+        assert_ok!(Escrow::mark_delivered(RuntimeOrigin::signed(DRIVER), id));
+        // One event carries what a push notification needs: driver, amount, block.
+        System::assert_last_event(
+            Event::Delivered { escrow_id: id, driver: DRIVER, amount: 100, at: 4 }.into(),
+        );
+        assert_eq!(Escrow::escrows(id).unwrap().status, DeliveryStatus::DeliveredByCourier);
+
+        assert_ok!(Escrow::confirm_received(RuntimeOrigin::signed(PAYER), id));
+        assert_eq!(Escrow::escrows(id).unwrap().status, DeliveryStatus::ConfirmedByReceiver);
+        System::assert_has_event(Event::PaymentReleased { escrow_id: id, amount: 100 }.into());
+        System::assert_last_event(Event::ReceiverConfirmed { escrow_id: id }.into());
+    });
+}
+
+#[test]
+fn release_escrow_requires_an_active_match() {
+    new_test_ext().execute_with(|| {
+        let active = created_escrow(1);
+        assert_ok!(Escrow::release_escrow(RuntimeOrigin::signed(BACKEND), uuid(1), uuid(1)));
+        assert_eq!(Escrow::escrows(active).unwrap().status, DeliveryStatus::ConfirmedByReceiver);
+
+        // The finalization of slot 3 that matched offer 2 was revoked.
+        let revoked = created_escrow(2);
+        Escrow::flag_revoked_match(3, uuid(2));
+        assert_noop!(
+            Escrow::release_escrow(RuntimeOrigin::signed(BACKEND), uuid(2), uuid(2)),
+            Error::<Test>::OfferNoLongerActive
+        );
+        assert_eq!(Escrow::escrows(revoked).unwrap().status, DeliveryStatus::Created);
+    });
+}
+
+#[test]
+fn release_escrow_distinguishes_missing_escrow_from_wrong_offer() {
+    new_test_ext().execute_with(|| {
+        let id = created_escrow(1);
+        assert_noop!(
+            Escrow::release_escrow(RuntimeOrigin::signed(BACKEND), uuid(2), uuid(1)),
+            Error::<Test>::EscrowNotFound
+        );
+        assert_noop!(
+            Escrow::release_escrow(RuntimeOrigin::signed(BACKEND), uuid(1), uuid(2)),
+            Error::<Test>::OfferMismatch
+        );
+        assert_eq!(Escrow::escrows(id).unwrap().status, DeliveryStatus::Created);
+    });
+}
+
+#[test]
+fn admin_voids_mid_flight_escrow_and_request_can_be_escrowed_again() {
+    new_test_ext().execute_with(|| {
+        let id = created_escrow(1);
+        assert_ok!(Escrow::mark_picked_up(RuntimeOrigin::signed(DRIVER), id));
+        assert_noop!(
+            Escrow::admin_void_request(RuntimeOrigin::signed(BACKEND), uuid(1)),
+            sp_runtime::DispatchError::BadOrigin
+        );
+
+        assert_ok!(Escrow::admin_void_request(RuntimeOrigin::root(), uuid(1)));
+        System::assert_last_event(Event::EscrowVoided { escrow_id: id, request_uuid: uuid(1) }.into());
+        assert_eq!(Escrow::escrows(id).unwrap().status, DeliveryStatus::Failed);
+        assert_eq!(Escrow::request_to_escrow(uuid(1)), None);
+        assert_noop!(
+            Escrow::admin_void_request(RuntimeOrigin::root(), uuid(1)),
+            Error::<Test>::EscrowNotFound
+        );
+
+        let again = created_escrow(1);
+        assert_ne!(again, id);
+        assert_eq!(Escrow::escrows(again).unwrap().status, DeliveryStatus::Created);
+    });
+}
+
+#[test]
+fn admin_void_rejects_final_escrow() {
+    new_test_ext().execute_with(|| {
+        let id = confirmed_escrow(1);
+        assert_noop!(
+            Escrow::admin_void_request(RuntimeOrigin::root(), uuid(1)),
+            Error::<Test>::EscrowAlreadyFinal
+        );
+        assert_eq!(Escrow::escrows(id).unwrap().status, DeliveryStatus::ConfirmedByReceiver);
+    });
+}
+
+#[test]
+fn allowed_transitions_follow_the_lifecycle() {
+    use DeliveryStatus::*;
+    use EscrowRole::*;
+
+    new_test_ext().execute_with(|| {
+        assert!(Escrow::allowed_transitions(0, Driver).is_empty());
+
+        let id = created_escrow(1);
+        let allowed = |role| Escrow::allowed_transitions(id, role);
+        assert_eq!(allowed(Driver), [PickedUpByCourier]);
+        // Releasing the whole amount early confirms the escrow.
+        assert_eq!(allowed(Payer), [ConfirmedByReceiver]);
+        assert_eq!(allowed(Anyone), [ConfirmedByReceiver, Failed]);
+        assert_eq!(allowed(Admin), [Failed]);
+
+        assert_ok!(Escrow::mark_picked_up(RuntimeOrigin::signed(DRIVER), id));
+        assert_eq!(allowed(Driver), [DeliveredByCourier]);
+        assert_eq!(allowed(Payer), [ConfirmedByReceiver]);
+
+        assert_ok!(Escrow::mark_delivered(RuntimeOrigin::signed(DRIVER), id));
+        assert!(allowed(Driver).is_empty());
+        assert_eq!(allowed(Payer), [ConfirmedByReceiver]);
+        assert_eq!(allowed(Anyone), [ConfirmedByReceiver, TimeoutReleased]);
+
+        assert_ok!(Escrow::confirm_received(RuntimeOrigin::signed(PAYER), id));
+        for role in [Driver, Payer, Admin, Anyone] {
+            assert!(allowed(role).is_empty());
+        }
+    });
+}
+
+#[test]
+fn only_participants_make_feeless_lifecycle_calls() {
+    use crate::Call;
+
+    new_test_ext().execute_with(|| {
+        let id = created_escrow(1);
+        let feeless = |who, call: Call<Test>| Escrow::is_feeless(&who, &call);
+
+        assert!(feeless(DRIVER, Call::mark_picked_up { escrow_id: id }));
+        // Wrong party, not-yet-allowed transition, unknown escrow, other calls.
+        assert!(!feeless(BACKEND, Call::mark_picked_up { escrow_id: id }));
+        assert!(!feeless(PAYER, Call::mark_picked_up { escrow_id: id }));
+        assert!(!feeless(DRIVER, Call::mark_delivered { escrow_id: id }));
+        assert!(!feeless(DRIVER, Call::mark_picked_up { escrow_id: id + 1 }));
+        let release = Call::release_escrow { request_uuid: uuid(1), offer_uuid: uuid(1) };
+        assert!(!feeless(DRIVER, release));
+
+        assert_ok!(Escrow::mark_picked_up(RuntimeOrigin::signed(DRIVER), id));
+        assert!(feeless(DRIVER, Call::mark_delivered { escrow_id: id }));
+        assert_ok!(Escrow::mark_delivered(RuntimeOrigin::signed(DRIVER), id));
+        assert!(feeless(PAYER, Call::confirm_received { escrow_id: id }));
+        assert!(!feeless(DRIVER, Call::confirm_received { escrow_id: id }));
+
+        assert_ok!(Escrow::confirm_received(RuntimeOrigin::signed(PAYER), id));
+        assert!(!feeless(PAYER, Call::confirm_received { escrow_id: id }));
+    });
+}
+
+#[test]
+fn create_escrow_checks_market_when_required() {
+    new_test_ext().execute_with(|| {
+        let create = |request: u8, driver: u64| {
+            Escrow::create_escrow(RuntimeOrigin::signed(BACKEND), uuid(request), uuid(2), driver, PAYER, 100)
+        };
+        // Off by default: unknown requests are accepted.
+        assert_ok!(create(UNKNOWN, DRIVER));
+
+        RequireMarketConsistency::set(true);
+        assert_noop!(create(UNKNOWN + 1, DRIVER), Error::<Test>::MarketMismatch);
+        assert_noop!(create(1, BACKEND), Error::<Test>::MarketMismatch);
+        assert_ok!(create(1, DRIVER));
+    });
+}
+
+#[test]
+fn out_of_order_transitions_are_rejected() {
+    new_test_ext().execute_with(|| {
+        let id = created_escrow(1);
+        assert_noop!(
+            Escrow::mark_delivered(RuntimeOrigin::signed(DRIVER), id),
+            Error::<Test>::InvalidStatusTransition
+        );
+        assert_noop!(
+            Escrow::confirm_received(RuntimeOrigin::signed(PAYER), id),
+            Error::<Test>::InvalidStatusTransition
+        );
+
+        assert_ok!(Escrow::mark_picked_up(RuntimeOrigin::signed(DRIVER), id));
+        assert_noop!(
+            Escrow::mark_picked_up(RuntimeOrigin::signed(DRIVER), id),
+            Error::<Test>::InvalidStatusTransition
+        );
+        assert_noop!(
+            Escrow::confirm_received(RuntimeOrigin::signed(PAYER), id),
+            Error::<Test>::InvalidStatusTransition
+        );
+
+        // Nothing moves once confirmed.
+        assert_ok!(Escrow::mark_delivered(RuntimeOrigin::signed(DRIVER), id));
+        assert_ok!(Escrow::confirm_received(RuntimeOrigin::signed(PAYER), id));
+        assert_noop!(
+            Escrow::mark_delivered(RuntimeOrigin::signed(DRIVER), id),
+            Error::<Test>::EscrowAlreadyFinal
+        );
+        assert_noop!(
+            Escrow::confirm_received(RuntimeOrigin::signed(PAYER), id),
+            Error::<Test>::EscrowAlreadyFinal
+        );
+    });
+}
+
+#[test]
+fn only_driver_and_payer_advance_the_escrow() {
+    new_test_ext().execute_with(|| {
+        let id = created_escrow(1);
+        assert_noop!(
+            Escrow::mark_picked_up(RuntimeOrigin::signed(PAYER), id),
+            Error::<Test>::NotDriver
+        );
+        assert_ok!(Escrow::mark_picked_up(RuntimeOrigin::signed(DRIVER), id));
+        assert_noop!(
+            Escrow::mark_delivered(RuntimeOrigin::signed(BACKEND), id),
+            Error::<Test>::NotDriver
+        );
+        assert_ok!(Escrow::mark_delivered(RuntimeOrigin::signed(DRIVER), id));
+        assert_noop!(
+            Escrow::confirm_received(RuntimeOrigin::signed(DRIVER), id),
+            Error::<Test>::NotPayer
+        );
+    });
+}
+
+#[test]
+fn request_is_assigned_once_and_amount_must_be_positive() {
+    new_test_ext().execute_with(|| {
+        created_escrow(1);
+        assert_noop!(
+            Escrow::create_escrow(RuntimeOrigin::signed(BACKEND), uuid(1), uuid(2), DRIVER, PAYER, 100),
+            Error::<Test>::RequestAlreadyAssigned
+        );
+        assert_noop!(
+            Escrow::create_escrow(RuntimeOrigin::signed(BACKEND), uuid(2), uuid(2), DRIVER, PAYER, 0),
+            Error::<Test>::ZeroAmountNotAllowed
+        );
+    });
+}
+
+#[test]
+fn timeout_release_waits_for_deadline() {
+    new_test_ext().execute_with(|| {
+        let id = created_escrow(1);
+        System::set_block_number(TIMEOUT);
+        assert_noop!(
+            Escrow::force_timeout_release(RuntimeOrigin::signed(BACKEND), id),
+            Error::<Test>::TimeoutNotReached
+        );
+
+        // Created at block 1, so the deadline is 1 + TIMEOUT.
+        System::set_block_number(1 + TIMEOUT);
+        assert_ok!(Escrow::force_timeout_release(RuntimeOrigin::signed(BACKEND), id));
+        assert_eq!(Escrow::escrows(id).unwrap().status, DeliveryStatus::Failed);
+        System::assert_last_event(Event::PaymentRefunded { escrow_id: id, amount: 100 }.into());
+        assert_noop!(
+            Escrow::force_timeout_release(RuntimeOrigin::signed(BACKEND), id),
+            Error::<Test>::EscrowAlreadyFinal
+        );
+    });
+}
+
+#[test]
+fn timeout_refunds_undelivered_and_releases_delivered_escrows() {
+    new_test_ext().execute_with(|| {
+        let created = created_escrow(1);
+        let picked_up = created_escrow(2);
+        assert_ok!(Escrow::mark_picked_up(RuntimeOrigin::signed(DRIVER), picked_up));
+        let delivered = created_escrow(3);
+        assert_ok!(Escrow::mark_picked_up(RuntimeOrigin::signed(DRIVER), delivered));
+        assert_ok!(Escrow::mark_delivered(RuntimeOrigin::signed(DRIVER), delivered));
+
+        System::set_block_number(1 + TIMEOUT);
+        for id in [created, picked_up] {
+            assert_ok!(Escrow::force_timeout_release(RuntimeOrigin::signed(BACKEND), id));
+            System::assert_last_event(Event::PaymentRefunded { escrow_id: id, amount: 100 }.into());
+            let escrow = Escrow::escrows(id).unwrap();
+            assert_eq!(escrow.status, DeliveryStatus::Failed);
+            assert_eq!(escrow.released_so_far, 0);
+        }
+
+        assert_ok!(Escrow::force_timeout_release(RuntimeOrigin::signed(BACKEND), delivered));
+        System::assert_last_event(
+            Event::PaymentReleased { escrow_id: delivered, amount: 100 }.into(),
+        );
+        let escrow = Escrow::escrows(delivered).unwrap();
+        assert_eq!(escrow.status, DeliveryStatus::TimeoutReleased);
+        assert_eq!(escrow.released_so_far, 100);
+    });
+}
+
+/// When the timeout of escrow `id` is scheduled to run, if it is.
+fn scheduled_timeout(id: u64) -> Option<u64> {
+    <Scheduler as Named<u64, RuntimeCall, OriginCaller>>::next_dispatch_time(
+        Escrow::timeout_task(id),
+    )
+    .ok()
+}
+
+#[test]
+fn scheduled_timeout_releases_delivered_escrow_at_the_deadline() {
+    new_test_ext().execute_with(|| {
+        let id = created_escrow(1);
+        let deadline = Escrow::escrows(id).unwrap().deadline;
+        assert_eq!(scheduled_timeout(id), Some(deadline));
+        assert_ok!(Escrow::mark_picked_up(RuntimeOrigin::signed(DRIVER), id));
+        assert_ok!(Escrow::mark_delivered(RuntimeOrigin::signed(DRIVER), id));
+        assert_eq!(scheduled_timeout(id), Some(deadline));
+
+        run_to_block(deadline - 1);
+        assert_eq!(Escrow::escrows(id).unwrap().status, DeliveryStatus::DeliveredByCourier);
+
+        // No keeper call: the scheduler releases it in the deadline block.
+        run_to_block(deadline);
+        let escrow = Escrow::escrows(id).unwrap();
+        assert_eq!(escrow.status, DeliveryStatus::TimeoutReleased);
+        assert_eq!(escrow.released_so_far, 100);
+        System::assert_has_event(Event::PaymentReleased { escrow_id: id, amount: 100 }.into());
+        assert_eq!(scheduled_timeout(id), None);
+    });
+}
+
+#[test]
+fn scheduled_timeout_refunds_undelivered_escrow_at_the_deadline() {
+    new_test_ext().execute_with(|| {
+        let id = created_escrow(1);
+        assert_ok!(Escrow::mark_picked_up(RuntimeOrigin::signed(DRIVER), id));
+        let deadline = Escrow::escrows(id).unwrap().deadline;
+
+        // Never delivered: the scheduler refunds the payer.
+        run_to_block(deadline);
+        let escrow = Escrow::escrows(id).unwrap();
+        assert_eq!(escrow.status, DeliveryStatus::Failed);
+        assert_eq!(escrow.released_so_far, 0);
+        System::assert_has_event(Event::PaymentRefunded { escrow_id: id, amount: 100 }.into());
+        assert_eq!(scheduled_timeout(id), None);
+        // The task ran to completion rather than cancelling itself.
+        assert!(!System::events().iter().any(|r| matches!(
+            r.event,
+            RuntimeEvent::Scheduler(pallet_scheduler::Event::Canceled { .. })
+        )));
+        System::assert_has_event(
+            pallet_scheduler::Event::Dispatched {
+                task: (deadline, 0),
+                id: Some(Escrow::timeout_task(id)),
+                result: Ok(()),
+            }
+            .into(),
+        );
+    });
+}
+
+#[test]
+fn confirmation_cancels_the_scheduled_timeout() {
+    new_test_ext().execute_with(|| {
+        let id = created_escrow(1);
+        assert_ok!(Escrow::mark_picked_up(RuntimeOrigin::signed(DRIVER), id));
+        assert_ok!(Escrow::mark_delivered(RuntimeOrigin::signed(DRIVER), id));
+        let deadline = Escrow::escrows(id).unwrap().deadline;
+        assert_eq!(scheduled_timeout(id), Some(deadline));
+
+        assert_ok!(Escrow::confirm_received(RuntimeOrigin::signed(PAYER), id));
+        assert_eq!(scheduled_timeout(id), None);
+
+        run_to_block(deadline);
+        assert_eq!(Escrow::escrows(id).unwrap().status, DeliveryStatus::ConfirmedByReceiver);
+        System::assert_has_event(
+            pallet_scheduler::Event::Canceled { when: deadline, index: 0 }.into(),
+        );
+    });
+}
+
+#[test]
+fn keeper_release_removes_the_scheduled_timeout() {
+    new_test_ext().execute_with(|| {
+        let lookup = |id| pallet_scheduler::Lookup::<Test>::contains_key(Escrow::timeout_task(id));
+        let (single, batched) = (created_escrow(1), created_escrow(2));
+        for id in [single, batched] {
+            assert_ok!(Escrow::mark_picked_up(RuntimeOrigin::signed(DRIVER), id));
+            assert_ok!(Escrow::mark_delivered(RuntimeOrigin::signed(DRIVER), id));
+            assert!(lookup(id));
+        }
+
+        // A keeper gets in first, in the deadline block.
+        System::set_block_number(Escrow::escrows(single).unwrap().deadline);
+        assert_ok!(Escrow::force_timeout_release(RuntimeOrigin::signed(BACKEND), single));
+        assert!(!lookup(single));
+        assert_ok!(Escrow::force_timeout_release_batch(
+            RuntimeOrigin::signed(BACKEND),
+            vec![batched]
+        ));
+        assert!(!lookup(batched));
+        assert_eq!(scheduled_timeout(batched), None);
+    });
+}
+
+#[test]
+fn v1_migration_fills_new_escrow_fields_with_zero() {
+    new_test_ext().execute_with(|| {
+        StorageVersion::new(0).put::<Escrow>();
+        // v0 layout: no `released_so_far` / `bonus_top_up`.
+        let old = (
+            uuid(1),
+            uuid(2),
+            DRIVER,
+            PAYER,
+            500u64,
+            DeliveryStatus::DeliveredByCourier,
+            1u64,
+            11u64,
+        );
+        frame_support::storage::unhashed::put(&crate::Escrows::<Test>::hashed_key_for(7), &old);
+        assert_eq!(Escrow::escrows(7), None);
+
+        crate::migrations::v1::MigrateV0ToV1::<Test>::on_runtime_upgrade();
+
+        assert_eq!(
+            Escrow::escrows(7),
+            Some(AssignmentEscrow {
+                request_uuid: uuid(1),
+                offer_uuid: uuid(2),
+                driver: DRIVER,
+                payer: PAYER,
+                amount: 500,
+                released_so_far: 0,
+                bonus_top_up: 0,
+                status: DeliveryStatus::DeliveredByCourier,
+                created_at: 1,
+                deadline: 11,
+            })
+        );
+        assert_eq!(StorageVersion::get::<Escrow>(), 1);
+
+        // Already on v1: a second run leaves the escrow alone.
+        crate::migrations::v1::MigrateV0ToV1::<Test>::on_runtime_upgrade();
+        assert_eq!(Escrow::escrows(7).unwrap().amount, 500);
+    });
+}
@@ -0,0 +1,29 @@
+//! Runtime API for reading PoBA proposals without decoding raw storage keys.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::Codec;
+pub use pallet_poba::{Match, Proposal, ProposerStats};
+use sp_runtime::traits::Get;
+use sp_std::vec::Vec;
+
+sp_api::decl_runtime_apis! {
+    /// Read access to PoBA proposals for the worker, the backend and RPC.
+    /// `MaxMatches` is the runtime's `MaxMatchesPerProposal`.
+    pub trait PobaApi<MaxMatches, AccountId> where MaxMatches: Get<u32>, AccountId: Codec {
+        /// Current best proposal of an open slot.
+        fn best_proposal(slot: u64) -> Option<Proposal<MaxMatches>>;
+        /// Winning proposal of a finalized slot, unless already pruned.
+        fn finalized_proposal(slot: u64) -> Option<Proposal<MaxMatches>>;
+        /// Highest finalized (or skipped) slot.
+        fn last_finalized_slot() -> u64;
+        /// Requests matched by slots `>= slot`.
+        fn matched_requests_since(slot: u64) -> Vec<[u8; 16]>;
+        /// Up to `n` proposers ranked by wins, then total winning score.
+        fn top_proposers(n: u32) -> Vec<(AccountId, ProposerStats)>;
+        /// The slot as the pallet derives it at this block (its timestamp in
+        /// `SlotDurationMs` windows), so off-chain callers use the same value
+        /// submissions are checked against.
+        fn current_slot() -> u64;
+    }
+}
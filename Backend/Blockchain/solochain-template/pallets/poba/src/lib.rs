@@ -1,12 +1,28 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 
 // --------------------------- Imports & Prelude ---------------------------
-use codec::{Decode, Encode, MaxEncodedLen};
-use frame_support::{pallet_prelude::*, BoundedVec};
+use codec::{Decode, DecodeWithMemTracking, Encode, MaxEncodedLen};
+use frame_support::{
+    pallet_prelude::*,
+    traits::{BalanceStatus, Currency, ReservableCurrency, Time},
+    BoundedVec,
+};
 use frame_system::pallet_prelude::*;
 use scale_info::TypeInfo;
-use sp_runtime::RuntimeDebug;
-use sp_std::vec::Vec;
+use sp_runtime::{
+    traits::{Hash as HashT, SaturatedConversion, Saturating, Zero},
+    KeyTypeId, Perbill, RuntimeAppPublic, RuntimeDebug,
+};
+use sp_std::{collections::btree_set::BTreeSet, marker::PhantomData, vec::Vec};
+
+#[cfg(test)]
+mod mock;
+#[cfg(test)]
+mod tests;
+
+/// Balance type of the currency used for proposal bonds.
+pub type BalanceOf<T> =
+    <<T as Config>::Currency as Currency<<T as frame_system::Config>::AccountId>>::Balance;
 
 // --------------------------- Domain Types ---------------------------
 
@@ -19,15 +35,177 @@ pub struct Match {
     pub partial_score: i64,
 }
 
-/// Upper bound on how many matches a single proposal may include.
-pub const MAX_MATCHES_PER_PROPOSAL: u32 = 256;
-pub type MatchesBounded = BoundedVec<Match, ConstU32<MAX_MATCHES_PER_PROPOSAL>>;
+/// Matches of a proposal, bounded by `Config::MaxMatchesPerProposal`.
+pub type MatchesOf<T> = BoundedVec<Match, <T as Config>::MaxMatchesPerProposal>;
+
+/// Upper bound on how many slots `on_initialize` walks when pruning.
+pub const MAX_PRUNE_PER_BLOCK: u64 = 64;
+
+/// Upper bound on the entries `top_proposers` returns.
+pub const MAX_TOP_PROPOSERS: u32 = 32;
+
+/// Keystore key type of the authority keys signing unsigned proposals.
+pub const KEY_TYPE: KeyTypeId = KeyTypeId(*b"poba");
+
+/// sr25519 authority keys under `KEY_TYPE`, for `Config::AuthorityId`.
+pub mod crypto {
+    use super::KEY_TYPE;
+    use sp_runtime::app_crypto::{app_crypto, sr25519};
+    app_crypto!(sr25519, KEY_TYPE);
+}
+
+/// Signature type of `Config::AuthorityId`.
+pub type AuthoritySignatureOf<T> = <<T as Config>::AuthorityId as RuntimeAppPublic>::Signature;
 
 /// A full proposal as stored on-chain for a given slot.
+#[derive(
+    CloneNoBound, PartialEqNoBound, EqNoBound, Encode, Decode, MaxEncodedLen, TypeInfo,
+    RuntimeDebugNoBound,
+)]
+#[codec(mel_bound())]
+#[scale_info(skip_type_params(MaxMatches))]
+pub struct Proposal<MaxMatches: Get<u32>> {
+    pub total_score: i64,
+    pub matches:     BoundedVec<Match, MaxMatches>,
+}
+
+/// Proposal bounded by the runtime's `MaxMatchesPerProposal`.
+pub type ProposalOf<T> = Proposal<<T as Config>::MaxMatchesPerProposal>;
+
+/// Hook run by `finalize_slot` once the winning matches are stored, so other
+/// pallets (e.g. escrow) can act on them in the same extrinsic.
+pub trait OnSlotFinalized<AccountId> {
+    fn on_slot_finalized(slot: u64, matches: &[Match]);
+
+    /// Counterpart run by `report_invalid_finalization` with the matches of
+    /// a revoked finalization, e.g. to flag escrows already created for them.
+    fn on_slot_revoked(_slot: u64, _matches: &[Match]) {}
+}
+
+impl<AccountId> OnSlotFinalized<AccountId> for () {
+    fn on_slot_finalized(_slot: u64, _matches: &[Match]) {}
+}
+
+/// Price limits of the bids a match references, as known to the market.
+pub trait PriceBands {
+    /// `min_price_cents` of the offer, if known.
+    fn offer_min_price(offer_uuid: &[u8; 16]) -> Option<u32>;
+    /// `max_price_cents` of the request, if known.
+    fn request_max_price(request_uuid: &[u8; 16]) -> Option<u32>;
+}
+
+/// No market on-chain: prices are not checked.
+impl PriceBands for () {
+    fn offer_min_price(_: &[u8; 16]) -> Option<u32> {
+        None
+    }
+    fn request_max_price(_: &[u8; 16]) -> Option<u32> {
+        None
+    }
+}
+
+/// Lifetime counters of one proposer, for the admin leaderboard.
+#[derive(
+    Clone, Default, PartialEq, Eq, Encode, Decode, MaxEncodedLen, TypeInfo, RuntimeDebug,
+)]
+pub struct ProposerStats {
+    /// Accepted submissions, resubmissions included.
+    pub submitted: u64,
+    /// Slots finalized with this proposer's proposal.
+    pub wins: u64,
+    pub total_winning_score: i64,
+}
+
+/// The market a slot's proposals must be computed against, as published by
+/// the backend when the slot opens.
+#[derive(Clone, PartialEq, Eq, Encode, Decode, MaxEncodedLen, TypeInfo, RuntimeDebug)]
+pub struct MarketSnapshotInfo<Hash> {
+    /// Hash of the open requests and active offers the backend served.
+    pub snapshot_hash: Hash,
+    pub request_count: u32,
+    pub offer_count: u32,
+}
+
+/// Compact record of a finalized proposal, kept after the full proposal is pruned
+/// so it can still be checked against off-chain archives.
 #[derive(Clone, PartialEq, Eq, Encode, Decode, MaxEncodedLen, TypeInfo, RuntimeDebug)]
-pub struct Proposal {
+pub struct ProposalDigest<Hash> {
     pub total_score: i64,
-    pub matches:     MatchesBounded,
+    pub matches_count: u32,
+    /// `T::Hashing` hash of the SCALE-encoded matches.
+    pub matches_hash: Hash,
+}
+
+/// A finalization opened by `propose_finalization`, waiting out its challenge window.
+#[derive(
+    CloneNoBound, PartialEqNoBound, EqNoBound, Encode, Decode, MaxEncodedLen, TypeInfo,
+    RuntimeDebugNoBound,
+)]
+#[codec(mel_bound())]
+#[scale_info(skip_type_params(T))]
+pub struct PendingFinalization<T: Config> {
+    pub proposer: T::AccountId,
+    pub proposal: ProposalOf<T>,
+    /// First block at which `finalize_slot` may commit this proposal.
+    pub challenge_until: BlockNumberFor<T>,
+}
+
+/// Concrete rule violation a challenger claims against a pending finalization.
+#[derive(
+    Clone, Copy, PartialEq, Eq, Encode, Decode, DecodeWithMemTracking, MaxEncodedLen, TypeInfo,
+    RuntimeDebug,
+)]
+pub enum ChallengeReason {
+    /// The same request_uuid appears in more than one match.
+    DuplicateRequest,
+    /// total_score differs from the sum of partial scores. Submissions are
+    /// checked for this already, so it only applies to older proposals.
+    ScoreMismatch,
+    /// A match price lies outside its bids' `PriceBands`.
+    PriceOutOfBand,
+    /// Reported for `BetterProposal` evidence; never a violation by itself.
+    Outscored,
+}
+
+/// Phase of a slot when `SubmissionWindowSlots` is non-zero.
+#[derive(
+    Clone, Copy, PartialEq, Eq, Encode, Decode, DecodeWithMemTracking, MaxEncodedLen, TypeInfo,
+    RuntimeDebug,
+)]
+pub enum SlotPhase {
+    /// Proposals are accepted; the slot cannot be finalized yet.
+    Submission,
+    /// Submissions are closed; the slot waits for (or got) finalization.
+    Quiet,
+}
+
+/// Proof that an already finalized proposal breaks a rule, for
+/// `report_invalid_finalization`.
+#[derive(Clone, PartialEq, Eq, Encode, Decode, DecodeWithMemTracking, TypeInfo, RuntimeDebug)]
+pub enum InvalidityProof {
+    /// The finalized proposal itself breaks the rule (`Outscored` never holds).
+    Violation(ChallengeReason),
+    /// `request_uuid` was already matched by the finalized proposal of the
+    /// earlier `other_slot`.
+    RequestMatchedTwice {
+        request_uuid: [u8; 16],
+        other_slot: u64,
+    },
+}
+
+/// What a challenger submits against a pending finalization.
+#[derive(Clone, PartialEq, Eq, Encode, Decode, DecodeWithMemTracking, TypeInfo, RuntimeDebug)]
+pub enum ChallengeEvidence<Hash> {
+    /// The pending proposal breaks a rule the chain can re-check.
+    Violation(ChallengeReason),
+    /// A valid competing proposal with a strictly higher recomputed score,
+    /// admitted only where `submit_proposal` would accept it.
+    BetterProposal {
+        snapshot_hash: Hash,
+        total_score: i64,
+        // (request_uuid, offer_uuid, agreed_price_cents, partial_score)
+        matches: Vec<([u8; 16], [u8; 16], u32, i64)>,
+    },
 }
 
 // ------------------------------ Pallet ------------------------------
@@ -41,6 +219,170 @@ pub mod pallet {
     pub trait Config: frame_system::Config {
         /// Pallet events.
         type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
+
+        /// Currency used to reserve proposal bonds.
+        type Currency: ReservableCurrency<Self::AccountId>;
+
+        /// Bond reserved from a proposer on its first submission for a slot.
+        #[pallet::constant]
+        type ProposalBond: Get<BalanceOf<Self>>;
+
+        /// How far (in slots) a submission may lag behind `LastFinalizedSlot`
+        /// or run ahead of the current slot.
+        #[pallet::constant]
+        type MaxSlotLag: Get<u64>;
+
+        /// Maximum number of matches in a single proposal.
+        #[pallet::constant]
+        type MaxMatchesPerProposal: Get<u32>;
+
+        /// Maximum number of distinct proposers kept per slot in `Proposals`.
+        #[pallet::constant]
+        type MaxProposersPerSlot: Get<u32>;
+
+        /// Reward credited to the winning proposer of each finalized slot.
+        #[pallet::constant]
+        type WinnerReward: Get<BalanceOf<Self>>;
+
+        /// Account the winner rewards are reserved on until claimed (e.g. a
+        /// treasury pot).
+        type RewardSource: Get<Self::AccountId>;
+
+        /// Origin allowed to manage the authorized proposer registry.
+        type AdminOrigin: EnsureOrigin<Self::RuntimeOrigin>;
+
+        /// Blocks a proposed finalization stays open for challenges.
+        ///
+        /// Zero disables the two-phase flow: `finalize_slot` commits the
+        /// current best proposal directly.
+        #[pallet::constant]
+        type ChallengeWindowBlocks: Get<BlockNumberFor<Self>>;
+
+        /// Origin allowed to propose and commit slot finalizations.
+        type FinalizeOrigin: EnsureOrigin<Self::RuntimeOrigin>;
+
+        /// Blocks the current best proposal of a slot must have led before the
+        /// slot can be finalized (or proposed for finalization).
+        #[pallet::constant]
+        type MinProposalAge: Get<BlockNumberFor<Self>>;
+
+        /// How many slots below `LastFinalizedSlot` keep their `FinalizedProposal`.
+        /// Older entries are pruned in `on_initialize`.
+        #[pallet::constant]
+        type RetainFinalizedSlots: Get<u64>;
+
+        /// If true, `finalize_slot` on a slot without proposals succeeds with
+        /// `SlotSkipped` and advances `LastFinalizedSlot`; if false it fails
+        /// with `NoProposalForSlot`.
+        #[pallet::constant]
+        type AllowEmptySlots: Get<bool>;
+
+        /// Called with the winning matches after a slot is finalized.
+        type OnSlotFinalized: OnSlotFinalized<Self::AccountId>;
+
+        /// Maximum accepted `submit_proposal` calls per (slot, proposer).
+        #[pallet::constant]
+        type MaxSubmissionsPerSlot: Get<u32>;
+
+        /// Minimum score gain a resubmission needs over the proposer's
+        /// previous proposal for the same slot.
+        #[pallet::constant]
+        type MinImprovementDelta: Get<i64>;
+
+        /// Bid price limits used to validate `agreed_price_cents`.
+        type PriceBands: PriceBands;
+
+        /// Blocks after a slot's first commitment during which commitments are
+        /// accepted. Zero disables commit-reveal and keeps `submit_proposal`.
+        #[pallet::constant]
+        type CommitWindowBlocks: Get<BlockNumberFor<Self>>;
+
+        /// Blocks after the commit window during which commitments are revealed.
+        #[pallet::constant]
+        type RevealWindowBlocks: Get<BlockNumberFor<Self>>;
+
+        /// How `submit_proposal` treats matches whose request was already
+        /// finalized in an earlier slot: drop them silently (true) or reject
+        /// the whole proposal with `RequestAlreadyMatched` (false).
+        #[pallet::constant]
+        type FilterMatchedRequests: Get<bool>;
+
+        /// How far (in slots) a new submission or commitment may be from the
+        /// current slot, in either direction.
+        #[pallet::constant]
+        type SlotTolerance: Get<u64>;
+
+        /// Clock the current slot is derived from (`pallet_timestamp` in the
+        /// runtime).
+        type Time: Time;
+
+        /// Length of a slot in milliseconds of `Time`; non-zero.
+        #[pallet::constant]
+        type SlotDurationMs: Get<u64>;
+
+        /// Expected block time in milliseconds; non-zero. Turns slot deadlines
+        /// into block counts, e.g. for transaction `longevity`.
+        #[pallet::constant]
+        type BlockTimeMs: Get<u64>;
+
+        /// `finalize_slot` leaves the slot open (emitting `SlotBelowThreshold`)
+        /// while the winning proposal scores below this.
+        #[pallet::constant]
+        type MinFinalizeScore: Get<i64>;
+
+        /// `finalize_slot` leaves the slot open while the winning proposal
+        /// has fewer matches than this.
+        #[pallet::constant]
+        type MinFinalizeMatches: Get<u32>;
+
+        /// Key an authority signs `submit_proposal_unsigned` payloads with.
+        type AuthorityId: Member
+            + Parameter
+            + RuntimeAppPublic
+            + Ord
+            + MaybeSerializeDeserialize
+            + MaxEncodedLen;
+
+        /// Maximum number of entries in `Authorities`.
+        #[pallet::constant]
+        type MaxAuthorities: Get<u32>;
+
+        /// Transaction pool priority of unsigned proposals.
+        #[pallet::constant]
+        type UnsignedPriority: Get<TransactionPriority>;
+
+        /// Maximum request UUIDs listed in one market snapshot.
+        #[pallet::constant]
+        type MaxSnapshotRequests: Get<u32>;
+
+        /// Maximum unmatched request UUIDs stored per finalized slot; the
+        /// `UnmatchedCount` event still reports the full count.
+        #[pallet::constant]
+        type MaxUnmatched: Get<u32>;
+
+        /// Upper bound on a match's `partial_score` (which must be `>= 0`).
+        #[pallet::constant]
+        type MaxPartialScore: Get<i64>;
+
+        /// Slots from a slot's start (`current_slot == slot`) during which
+        /// proposals are accepted; the slot can only be finalized afterwards.
+        /// Zero disables the phases.
+        #[pallet::constant]
+        type SubmissionWindowSlots: Get<u64>;
+
+        /// Blocks after finalization during which the winner's bond stays
+        /// reserved and `report_invalid_finalization` is accepted. Zero returns
+        /// the bond at finalization and disables reports.
+        #[pallet::constant]
+        type ReportWindowBlocks: Get<BlockNumberFor<Self>>;
+
+        /// Part of the winner's bond slashed by a successful report.
+        #[pallet::constant]
+        type SlashFraction: Get<Perbill>;
+
+        /// Part of the slashed amount paid to the reporter; the rest is burned.
+        #[pallet::constant]
+        type ReporterShare: Get<Perbill>;
     }
 
     /// The pallet type.
@@ -49,41 +391,458 @@ pub mod pallet {
 
     // -------- Storage --------
 
-    /// For each slot, keep the *best* proposal seen so far (by total_score).
+    /// Accounts allowed to call `submit_proposal` (the authority nodes).
+    #[pallet::storage]
+    #[pallet::getter(fn is_authorized_proposer)]
+    pub type AuthorizedProposers<T: Config> =
+        StorageMap<_, Blake2_128Concat, T::AccountId, (), OptionQuery>;
+
+    /// Every competing proposal per slot, one entry per proposer
+    /// (a resubmission overwrites the proposer's own entry).
+    #[pallet::storage]
+    #[pallet::getter(fn proposals)]
+    pub type Proposals<T: Config> = StorageMap<
+        _,
+        Blake2_128Concat,
+        u64, /*slot*/
+        BoundedVec<(T::AccountId, ProposalOf<T>), T::MaxProposersPerSlot>,
+        ValueQuery,
+    >;
+
+    /// For each slot, keep the *best* proposal seen so far (by total_score,
+    /// then number of matches).
+    ///
+    /// Derived from `Proposals`: always the entry chosen by `select_best`.
     #[pallet::storage]
     #[pallet::getter(fn best_proposal)]
     pub type BestProposal<T: Config> =
-        StorageMap<_, Blake2_128Concat, u64 /*slot*/, Proposal, OptionQuery>;
+        StorageMap<_, Blake2_128Concat, u64 /*slot*/, ProposalOf<T>, OptionQuery>;
+
+    /// Account that submitted the current `BestProposal` for a slot.
+    #[pallet::storage]
+    #[pallet::getter(fn best_proposer)]
+    pub type BestProposer<T: Config> =
+        StorageMap<_, Blake2_128Concat, u64 /*slot*/, T::AccountId, OptionQuery>;
+
+    /// Bond reserved per (slot, proposer). Removed when returned.
+    #[pallet::storage]
+    #[pallet::getter(fn bonds)]
+    pub type Bonds<T: Config> = StorageDoubleMap<
+        _,
+        Blake2_128Concat,
+        u64, /*slot*/
+        Blake2_128Concat,
+        T::AccountId,
+        BalanceOf<T>,
+        OptionQuery,
+    >;
+
+    /// Accepted submissions per (slot, proposer). Cleared when the slot is
+    /// finalized or pruned.
+    #[pallet::storage]
+    #[pallet::getter(fn submission_count)]
+    pub type SubmissionCount<T: Config> = StorageDoubleMap<
+        _,
+        Blake2_128Concat,
+        u64, /*slot*/
+        Blake2_128Concat,
+        T::AccountId,
+        u32,
+        ValueQuery,
+    >;
+
+    /// `submission_key` of the last proposal accepted per (slot, proposer),
+    /// so an identical resubmission (e.g. a retried backend call) is a no-op.
+    /// Cleared with `SubmissionCount`.
+    #[pallet::storage]
+    #[pallet::getter(fn submitted_hash)]
+    pub type SubmittedHashes<T: Config> = StorageDoubleMap<
+        _,
+        Blake2_128Concat,
+        u64, /*slot*/
+        Blake2_128Concat,
+        T::AccountId,
+        T::Hash,
+        OptionQuery,
+    >;
+
+    /// Hidden proposals: `hash((total_score, matches, salt))` per (slot, proposer).
+    /// Removed on reveal; left-overs forfeit their bond at finalization.
+    #[pallet::storage]
+    #[pallet::getter(fn commitment)]
+    pub type Commitments<T: Config> = StorageDoubleMap<
+        _,
+        Blake2_128Concat,
+        u64, /*slot*/
+        Blake2_128Concat,
+        T::AccountId,
+        T::Hash,
+        OptionQuery,
+    >;
+
+    /// `(commit_until, reveal_until)` of a slot, fixed by its first commitment.
+    #[pallet::storage]
+    #[pallet::getter(fn reveal_window)]
+    pub type RevealWindows<T: Config> = StorageMap<
+        _,
+        Blake2_128Concat,
+        u64, /*slot*/
+        (BlockNumberFor<T>, BlockNumberFor<T>),
+        OptionQuery,
+    >;
+
+    /// Accrued, not yet claimed proposer rewards. Each is held reserved on
+    /// the `RewardSource` account until claimed.
+    #[pallet::storage]
+    #[pallet::getter(fn rewards)]
+    pub type Rewards<T: Config> =
+        StorageMap<_, Blake2_128Concat, T::AccountId, BalanceOf<T>, ValueQuery>;
+
+    /// Block at which the current best proposal of a slot took the lead.
+    #[pallet::storage]
+    pub type BestProposalSince<T: Config> =
+        StorageMap<_, Blake2_128Concat, u64 /*slot*/, BlockNumberFor<T>, OptionQuery>;
+
+    /// Finalizations waiting out their challenge window, per slot.
+    #[pallet::storage]
+    #[pallet::getter(fn pending_finalization)]
+    pub type PendingFinalizations<T: Config> = StorageMap<
+        _,
+        Blake2_128Concat,
+        u64, /*slot*/
+        PendingFinalization<T>,
+        OptionQuery,
+    >;
 
     /// Final winner per slot after `finalize_slot`.
     #[pallet::storage]
     #[pallet::getter(fn finalized_proposal)]
     pub type FinalizedProposal<T: Config> =
-        StorageMap<_, Blake2_128Concat, u64 /*slot*/, Proposal, OptionQuery>;
+        StorageMap<_, Blake2_128Concat, u64 /*slot*/, ProposalOf<T>, OptionQuery>;
+
+    /// Requests matched by a finalized proposal, with the slot that matched them.
+    #[pallet::storage]
+    #[pallet::getter(fn matched_request)]
+    pub type MatchedRequests<T: Config> =
+        StorageMap<_, Blake2_128Concat, [u8; 16] /*request_uuid*/, u64 /*slot*/, OptionQuery>;
+
+    /// Digest of every finalized proposal; survives pruning of `FinalizedProposal`.
+    #[pallet::storage]
+    #[pallet::getter(fn finalized_digest)]
+    pub type FinalizedDigest<T: Config> =
+        StorageMap<_, Blake2_128Concat, u64 /*slot*/, ProposalDigest<T::Hash>, OptionQuery>;
 
     /// The last finalized slot (for convenience from UI/backend).
     #[pallet::storage]
     #[pallet::getter(fn last_finalized_slot)]
     pub type LastFinalizedSlot<T: Config> = StorageValue<_, u64, ValueQuery>;
 
+    /// Submission and win counters per proposer; never pruned.
+    #[pallet::storage]
+    #[pallet::getter(fn proposer_stats)]
+    pub type ProposerStatsOf<T: Config> =
+        StorageMap<_, Blake2_128Concat, T::AccountId, ProposerStats, ValueQuery>;
+
+    /// Market snapshot per slot; proposals must reference its hash. Pruned
+    /// together with `FinalizedProposal`.
+    #[pallet::storage]
+    #[pallet::getter(fn market_snapshot)]
+    pub type MarketSnapshot<T: Config> =
+        StorageMap<_, Blake2_128Concat, u64 /*slot*/, MarketSnapshotInfo<T::Hash>, OptionQuery>;
+
+    /// Open request UUIDs of a slot's market snapshot, if the publisher
+    /// listed them. Pruned together with `MarketSnapshot`.
+    #[pallet::storage]
+    #[pallet::getter(fn snapshot_requests)]
+    pub type SnapshotRequests<T: Config> = StorageMap<
+        _,
+        Blake2_128Concat,
+        u64, /*slot*/
+        BoundedVec<[u8; 16], T::MaxSnapshotRequests>,
+        ValueQuery,
+    >;
+
+    /// Snapshot requests the winning proposal of a finalized slot left
+    /// unmatched (first `MaxUnmatched`). Pruned with `FinalizedProposal`.
+    #[pallet::storage]
+    #[pallet::getter(fn unmatched_requests)]
+    pub type UnmatchedRequests<T: Config> = StorageMap<
+        _,
+        Blake2_128Concat,
+        u64, /*slot*/
+        BoundedVec<[u8; 16], T::MaxUnmatched>,
+        ValueQuery,
+    >;
+
+    /// Authority keys allowed to sign `submit_proposal_unsigned`, each with
+    /// the account its proposals are entered (and bonded) for. Indexed by
+    /// `authority_index`.
+    #[pallet::storage]
+    #[pallet::getter(fn authorities)]
+    pub type Authorities<T: Config> = StorageValue<
+        _,
+        BoundedVec<(T::AuthorityId, T::AccountId), T::MaxAuthorities>,
+        ValueQuery,
+    >;
+
+    /// Unsigned submissions per (slot, authority index); one is allowed.
+    /// Cleared together with `SubmissionCount`.
+    #[pallet::storage]
+    pub type UnsignedSubmissions<T: Config> = StorageDoubleMap<
+        _,
+        Blake2_128Concat,
+        u64, /*slot*/
+        Blake2_128Concat,
+        u32, /*authority_index*/
+        (),
+        OptionQuery,
+    >;
+
+    /// Winner and report deadline of finalized slots whose winner bond is
+    /// still held for reports. Removed when the bond is reclaimed or slashed.
+    #[pallet::storage]
+    #[pallet::getter(fn report_window)]
+    pub type ReportWindows<T: Config> = StorageMap<
+        _,
+        Blake2_128Concat,
+        u64, /*slot*/
+        (T::AccountId, BlockNumberFor<T>),
+        OptionQuery,
+    >;
+
+    /// Finalizations revoked by `report_invalid_finalization`, with the
+    /// reporter. The revoked `FinalizedProposal` is kept as evidence.
+    #[pallet::storage]
+    #[pallet::getter(fn revoked_by)]
+    pub type RevokedFinalizations<T: Config> =
+        StorageMap<_, Blake2_128Concat, u64 /*slot*/, T::AccountId, OptionQuery>;
+
+    /// Every slot below this one has had its `FinalizedProposal` pruned.
+    #[pallet::storage]
+    #[pallet::getter(fn pruned_up_to)]
+    pub type PrunedUpTo<T: Config> = StorageValue<_, u64, ValueQuery>;
+
+    /// Current slot when `SlotPhaseChanged` was last emitted.
+    #[pallet::storage]
+    pub type PhaseSlot<T: Config> = StorageValue<_, u64, OptionQuery>;
+
+    // -------- Genesis --------
+
+    #[pallet::genesis_config]
+    #[derive(frame_support::DefaultNoBound)]
+    pub struct GenesisConfig<T: Config> {
+        /// Accounts authorized to submit proposals from block 0.
+        pub proposers: Vec<T::AccountId>,
+        /// Initial `Authorities`: signing key and proposer account.
+        pub authorities: Vec<(T::AuthorityId, T::AccountId)>,
+        /// Finalized history to start from, as `(slot, total_score, matches)`
+        /// with matches in `submit_proposal` form. Slots must be strictly
+        /// increasing; the last one becomes `LastFinalizedSlot`. Entries older
+        /// than `RetainFinalizedSlots` are pruned as usual.
+        pub finalized: Vec<(u64, i64, Vec<([u8; 16], [u8; 16], u32, i64)>)>,
+    }
+
+    #[pallet::genesis_build]
+    impl<T: Config> BuildGenesisConfig for GenesisConfig<T> {
+        fn build(&self) {
+            for who in &self.proposers {
+                AuthorizedProposers::<T>::insert(who, ());
+            }
+            let authorities = BoundedVec::try_from(self.authorities.clone())
+                .expect("more PoBA authorities than MaxAuthorities at genesis");
+            Authorities::<T>::put(authorities);
+
+            let mut previous: Option<u64> = None;
+            for (slot, total_score, matches) in &self.finalized {
+                assert!(
+                    previous < Some(*slot),
+                    "PoBA genesis finalized slots must be unique and increasing"
+                );
+                previous = Some(*slot);
+
+                let matches: Vec<Match> = matches
+                    .iter()
+                    .map(|(rq, of, price, part)| Match {
+                        request_uuid: *rq,
+                        offer_uuid: *of,
+                        agreed_price_cents: *price,
+                        partial_score: *part,
+                    })
+                    .collect();
+                let matches: MatchesOf<T> = BoundedVec::try_from(matches)
+                    .expect("PoBA genesis proposal exceeds MaxMatchesPerProposal");
+                assert_eq!(
+                    Pallet::<T>::partial_sum(&matches),
+                    Some(*total_score),
+                    "PoBA genesis total_score must equal the sum of partial scores"
+                );
+
+                for m in matches.iter() {
+                    MatchedRequests::<T>::insert(m.request_uuid, *slot);
+                }
+                FinalizedDigest::<T>::insert(
+                    slot,
+                    ProposalDigest {
+                        total_score: *total_score,
+                        matches_count: matches.len() as u32,
+                        matches_hash: T::Hashing::hash_of(&matches),
+                    },
+                );
+                FinalizedProposal::<T>::insert(slot, Proposal { total_score: *total_score, matches });
+            }
+            if let Some(last) = previous {
+                LastFinalizedSlot::<T>::put(last);
+            }
+        }
+    }
+
     // -------- Events --------
 
     #[pallet::event]
     #[pallet::generate_deposit(pub(super) fn deposit_event)]
     pub enum Event<T: Config> {
         /// A proposal was submitted for `slot` with `total_score` and `matches` count,
-        /// including the account that submitted it.
+        /// including the account that submitted it and whether it now leads the slot.
         ProposalSubmitted {
             slot: u64,
             total_score: i64,
             matches: u32,
             proposer: T::AccountId,
+            is_best: bool,
         },
         /// The slot was finalized with the winning `total_score` and `matches` count.
+        /// `snapshot_hash` names the market the winner was computed against and
+        /// `winner` is the account that submitted it.
         SlotFinalized     {
             slot: u64,
             total_score: i64,
             matches: u32,
+            snapshot_hash: T::Hash,
+            winner: T::AccountId,
+        },
+        /// `finalize_slot` left `slot` open: its best proposal is below
+        /// `MinFinalizeScore` or `MinFinalizeMatches`.
+        SlotBelowThreshold {
+            slot: u64,
+            best_score: i64,
+        },
+        /// The market snapshot proposals for `slot` must reference.
+        MarketSnapshotSet {
+            slot: u64,
+            snapshot_hash: T::Hash,
+            request_count: u32,
+            offer_count: u32,
+        },
+        /// A proposal bond was reserved from `who` for `slot`.
+        BondReserved {
+            slot: u64,
+            who: T::AccountId,
+            amount: BalanceOf<T>,
+        },
+        /// A proposal bond for `slot` was returned to `who`.
+        BondReturned {
+            slot: u64,
+            who: T::AccountId,
+            amount: BalanceOf<T>,
+        },
+        /// The winner of `slot` was credited `amount` of claimable reward,
+        /// set aside in the reward pot.
+        RewardAccrued {
+            slot: u64,
+            who: T::AccountId,
+            amount: BalanceOf<T>,
+        },
+        /// The reward pot could not cover the reward for the winner of `slot`.
+        RewardSkipped {
+            slot: u64,
+            who: T::AccountId,
+            amount: BalanceOf<T>,
+        },
+        /// `who` claimed `amount` of accrued rewards.
+        RewardClaimed {
+            who: T::AccountId,
+            amount: BalanceOf<T>,
+        },
+        /// `who` was added to the authorized proposers.
+        ProposerAdded { who: T::AccountId },
+        /// `who` was removed from the authorized proposers.
+        ProposerRemoved { who: T::AccountId },
+        /// `removed` old finalized proposals were pruned from storage.
+        FinalizedPruned { removed: u32 },
+        /// The finalized proposal of `slot` was pruned via `prune_finalized`.
+        ProposalPruned { slot: u64 },
+        /// `slot` had no proposals and was finalized empty.
+        SlotSkipped { slot: u64 },
+        /// A request matched in `slot` was released back to the market.
+        RequestUnmatched { request_uuid: [u8; 16], slot: u64 },
+        /// The best proposal of `slot` was snapshotted; it can be challenged
+        /// until `challenge_until`.
+        FinalizationProposed {
+            slot: u64,
+            proposer: T::AccountId,
+            total_score: i64,
+            challenge_until: BlockNumberFor<T>,
+        },
+        /// A pending finalization was overturned by a successful challenge and
+        /// the offending proposal was dropped from the slot. `Outscored`
+        /// means the challenger's proposal is now pending instead.
+        FinalizationChallenged {
+            slot: u64,
+            challenger: T::AccountId,
+            proposer: T::AccountId,
+            reason: ChallengeReason,
+        },
+        /// A hidden proposal was committed; it can be revealed in
+        /// `[reveal_from, reveal_until)`.
+        ProposalCommitted {
+            slot: u64,
+            who: T::AccountId,
+            reveal_from: BlockNumberFor<T>,
+            reveal_until: BlockNumberFor<T>,
+        },
+        /// A commitment was opened; the proposal entered the slot's competition.
+        ProposalRevealed {
+            slot: u64,
+            who: T::AccountId,
+        },
+        /// A proposer's bond was paid to the challenger who beat it.
+        BondSlashed {
+            slot: u64,
+            who: T::AccountId,
+            challenger: T::AccountId,
+            amount: BalanceOf<T>,
+        },
+        /// A commitment was never revealed and its bond was slashed.
+        CommitmentForfeited {
+            slot: u64,
+            who: T::AccountId,
+            amount: BalanceOf<T>,
+        },
+        /// `count` snapshot requests of the finalized `slot` got no match;
+        /// see `UnmatchedRequests`.
+        UnmatchedCount {
+            slot: u64,
+            count: u32,
+        },
+        /// `slot` entered `phase` in this block (emitted when
+        /// `SubmissionWindowSlots` is non-zero, once per slot change).
+        SlotPhaseChanged {
+            slot: u64,
+            phase: SlotPhase,
+        },
+        /// `Authorities` was replaced; `count` keys may sign unsigned proposals.
+        AuthoritiesSet {
+            count: u32,
+        },
+        /// The finalization of `slot` was proven invalid: `slashed` was taken
+        /// from the winner's bond, `reward` of it paid to the reporter, and
+        /// its matches were released back to the market.
+        FinalizationRevoked {
+            slot: u64,
+            proposer: T::AccountId,
+            reporter: T::AccountId,
+            slashed: BalanceOf<T>,
+            reward: BalanceOf<T>,
         },
     }
 
@@ -97,6 +856,153 @@ pub mod pallet {
         NoProposalForSlot,
         /// Submitted proposal has zero matches (not allowed).
         EmptyMatches,
+        /// Proposer cannot afford the proposal bond.
+        InsufficientBond,
+        /// No bond is held for this (slot, proposer).
+        NoBond,
+        /// Bonds can only be reclaimed once the slot has been finalized.
+        SlotNotFinalized,
+        /// The slot was already finalized; no more proposals are accepted.
+        SlotAlreadyFinalized,
+        /// The slot is too far behind the last finalized slot or too far
+        /// ahead of the current slot (see `MaxSlotLag`).
+        SlotOutOfRange,
+        /// The slot lies outside `[current_slot - SlotTolerance,
+        /// current_slot + SlotTolerance]`, `current_slot` being `Time` bucketed
+        /// by `SlotDurationMs`.
+        SlotOutsideWindow,
+        /// The proposal's snapshot hash differs from `MarketSnapshot` of the
+        /// slot, or the slot has no snapshot yet.
+        SnapshotMismatch,
+        /// The slot has no market snapshot, so it cannot be finalized.
+        MissingSnapshot,
+        /// A market snapshot was already set for this slot.
+        SnapshotAlreadySet,
+        /// The slot already holds `MaxProposersPerSlot` distinct proposers.
+        TooManyProposers,
+        /// The caller has no accrued rewards to claim.
+        NoRewards,
+        /// The account is not in the authorized proposer registry.
+        NotAuthorizedProposer,
+        /// The account is already an authorized proposer.
+        AlreadyAuthorizedProposer,
+        /// `ChallengeWindowBlocks` is zero, so there is no proposal phase.
+        ChallengeWindowDisabled,
+        /// A finalization for this slot is already pending.
+        FinalizationAlreadyPending,
+        /// No finalization has been proposed for this slot.
+        NoPendingFinalization,
+        /// The challenge window has not elapsed yet.
+        ChallengePeriodActive,
+        /// The challenge window is over; the slot can only be finalized.
+        ChallengePeriodOver,
+        /// The pending proposal does not violate the claimed rule.
+        ChallengeRejected,
+        /// The best proposal has not led for `MinProposalAge` blocks yet.
+        ProposalTooRecent,
+        /// The slot is still inside the `RetainFinalizedSlots` window.
+        SlotWithinRetention,
+        /// No finalized proposal is stored for this slot.
+        NothingToPrune,
+        /// A match uses a request already matched in a finalized slot.
+        RequestAlreadyMatched,
+        /// The request is not matched by any finalized slot.
+        RequestNotMatched,
+        /// The proposer reached `MaxSubmissionsPerSlot` for this slot.
+        TooManySubmissions,
+        /// A resubmission must beat the previous score by `MinImprovementDelta`.
+        InsufficientImprovement,
+        /// agreed_price_cents is below the offer's minimum or above the request's maximum.
+        PriceOutOfBand,
+        /// Commit-reveal is enabled: use `commit_proposal` / `reveal_proposal`.
+        CommitRevealRequired,
+        /// Commit-reveal is disabled (`CommitWindowBlocks` is zero).
+        CommitRevealDisabled,
+        /// The slot's commit window has ended.
+        CommitWindowClosed,
+        /// Not within the slot's reveal window.
+        RevealWindowClosed,
+        /// The revealed proposal does not hash to the commitment.
+        RevealMismatch,
+        /// No commitment for this (slot, proposer).
+        NothingCommitted,
+        /// The slot's reveal window has not ended yet.
+        RevealPeriodActive,
+        /// `authority_index` is not an entry of `Authorities`.
+        UnknownAuthority,
+        /// This authority already submitted an unsigned proposal for the slot.
+        AlreadySubmittedUnsigned,
+        /// More authorities than `MaxAuthorities`.
+        TooManyAuthorities,
+        /// More snapshot request UUIDs than `MaxSnapshotRequests`.
+        TooManySnapshotRequests,
+        /// A partial_score is negative or above `MaxPartialScore`.
+        InvalidPartialScore,
+        /// Score arithmetic overflowed i64.
+        ScoreOverflow,
+        /// total_score differs from the sum of partial scores.
+        ScoreMismatch,
+        /// The slot's submission window is over.
+        SubmissionWindowClosed,
+        /// The slot's submission window has not ended yet.
+        SubmissionWindowStillOpen,
+        /// The winner's bond is held until the slot's report window ends.
+        ReportWindowActive,
+        /// The slot is not open for reports: not finalized, its report window
+        /// is over, or `ReportWindowBlocks` is zero.
+        ReportWindowClosed,
+        /// The finalized proposal does not break the claimed rule.
+        ReportRejected,
+        /// The slot's finalization was already revoked.
+        FinalizationAlreadyRevoked,
+    }
+
+    // -------- Hooks --------
+
+    #[pallet::hooks]
+    impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+        fn on_initialize(_n: BlockNumberFor<T>) -> Weight {
+            let (scanned, removed) = Self::sweep_finalized();
+            if removed > 0 {
+                Self::deposit_event(Event::FinalizedPruned { removed });
+            }
+
+            // `on_finalize` announces phase changes.
+            let phases = if T::SubmissionWindowSlots::get().is_zero() {
+                Weight::zero()
+            } else {
+                T::DbWeight::get().reads_writes(2, 1)
+            };
+            T::DbWeight::get().reads_writes(2 + 2 * scanned, 1 + 8 * scanned).saturating_add(phases)
+        }
+
+        // Runs after the timestamp inherent, so `current_slot` is this block's.
+        fn on_finalize(_n: BlockNumberFor<T>) {
+            let window = T::SubmissionWindowSlots::get();
+            if window.is_zero() {
+                return;
+            }
+            let current = Self::current_slot();
+            if PhaseSlot::<T>::get() == Some(current) {
+                return;
+            }
+            PhaseSlot::<T>::put(current);
+            Self::deposit_event(Event::SlotPhaseChanged {
+                slot: current,
+                phase: SlotPhase::Submission,
+            });
+            if let Some(closed) = current.checked_sub(window) {
+                Self::deposit_event(Event::SlotPhaseChanged {
+                    slot: closed,
+                    phase: SlotPhase::Quiet,
+                });
+            }
+        }
+
+        fn integrity_test() {
+            assert!(T::SlotDurationMs::get() > 0, "`SlotDurationMs` must not be zero");
+            assert!(T::BlockTimeMs::get() > 0, "`BlockTimeMs` must not be zero");
+        }
     }
 
     // -------- Calls --------
@@ -107,88 +1013,1263 @@ pub mod pallet {
         ///
         /// NOTE: To satisfy FRAME's DecodeWithMemTracking on call parameters,
         /// we accept Vec of tuples and convert inside.
-        #[pallet::weight(10_000)]
+        ///
+        /// `snapshot_hash` must equal the slot's `MarketSnapshot`.
+        ///
+        /// Weight grows linearly with the number of submitted matches.
+        #[pallet::weight(10_000u64.saturating_add(1_000u64.saturating_mul(matches.len() as u64)))]
         pub fn submit_proposal(
             origin: OriginFor<T>,
             slot: u64,
+            snapshot_hash: T::Hash,
             total_score: i64,
             // (request_uuid, offer_uuid, agreed_price_cents, partial_score)
             matches: Vec<([u8; 16], [u8; 16], u32, i64)>,
         ) -> DispatchResult {
             // מי הגיש את ההצעה (Alice / Bob וכו')
             let who = ensure_signed(origin)?;
+            ensure!(
+                T::CommitWindowBlocks::get().is_zero(),
+                Error::<T>::CommitRevealRequired
+            );
+            Self::ensure_slot_in_window(slot)?;
+            Self::ensure_submission_open(slot)?;
+            Self::do_submit(who, slot, snapshot_hash, total_score, matches, true)
+        }
 
-            // Convert tuples → Match → BoundedVec
-            let mut tmp: Vec<Match> = Vec::with_capacity(matches.len());
-            for (rq, of, price, part) in matches.into_iter() {
-                tmp.push(Match {
-                    request_uuid: rq,
-                    offer_uuid: of,
-                    agreed_price_cents: price,
-                    partial_score: part,
-                });
-            }
-            let bounded: MatchesBounded =
-                BoundedVec::try_from(tmp).map_err(|_| Error::<T>::TooManyMatches)?;
-
-            // Reject empty proposals
-            ensure!(!bounded.is_empty(), Error::<T>::EmptyMatches);
+        /// `submit_proposal` as an unsigned extrinsic, signed instead by the
+        /// key of `Authorities[authority_index]` so the authority pays no fee.
+        ///
+        /// `signature` covers `unsigned_payload` and is checked in
+        /// `validate_unsigned`. One unsigned submission per authority and slot;
+        /// the proposal goes to the authority's account, which needs neither
+        /// funds for a bond nor a place in `AuthorizedProposers`.
+        #[pallet::weight(10_000u64.saturating_add(1_000u64.saturating_mul(matches.len() as u64)))]
+        pub fn submit_proposal_unsigned(
+            origin: OriginFor<T>,
+            slot: u64,
+            snapshot_hash: T::Hash,
+            total_score: i64,
+            // (request_uuid, offer_uuid, agreed_price_cents, partial_score)
+            matches: Vec<([u8; 16], [u8; 16], u32, i64)>,
+            authority_index: u32,
+            _signature: AuthoritySignatureOf<T>,
+        ) -> DispatchResult {
+            ensure_none(origin)?;
+            ensure!(
+                T::CommitWindowBlocks::get().is_zero(),
+                Error::<T>::CommitRevealRequired
+            );
+            let (_, who) = Authorities::<T>::get()
+                .get(authority_index as usize)
+                .cloned()
+                .ok_or(Error::<T>::UnknownAuthority)?;
+            ensure!(
+                !UnsignedSubmissions::<T>::contains_key(slot, authority_index),
+                Error::<T>::AlreadySubmittedUnsigned
+            );
+            Self::ensure_slot_in_window(slot)?;
+            Self::ensure_submission_open(slot)?;
+            Self::do_submit(who, slot, snapshot_hash, total_score, matches, false)?;
+            UnsignedSubmissions::<T>::insert(slot, authority_index, ());
+            Ok(())
+        }
 
-            let matches_len: u32 = bounded.len() as u32;
-            let proposal = Proposal {
-                total_score,
-                matches: bounded,
-            };
+        /// Commit to a hidden proposal for `slot` (commit-reveal mode).
+        ///
+        /// `commitment` is `T::Hashing` of the SCALE-encoded
+        /// `(total_score, matches, salt)` later passed to `reveal_proposal`.
+        /// The proposal bond is reserved here and slashed if never revealed.
+        #[pallet::weight(10_000)]
+        pub fn commit_proposal(origin: OriginFor<T>, slot: u64, commitment: T::Hash) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+            ensure!(
+                AuthorizedProposers::<T>::contains_key(&who),
+                Error::<T>::NotAuthorizedProposer
+            );
+            let commit_blocks = T::CommitWindowBlocks::get();
+            ensure!(!commit_blocks.is_zero(), Error::<T>::CommitRevealDisabled);
+            Self::ensure_slot_in_window(slot)?;
+            Self::ensure_slot_open(slot)?;
 
-            // לעדכן BestProposal רק אם זו ההצעה הראשונה לסלוט
-            // או אם היא משפרת את total_score, אבל:
-            // *תמיד* נייצר אירוע ProposalSubmitted (גם אם לא שיפרנו).
-            match BestProposal::<T>::get(slot) {
-                Some(existing) => {
-                    if total_score > existing.total_score {
-                        BestProposal::<T>::insert(slot, &proposal);
-                    }
-                }
+            let now = frame_system::Pallet::<T>::block_number();
+            let (commit_until, reveal_until) = match RevealWindows::<T>::get(slot) {
+                Some(window) => window,
                 None => {
-                    BestProposal::<T>::insert(slot, &proposal);
+                    let commit_until = now.saturating_add(commit_blocks);
+                    let window =
+                        (commit_until, commit_until.saturating_add(T::RevealWindowBlocks::get()));
+                    RevealWindows::<T>::insert(slot, window);
+                    window
                 }
-            }
+            };
+            ensure!(now < commit_until, Error::<T>::CommitWindowClosed);
 
-            // 🔔 אירוע תמידי – כל הגשה נרשמת, כולל מי הגיש
-            Self::deposit_event(Event::ProposalSubmitted {
+            Self::reserve_bond(slot, &who)?;
+            Commitments::<T>::insert(slot, &who, commitment);
+
+            Self::deposit_event(Event::ProposalCommitted {
                 slot,
-                total_score,
-                matches: matches_len,
-                proposer: who,
+                who,
+                reveal_from: commit_until,
+                reveal_until,
             });
-
             Ok(())
         }
 
-        /// Finalize a slot: move best → finalized, update last slot, emit rich event.
+        /// Reveal a committed proposal; it then competes like `submit_proposal`.
+        #[pallet::weight(10_000u64.saturating_add(1_000u64.saturating_mul(matches.len() as u64)))]
+        pub fn reveal_proposal(
+            origin: OriginFor<T>,
+            slot: u64,
+            snapshot_hash: T::Hash,
+            total_score: i64,
+            // (request_uuid, offer_uuid, agreed_price_cents, partial_score)
+            matches: Vec<([u8; 16], [u8; 16], u32, i64)>,
+            salt: [u8; 32],
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            let (commit_until, reveal_until) =
+                RevealWindows::<T>::get(slot).ok_or(Error::<T>::NothingCommitted)?;
+            let now = frame_system::Pallet::<T>::block_number();
+            ensure!(now >= commit_until && now < reveal_until, Error::<T>::RevealWindowClosed);
+
+            let commitment =
+                Commitments::<T>::take(slot, &who).ok_or(Error::<T>::NothingCommitted)?;
+            ensure!(
+                Self::commitment_of(total_score, &matches, &salt) == commitment,
+                Error::<T>::RevealMismatch
+            );
+
+            Self::deposit_event(Event::ProposalRevealed { slot, who: who.clone() });
+            Self::do_submit(who, slot, snapshot_hash, total_score, matches, true)
+        }
+
+        /// Open the challenge window for `slot` by snapshotting its best proposal.
         #[pallet::weight(10_000)]
-        pub fn finalize_slot(origin: OriginFor<T>, slot: u64) -> DispatchResult {
-            let _who = ensure_signed(origin)?;
+        pub fn propose_finalization(origin: OriginFor<T>, slot: u64) -> DispatchResult {
+            T::FinalizeOrigin::ensure_origin(origin)?;
 
-            // Must have a non-empty best proposal for this slot
-            let winner = BestProposal::<T>::take(slot).ok_or(Error::<T>::NoProposalForSlot)?;
-            ensure!(!winner.matches.is_empty(), Error::<T>::NoProposalForSlot);
+            let window = T::ChallengeWindowBlocks::get();
+            ensure!(!window.is_zero(), Error::<T>::ChallengeWindowDisabled);
+            ensure!(!Self::is_finalized(slot), Error::<T>::SlotAlreadyFinalized);
+            ensure!(
+                !PendingFinalizations::<T>::contains_key(slot),
+                Error::<T>::FinalizationAlreadyPending
+            );
 
-            let matches_len = winner.matches.len() as u32;
+            Self::ensure_reveal_over(slot)?;
+            Self::ensure_submission_over(slot)?;
 
-            FinalizedProposal::<T>::insert(slot, &winner);
-            LastFinalizedSlot::<T>::put(slot);
+            let entries = Proposals::<T>::get(slot);
+            let (proposer, proposal) =
+                Self::select_best(&entries).cloned().ok_or(Error::<T>::NoProposalForSlot)?;
+            ensure!(!proposal.matches.is_empty(), Error::<T>::NoProposalForSlot);
+            Self::ensure_proposal_aged(slot)?;
 
-            Self::deposit_event(Event::SlotFinalized {
+            let challenge_until = frame_system::Pallet::<T>::block_number().saturating_add(window);
+            let total_score = proposal.total_score;
+            PendingFinalizations::<T>::insert(
                 slot,
-                total_score: winner.total_score,
-                matches: matches_len,
-            });
+                PendingFinalization { proposer: proposer.clone(), proposal, challenge_until },
+            );
 
+            Self::deposit_event(Event::FinalizationProposed {
+                slot,
+                proposer,
+                total_score,
+                challenge_until,
+            });
             Ok(())
         }
-    }
-}
+
+        /// Challenge a pending finalization with fraud evidence.
+        ///
+        /// Evidence is re-checked on-chain. A proven `Violation` drops the
+        /// pending entry and the offending proposal; a `BetterProposal`
+        /// replaces the winner (the challenger becomes proposer) and restarts
+        /// the window. Either way the original proposer's bond goes to the
+        /// challenger.
+        ///
+        /// A `BetterProposal` passes the checks of `submit_proposal` (snapshot
+        /// hash, slot and submission windows, `MaxSubmissionsPerSlot`) and
+        /// counts as one of the challenger's submissions. With submission
+        /// phases on, the window is closed by the time finalization is
+        /// proposed, so only `Violation` challenges remain.
+        #[pallet::weight(10_000)]
+        pub fn challenge_finalization(
+            origin: OriginFor<T>,
+            slot: u64,
+            evidence: ChallengeEvidence<T::Hash>,
+        ) -> DispatchResult {
+            let challenger = ensure_signed(origin)?;
+
+            let pending =
+                PendingFinalizations::<T>::get(slot).ok_or(Error::<T>::NoPendingFinalization)?;
+            let now = frame_system::Pallet::<T>::block_number();
+            ensure!(now < pending.challenge_until, Error::<T>::ChallengePeriodOver);
+            ensure!(challenger != pending.proposer, Error::<T>::ChallengeRejected);
+
+            let (reason, replacement) = match evidence {
+                ChallengeEvidence::Violation(reason) => {
+                    ensure!(
+                        Self::violates(&pending.proposal, reason),
+                        Error::<T>::ChallengeRejected
+                    );
+                    (reason, None)
+                }
+                ChallengeEvidence::BetterProposal { snapshot_hash, total_score, matches } => {
+                    ensure!(
+                        T::CommitWindowBlocks::get().is_zero(),
+                        Error::<T>::CommitRevealRequired
+                    );
+                    Self::ensure_slot_in_window(slot)?;
+                    Self::ensure_submission_open(slot)?;
+                    ensure!(
+                        AuthorizedProposers::<T>::contains_key(&challenger),
+                        Error::<T>::NotAuthorizedProposer
+                    );
+                    Self::ensure_admissible(slot, snapshot_hash)?;
+                    Self::ensure_submission_quota(slot, &challenger)?;
+                    let better = Self::build_proposal(total_score, matches)?;
+                    ensure!(
+                        !Self::violates(&better, ChallengeReason::DuplicateRequest) &&
+                            !Self::violates(&better, ChallengeReason::ScoreMismatch) &&
+                            better.total_score > pending.proposal.total_score,
+                        Error::<T>::ChallengeRejected
+                    );
+                    (ChallengeReason::Outscored, Some(better))
+                }
+            };
+
+            // The new winner is bonded like any proposer, so it can be
+            // challenged in turn.
+            if replacement.is_some() {
+                Self::reserve_bond(slot, &challenger)?;
+            }
+            Self::slash_bond_to(slot, &pending.proposer, &challenger);
+            Proposals::<T>::try_mutate(slot, |entries| -> DispatchResult {
+                entries.retain(|(p, _)| p != &pending.proposer);
+                if let Some(better) = &replacement {
+                    match entries.iter_mut().find(|(p, _)| p == &challenger) {
+                        Some(entry) => entry.1 = better.clone(),
+                        None => entries
+                            .try_push((challenger.clone(), better.clone()))
+                            .map_err(|_| Error::<T>::TooManyProposers)?,
+                    }
+                }
+                Self::refresh_best(slot, entries);
+                Ok(())
+            })?;
+            match replacement {
+                Some(proposal) => {
+                    SubmissionCount::<T>::mutate(slot, &challenger, |n| *n = n.saturating_add(1));
+                    PendingFinalizations::<T>::insert(
+                        slot,
+                        PendingFinalization {
+                            proposer: challenger.clone(),
+                            proposal,
+                            challenge_until: now.saturating_add(T::ChallengeWindowBlocks::get()),
+                        },
+                    )
+                },
+                None => PendingFinalizations::<T>::remove(slot),
+            }
+
+            Self::deposit_event(Event::FinalizationChallenged {
+                slot,
+                challenger,
+                proposer: pending.proposer,
+                reason,
+            });
+            Ok(())
+        }
+
+        /// Finalize a slot: move best → finalized, update last slot, emit rich event.
+        ///
+        /// With a non-zero `ChallengeWindowBlocks` the slot must have been
+        /// proposed via `propose_finalization` and its window must have
+        /// elapsed unchallenged; the snapshotted proposal is committed.
+        ///
+        /// The winner's bond is returned here, or held for `ReportWindowBlocks`
+        /// when that is non-zero; other proposers reclaim theirs with
+        /// `reclaim_bond` once the slot is finalized.
+        ///
+        /// A slot without any proposal is skipped when `AllowEmptySlots` is set.
+        ///
+        /// A winner below `MinFinalizeScore` or `MinFinalizeMatches` does not
+        /// fail the call: the slot stays open and `SlotBelowThreshold` is
+        /// emitted, so the caller can retry once a better proposal arrives.
+        #[pallet::weight(10_000)]
+        pub fn finalize_slot(origin: OriginFor<T>, slot: u64) -> DispatchResult {
+            T::FinalizeOrigin::ensure_origin(origin)?;
+            Self::ensure_reveal_over(slot)?;
+            Self::ensure_submission_over(slot)?;
+
+            if T::AllowEmptySlots::get() &&
+                !PendingFinalizations::<T>::contains_key(slot) &&
+                Proposals::<T>::decode_len(slot).unwrap_or(0) == 0
+            {
+                ensure!(!Self::is_finalized(slot), Error::<T>::SlotAlreadyFinalized);
+                LastFinalizedSlot::<T>::mutate(|last| *last = (*last).max(slot));
+                Self::forfeit_unrevealed(slot);
+                Self::deposit_event(Event::SlotSkipped { slot });
+                return Ok(());
+            }
+
+            let snapshot = MarketSnapshot::<T>::get(slot).ok_or(Error::<T>::MissingSnapshot)?;
+
+            let (proposer, winner) = if T::ChallengeWindowBlocks::get().is_zero() {
+                // Pick the winner deterministically from all proposals of this slot
+                let entries = Proposals::<T>::get(slot);
+                let best =
+                    Self::select_best(&entries).cloned().ok_or(Error::<T>::NoProposalForSlot)?;
+                Self::ensure_proposal_aged(slot)?;
+                best
+            } else {
+                let pending = PendingFinalizations::<T>::get(slot)
+                    .ok_or(Error::<T>::NoPendingFinalization)?;
+                let now = frame_system::Pallet::<T>::block_number();
+                ensure!(now >= pending.challenge_until, Error::<T>::ChallengePeriodActive);
+                (pending.proposer, pending.proposal)
+            };
+            ensure!(!winner.matches.is_empty(), Error::<T>::NoProposalForSlot);
+            if winner.total_score < T::MinFinalizeScore::get() ||
+                (winner.matches.len() as u32) < T::MinFinalizeMatches::get()
+            {
+                // Proposals and a pending finalization stay in place: once
+                // improved, `finalize_slot` can be called again. In two-phase
+                // mode the challenge window reopens so a `BetterProposal`
+                // can replace the pending entry.
+                PendingFinalizations::<T>::mutate(slot, |pending| {
+                    if let Some(pending) = pending {
+                        pending.challenge_until = frame_system::Pallet::<T>::block_number()
+                            .saturating_add(T::ChallengeWindowBlocks::get());
+                    }
+                });
+                Self::deposit_event(Event::SlotBelowThreshold {
+                    slot,
+                    best_score: winner.total_score,
+                });
+                return Ok(());
+            }
+
+            PendingFinalizations::<T>::remove(slot);
+            Proposals::<T>::remove(slot);
+            BestProposal::<T>::remove(slot);
+            BestProposer::<T>::remove(slot);
+            BestProposalSince::<T>::remove(slot);
+            let _ = SubmissionCount::<T>::clear_prefix(slot, u32::MAX, None);
+            let _ = SubmittedHashes::<T>::clear_prefix(slot, u32::MAX, None);
+            let _ = UnsignedSubmissions::<T>::clear_prefix(slot, u32::MAX, None);
+            Self::forfeit_unrevealed(slot);
+
+            let matches_len = winner.matches.len() as u32;
+
+            FinalizedDigest::<T>::insert(
+                slot,
+                ProposalDigest {
+                    total_score: winner.total_score,
+                    matches_count: matches_len,
+                    matches_hash: T::Hashing::hash_of(&winner.matches),
+                },
+            );
+            FinalizedProposal::<T>::insert(slot, &winner);
+            LastFinalizedSlot::<T>::mutate(|last| *last = (*last).max(slot));
+
+            for m in winner.matches.iter() {
+                MatchedRequests::<T>::insert(m.request_uuid, slot);
+            }
+            Self::record_unmatched(slot, &winner.matches);
+
+            T::OnSlotFinalized::on_slot_finalized(slot, &winner.matches);
+
+            ProposerStatsOf::<T>::mutate(&proposer, |stats| {
+                stats.wins = stats.wins.saturating_add(1);
+                stats.total_winning_score =
+                    stats.total_winning_score.saturating_add(winner.total_score);
+            });
+
+            let report_window = T::ReportWindowBlocks::get();
+            if report_window.is_zero() {
+                Self::return_bond(slot, &proposer);
+            } else {
+                let now = frame_system::Pallet::<T>::block_number();
+                ReportWindows::<T>::insert(slot, (proposer.clone(), now.saturating_add(report_window)));
+            }
+
+            Self::accrue_reward(slot, &proposer);
+
+            Self::deposit_event(Event::SlotFinalized {
+                slot,
+                total_score: winner.total_score,
+                matches: matches_len,
+                snapshot_hash: snapshot.snapshot_hash,
+                winner: proposer,
+            });
+
+            Ok(())
+        }
+
+        /// Reclaim the caller's bond for an already finalized slot. The winner
+        /// must wait until the slot's report window is over.
+        #[pallet::weight(10_000)]
+        pub fn reclaim_bond(origin: OriginFor<T>, slot: u64) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            ensure!(Self::is_finalized(slot), Error::<T>::SlotNotFinalized);
+            ensure!(Bonds::<T>::contains_key(slot, &who), Error::<T>::NoBond);
+            if let Some((winner, report_until)) = ReportWindows::<T>::get(slot) {
+                if winner == who {
+                    let now = frame_system::Pallet::<T>::block_number();
+                    ensure!(now >= report_until, Error::<T>::ReportWindowActive);
+                    ReportWindows::<T>::remove(slot);
+                }
+            }
+
+            Self::return_bond(slot, &who);
+            Ok(())
+        }
+
+        /// Pay out all rewards accrued by the caller from the reward pot.
+        #[pallet::weight(10_000)]
+        pub fn claim_rewards(origin: OriginFor<T>) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            let amount = Rewards::<T>::take(&who);
+            ensure!(!amount.is_zero(), Error::<T>::NoRewards);
+
+            T::Currency::repatriate_reserved(
+                &T::RewardSource::get(),
+                &who,
+                amount,
+                BalanceStatus::Free,
+            )?;
+
+            Self::deposit_event(Event::RewardClaimed { who, amount });
+            Ok(())
+        }
+
+        /// Report that the finalized proposal of `slot` breaks a rule, while
+        /// its report window is open.
+        ///
+        /// The proof is re-checked on-chain. If it holds, `SlashFraction` of
+        /// the winner's bond is slashed (`ReporterShare` of that paid to the
+        /// reporter, the rest burned) and the remainder returned. The slot
+        /// stays finalized but is marked revoked: its requests return to the
+        /// market and `OnSlotFinalized::on_slot_revoked` flags what was
+        /// built on them.
+        #[pallet::weight(10_000)]
+        pub fn report_invalid_finalization(
+            origin: OriginFor<T>,
+            slot: u64,
+            proof: InvalidityProof,
+        ) -> DispatchResult {
+            let reporter = ensure_signed(origin)?;
+
+            ensure!(
+                !RevokedFinalizations::<T>::contains_key(slot),
+                Error::<T>::FinalizationAlreadyRevoked
+            );
+            let (proposer, report_until) =
+                ReportWindows::<T>::get(slot).ok_or(Error::<T>::ReportWindowClosed)?;
+            let now = frame_system::Pallet::<T>::block_number();
+            ensure!(now < report_until, Error::<T>::ReportWindowClosed);
+            ensure!(reporter != proposer, Error::<T>::ReportRejected);
+            let finalized = FinalizedProposal::<T>::get(slot).ok_or(Error::<T>::ReportRejected)?;
+
+            let proven = match proof {
+                InvalidityProof::Violation(reason) => Self::violates(&finalized, reason),
+                InvalidityProof::RequestMatchedTwice { request_uuid, other_slot } =>
+                    other_slot < slot &&
+                        !RevokedFinalizations::<T>::contains_key(other_slot) &&
+                        [slot, other_slot].into_iter().all(|s| {
+                            FinalizedProposal::<T>::get(s).is_some_and(|p| {
+                                p.matches.iter().any(|m| m.request_uuid == request_uuid)
+                            })
+                        }),
+            };
+            ensure!(proven, Error::<T>::ReportRejected);
+
+            ReportWindows::<T>::remove(slot);
+            let (slashed, reward) = Self::slash_winner_bond(slot, &proposer, &reporter);
+            RevokedFinalizations::<T>::insert(slot, &reporter);
+
+            for m in finalized.matches.iter() {
+                if MatchedRequests::<T>::get(m.request_uuid) == Some(slot) {
+                    MatchedRequests::<T>::remove(m.request_uuid);
+                }
+            }
+            ProposerStatsOf::<T>::mutate(&proposer, |stats| {
+                stats.wins = stats.wins.saturating_sub(1);
+                stats.total_winning_score =
+                    stats.total_winning_score.saturating_sub(finalized.total_score);
+            });
+            T::OnSlotFinalized::on_slot_revoked(slot, &finalized.matches);
+
+            Self::deposit_event(Event::FinalizationRevoked {
+                slot,
+                proposer,
+                reporter,
+                slashed,
+                reward,
+            });
+            Ok(())
+        }
+
+        /// Prune the finalized proposal of a slot that fell out of the
+        /// retention window. Permissionless; its digest is kept.
+        #[pallet::weight(10_000)]
+        pub fn prune_finalized(origin: OriginFor<T>, slot: u64) -> DispatchResult {
+            let _who = ensure_signed(origin)?;
+
+            let cutoff =
+                LastFinalizedSlot::<T>::get().saturating_sub(T::RetainFinalizedSlots::get());
+            ensure!(slot < cutoff, Error::<T>::SlotWithinRetention);
+            FinalizedProposal::<T>::take(slot).ok_or(Error::<T>::NothingToPrune)?;
+            UnmatchedRequests::<T>::remove(slot);
+            MarketSnapshot::<T>::remove(slot);
+            SnapshotRequests::<T>::remove(slot);
+
+            Self::deposit_event(Event::ProposalPruned { slot });
+            Ok(())
+        }
+
+        /// Publish the market snapshot of `slot` (backend, at slot open).
+        ///
+        /// Proposals for the slot must pass the same `snapshot_hash`, so every
+        /// proposer provably matched the same requests and offers.
+        /// `request_uuids` lists the open requests (may be empty); finalization
+        /// records those the winner left unmatched.
+        #[pallet::weight(10_000u64.saturating_add(100u64.saturating_mul(request_uuids.len() as u64)))]
+        pub fn set_market_snapshot(
+            origin: OriginFor<T>,
+            slot: u64,
+            snapshot_hash: T::Hash,
+            request_count: u32,
+            offer_count: u32,
+            request_uuids: Vec<[u8; 16]>,
+        ) -> DispatchResult {
+            T::AdminOrigin::ensure_origin(origin)?;
+            ensure!(!Self::is_finalized(slot), Error::<T>::SlotAlreadyFinalized);
+            ensure!(!MarketSnapshot::<T>::contains_key(slot), Error::<T>::SnapshotAlreadySet);
+            let request_uuids: BoundedVec<_, T::MaxSnapshotRequests> =
+                BoundedVec::try_from(request_uuids)
+                    .map_err(|_| Error::<T>::TooManySnapshotRequests)?;
+
+            if !request_uuids.is_empty() {
+                SnapshotRequests::<T>::insert(slot, request_uuids);
+            }
+            MarketSnapshot::<T>::insert(
+                slot,
+                MarketSnapshotInfo { snapshot_hash, request_count, offer_count },
+            );
+            Self::deposit_event(Event::MarketSnapshotSet {
+                slot,
+                snapshot_hash,
+                request_count,
+                offer_count,
+            });
+            Ok(())
+        }
+
+        /// Return a matched request to the market, e.g. after its escrow was
+        /// cancelled, so later proposals may match it again.
+        #[pallet::weight(10_000)]
+        pub fn unmatch_request(origin: OriginFor<T>, request_uuid: [u8; 16]) -> DispatchResult {
+            T::AdminOrigin::ensure_origin(origin)?;
+
+            let slot = MatchedRequests::<T>::take(request_uuid).ok_or(Error::<T>::RequestNotMatched)?;
+
+            Self::deposit_event(Event::RequestUnmatched { request_uuid, slot });
+            Ok(())
+        }
+
+        /// Authorize `who` to submit proposals.
+        #[pallet::weight(10_000)]
+        pub fn add_proposer(origin: OriginFor<T>, who: T::AccountId) -> DispatchResult {
+            T::AdminOrigin::ensure_origin(origin)?;
+            ensure!(
+                !AuthorizedProposers::<T>::contains_key(&who),
+                Error::<T>::AlreadyAuthorizedProposer
+            );
+
+            AuthorizedProposers::<T>::insert(&who, ());
+            Self::deposit_event(Event::ProposerAdded { who });
+            Ok(())
+        }
+
+        /// Revoke `who`'s permission to submit proposals.
+        #[pallet::weight(10_000)]
+        pub fn remove_proposer(origin: OriginFor<T>, who: T::AccountId) -> DispatchResult {
+            T::AdminOrigin::ensure_origin(origin)?;
+            ensure!(
+                AuthorizedProposers::<T>::contains_key(&who),
+                Error::<T>::NotAuthorizedProposer
+            );
+
+            AuthorizedProposers::<T>::remove(&who);
+            Self::deposit_event(Event::ProposerRemoved { who });
+            Ok(())
+        }
+
+        /// Replace `Authorities`. Indices shift, so signatures made for the
+        /// old set no longer verify.
+        #[pallet::weight(10_000)]
+        pub fn set_authorities(
+            origin: OriginFor<T>,
+            authorities: Vec<(T::AuthorityId, T::AccountId)>,
+        ) -> DispatchResult {
+            T::AdminOrigin::ensure_origin(origin)?;
+            let authorities: BoundedVec<_, T::MaxAuthorities> =
+                BoundedVec::try_from(authorities).map_err(|_| Error::<T>::TooManyAuthorities)?;
+
+            let count = authorities.len() as u32;
+            Authorities::<T>::put(authorities);
+            Self::deposit_event(Event::AuthoritiesSet { count });
+            Ok(())
+        }
+    }
+
+    // -------- Unsigned --------
+
+    #[pallet::validate_unsigned]
+    impl<T: Config> ValidateUnsigned for Pallet<T> {
+        type Call = Call<T>;
+
+        /// Accept `submit_proposal_unsigned` from a known authority with a
+        /// valid signature, once per (slot, authority) and only while the
+        /// slot is within `SlotTolerance`.
+        fn validate_unsigned(_source: TransactionSource, call: &Self::Call) -> TransactionValidity {
+            let Call::submit_proposal_unsigned {
+                slot,
+                snapshot_hash,
+                total_score,
+                matches,
+                authority_index,
+                _signature: signature,
+            } = call
+            else {
+                return InvalidTransaction::Call.into();
+            };
+
+            if Self::is_finalized(*slot) ||
+                Self::ensure_slot_in_window(*slot).is_err() ||
+                Self::ensure_submission_open(*slot).is_err() ||
+                UnsignedSubmissions::<T>::contains_key(slot, authority_index)
+            {
+                return InvalidTransaction::Stale.into();
+            }
+
+            let authorities = Authorities::<T>::get();
+            let Some((key, _)) = authorities.get(*authority_index as usize) else {
+                return InvalidTransaction::BadSigner.into();
+            };
+            let payload =
+                Self::unsigned_payload(*slot, snapshot_hash, *total_score, matches, *authority_index);
+            if !key.verify(&payload, signature) {
+                return InvalidTransaction::BadProof.into();
+            }
+
+            // Useless once the slot stops taking submissions.
+            let longevity = Self::blocks_until_close(*slot).max(1);
+            ValidTransaction::with_tag_prefix("poba")
+                .priority(T::UnsignedPriority::get())
+                .and_provides((slot, authority_index))
+                .longevity(longevity)
+                .propagate(true)
+                .build()
+        }
+    }
+
+    // -------- Helpers --------
+
+    impl<T: Config> Pallet<T> {
+        /// Choose the leading proposal: highest `total_score`, then most
+        /// matches (at equal score, serving more requests wins); if both tie
+        /// the earliest entry (first proposer to reach them) wins.
+        pub fn select_best(
+            entries: &[(T::AccountId, ProposalOf<T>)],
+        ) -> Option<&(T::AccountId, ProposalOf<T>)> {
+            let rank = |p: &ProposalOf<T>| (p.total_score, p.matches.len());
+            let mut best: Option<&(T::AccountId, ProposalOf<T>)> = None;
+            for entry in entries.iter() {
+                match best {
+                    Some((_, b)) if rank(&entry.1) <= rank(b) => {}
+                    _ => best = Some(entry),
+                }
+            }
+            best
+        }
+
+        /// Credit `WinnerReward` to the winner of `slot`, reserving it on the
+        /// reward pot until claimed. An underfunded pot skips the reward
+        /// instead of failing finalization.
+        fn accrue_reward(slot: u64, who: &T::AccountId) {
+            let amount = T::WinnerReward::get();
+            if amount.is_zero() {
+                return;
+            }
+
+            let who = who.clone();
+            match T::Currency::reserve(&T::RewardSource::get(), amount) {
+                Ok(()) => {
+                    Rewards::<T>::mutate(&who, |acc| *acc = acc.saturating_add(amount));
+                    Self::deposit_event(Event::RewardAccrued { slot, who, amount });
+                },
+                Err(_) => Self::deposit_event(Event::RewardSkipped { slot, who, amount }),
+            }
+        }
+
+        /// Recompute `BestProposal`/`BestProposer` of `slot` from its entries.
+        /// Returns the leading proposer, if any.
+        fn refresh_best(
+            slot: u64,
+            entries: &[(T::AccountId, ProposalOf<T>)],
+        ) -> Option<T::AccountId> {
+            match Self::select_best(entries) {
+                Some((who, best)) => {
+                    let changed = BestProposer::<T>::get(slot).as_ref() != Some(who) ||
+                        BestProposal::<T>::get(slot).as_ref() != Some(best);
+                    if changed {
+                        BestProposalSince::<T>::insert(
+                            slot,
+                            frame_system::Pallet::<T>::block_number(),
+                        );
+                    }
+                    BestProposal::<T>::insert(slot, best);
+                    BestProposer::<T>::insert(slot, who);
+                    Some(who.clone())
+                }
+                None => {
+                    BestProposal::<T>::remove(slot);
+                    BestProposer::<T>::remove(slot);
+                    BestProposalSince::<T>::remove(slot);
+                    None
+                }
+            }
+        }
+
+        /// Requests matched by finalized slots `>= slot` (for the runtime API).
+        pub fn matched_requests_since(slot: u64) -> Vec<[u8; 16]> {
+            MatchedRequests::<T>::iter()
+                .filter(|(_, matched_in)| *matched_in >= slot)
+                .map(|(request_uuid, _)| request_uuid)
+                .collect()
+        }
+
+        /// Up to `n` (capped at `MAX_TOP_PROPOSERS`) proposers by wins, then
+        /// total winning score (for the runtime API).
+        pub fn top_proposers(n: u32) -> Vec<(T::AccountId, ProposerStats)> {
+            let mut all: Vec<_> = ProposerStatsOf::<T>::iter().collect();
+            all.sort_by(|(a, x), (b, y)| {
+                y.wins
+                    .cmp(&x.wins)
+                    .then(y.total_winning_score.cmp(&x.total_winning_score))
+                    .then(a.cmp(b))
+            });
+            all.truncate(n.min(MAX_TOP_PROPOSERS) as usize);
+            all
+        }
+
+        /// Whether `slot` is finalized, including slots already pruned.
+        pub fn is_finalized(slot: u64) -> bool {
+            slot < PrunedUpTo::<T>::get() ||
+                FinalizedDigest::<T>::contains_key(slot) ||
+                FinalizedProposal::<T>::contains_key(slot)
+        }
+
+        /// Remove finalized proposals older than `RetainFinalizedSlots` below
+        /// the last finalized slot, at most `MAX_PRUNE_PER_BLOCK` slots per call.
+        /// Returns `(slots_scanned, entries_removed)`.
+        fn sweep_finalized() -> (u64, u32) {
+            let cutoff = LastFinalizedSlot::<T>::get().saturating_sub(T::RetainFinalizedSlots::get());
+            let from = PrunedUpTo::<T>::get();
+            if from >= cutoff {
+                return (0, 0);
+            }
+
+            let to = cutoff.min(from.saturating_add(MAX_PRUNE_PER_BLOCK));
+            let mut removed = 0u32;
+            for slot in from..to {
+                if FinalizedProposal::<T>::take(slot).is_some() {
+                    removed = removed.saturating_add(1);
+                }
+                // Counters and commitments of slots that never got finalized.
+                let _ = SubmissionCount::<T>::clear_prefix(slot, u32::MAX, None);
+                let _ = SubmittedHashes::<T>::clear_prefix(slot, u32::MAX, None);
+                let _ = UnsignedSubmissions::<T>::clear_prefix(slot, u32::MAX, None);
+                let _ = Commitments::<T>::clear_prefix(slot, u32::MAX, None);
+                RevealWindows::<T>::remove(slot);
+                UnmatchedRequests::<T>::remove(slot);
+                MarketSnapshot::<T>::remove(slot);
+                SnapshotRequests::<T>::remove(slot);
+            }
+            PrunedUpTo::<T>::put(to);
+            (to - from, removed)
+        }
+
+        /// Store the snapshot requests of `slot` not covered by `matches` and
+        /// report how many there are.
+        fn record_unmatched(slot: u64, matches: &[Match]) {
+            let matched: BTreeSet<[u8; 16]> = matches.iter().map(|m| m.request_uuid).collect();
+            let unmatched: Vec<[u8; 16]> = SnapshotRequests::<T>::get(slot)
+                .into_iter()
+                .filter(|r| !matched.contains(r))
+                .collect();
+            let count = unmatched.len() as u32;
+
+            let stored = BoundedVec::truncate_from(unmatched);
+            if !stored.is_empty() {
+                UnmatchedRequests::<T>::insert(slot, stored);
+            }
+            Self::deposit_event(Event::UnmatchedCount { slot, count });
+        }
+
+        /// Reject finalizing `slot` while its best proposal is younger than
+        /// `MinProposalAge` blocks.
+        fn ensure_proposal_aged(slot: u64) -> DispatchResult {
+            let since = BestProposalSince::<T>::get(slot).unwrap_or_default();
+            let now = frame_system::Pallet::<T>::block_number();
+            ensure!(
+                now >= since.saturating_add(T::MinProposalAge::get()),
+                Error::<T>::ProposalTooRecent
+            );
+            Ok(())
+        }
+
+        /// Whether `proposal` breaks the rule named by `reason`.
+        pub fn violates(proposal: &ProposalOf<T>, reason: ChallengeReason) -> bool {
+            match reason {
+                ChallengeReason::DuplicateRequest => {
+                    let mut seen = BTreeSet::new();
+                    proposal.matches.iter().any(|m| !seen.insert(m.request_uuid))
+                }
+                ChallengeReason::ScoreMismatch =>
+                    Self::partial_sum(&proposal.matches) != Some(proposal.total_score),
+                ChallengeReason::PriceOutOfBand => proposal.matches.iter().any(|m| {
+                    Self::ensure_price_in_band(&m.request_uuid, &m.offer_uuid, m.agreed_price_cents)
+                        .is_err()
+                }),
+                ChallengeReason::Outscored => false,
+            }
+        }
+
+        /// Sum of the partial scores, or `None` on overflow.
+        pub fn partial_sum(matches: &[Match]) -> Option<i64> {
+            matches.iter().try_fold(0i64, |acc, m| acc.checked_add(m.partial_score))
+        }
+
+        /// Current slot: `Time::now()` in whole `SlotDurationMs` windows. This is
+        /// the canonical derivation; off-chain callers read it through
+        /// `PobaApi::current_slot`.
+        pub fn current_slot() -> u64 {
+            let now: u64 = T::Time::now().saturated_into();
+            now / T::SlotDurationMs::get()
+        }
+
+        /// Check a match price against the referenced bids, where they are known.
+        fn ensure_price_in_band(request: &[u8; 16], offer: &[u8; 16], price: u32) -> DispatchResult {
+            if let Some(min) = T::PriceBands::offer_min_price(offer) {
+                ensure!(price >= min, Error::<T>::PriceOutOfBand);
+            }
+            if let Some(max) = T::PriceBands::request_max_price(request) {
+                ensure!(price <= max, Error::<T>::PriceOutOfBand);
+            }
+            Ok(())
+        }
+
+        /// Reject submissions for finalized slots and for slots outside
+        /// `[LastFinalizedSlot - MaxSlotLag, current_slot + MaxSlotLag]`.
+        fn ensure_slot_open(slot: u64) -> DispatchResult {
+            ensure!(!Self::is_finalized(slot), Error::<T>::SlotAlreadyFinalized);
+
+            let lag = T::MaxSlotLag::get();
+            let oldest = LastFinalizedSlot::<T>::get().saturating_sub(lag);
+            let newest = Self::current_slot().saturating_add(lag);
+            ensure!(slot >= oldest && slot <= newest, Error::<T>::SlotOutOfRange);
+
+            Ok(())
+        }
+
+        /// Checks shared by every way a proposal enters `slot`: an open slot
+        /// and a hash equal to its `MarketSnapshot`.
+        fn ensure_admissible(slot: u64, snapshot_hash: T::Hash) -> DispatchResult {
+            Self::ensure_slot_open(slot)?;
+            ensure!(
+                MarketSnapshot::<T>::get(slot).map(|s| s.snapshot_hash) == Some(snapshot_hash),
+                Error::<T>::SnapshotMismatch
+            );
+            Ok(())
+        }
+
+        /// Submissions `who` already made for `slot`, if another one fits
+        /// under `MaxSubmissionsPerSlot`.
+        fn ensure_submission_quota(slot: u64, who: &T::AccountId) -> Result<u32, DispatchError> {
+            let submissions = SubmissionCount::<T>::get(slot, who);
+            ensure!(
+                submissions < T::MaxSubmissionsPerSlot::get(),
+                Error::<T>::TooManySubmissions
+            );
+            Ok(submissions)
+        }
+
+        /// Reject slots too far from the current slot. Checked when a proposal
+        /// enters (submit or commit), not on reveal.
+        fn ensure_slot_in_window(slot: u64) -> DispatchResult {
+            let current = Self::current_slot();
+            let tolerance = T::SlotTolerance::get();
+            ensure!(
+                slot >= current.saturating_sub(tolerance) &&
+                    slot <= current.saturating_add(tolerance),
+                Error::<T>::SlotOutsideWindow
+            );
+            Ok(())
+        }
+
+        /// Phase of `slot` at the current block, or `None` if
+        /// `SubmissionWindowSlots` is zero.
+        pub fn slot_phase(slot: u64) -> Option<SlotPhase> {
+            let window = T::SubmissionWindowSlots::get();
+            if window.is_zero() {
+                return None;
+            }
+            Some(if Self::current_slot() < slot.saturating_add(window) {
+                SlotPhase::Submission
+            } else {
+                SlotPhase::Quiet
+            })
+        }
+
+        /// Reject submissions once the slot's submission window is over.
+        fn ensure_submission_open(slot: u64) -> DispatchResult {
+            ensure!(
+                Self::slot_phase(slot) != Some(SlotPhase::Quiet),
+                Error::<T>::SubmissionWindowClosed
+            );
+            Ok(())
+        }
+
+        /// Reject finalizing while the slot still accepts submissions.
+        fn ensure_submission_over(slot: u64) -> DispatchResult {
+            ensure!(
+                Self::slot_phase(slot) != Some(SlotPhase::Submission),
+                Error::<T>::SubmissionWindowStillOpen
+            );
+            Ok(())
+        }
+
+        /// Blocks, counting the current one, before `slot` stops taking
+        /// submissions: the end of its submission window when one is set,
+        /// else of `SlotTolerance`. The time left is converted with
+        /// `BlockTimeMs`. Zero once closed.
+        pub fn blocks_until_close(slot: u64) -> u64 {
+            let mut closes_at = slot.saturating_add(T::SlotTolerance::get()).saturating_add(1);
+            let window = T::SubmissionWindowSlots::get();
+            if !window.is_zero() {
+                closes_at = closes_at.min(slot.saturating_add(window));
+            }
+            let now: u64 = T::Time::now().saturated_into();
+            closes_at
+                .saturating_mul(T::SlotDurationMs::get())
+                .saturating_sub(now)
+                .div_ceil(T::BlockTimeMs::get())
+        }
+
+        /// Pool validity for `submit_proposal` / `finalize_slot` sent by an
+        /// authorized proposer: priority grows as the slot nears closing, up to
+        /// `UnsignedPriority` in its last block (and once it closed, for
+        /// finalization). Submissions expire when the slot closes. `None` for
+        /// other calls and senders.
+        pub fn deadline_validity(who: &T::AccountId, call: &Call<T>) -> Option<ValidTransaction> {
+            let (slot, expires) = match call {
+                Call::submit_proposal { slot, .. } => (*slot, true),
+                Call::finalize_slot { slot } => (*slot, false),
+                _ => return None,
+            };
+            if !AuthorizedProposers::<T>::contains_key(who) {
+                return None;
+            }
+            let left = Self::blocks_until_close(slot).max(1);
+            let mut valid = ValidTransaction {
+                priority: T::UnsignedPriority::get() / left,
+                ..Default::default()
+            };
+            if expires {
+                valid.longevity = left;
+            }
+            Some(valid)
+        }
+
+        /// Shared by `submit_proposal`, `submit_proposal_unsigned` and
+        /// `reveal_proposal`: validate the matches, reserve the bond and enter
+        /// the proposal for `slot`.
+        ///
+        /// With `bonded` unset (authority-signed payloads) `who` need not be
+        /// an authorized proposer and no bond is reserved: the authority
+        /// signature admits the proposal.
+        fn do_submit(
+            who: T::AccountId,
+            slot: u64,
+            snapshot_hash: T::Hash,
+            total_score: i64,
+            matches: Vec<([u8; 16], [u8; 16], u32, i64)>,
+            bonded: bool,
+        ) -> DispatchResult {
+            if bonded {
+                ensure!(
+                    AuthorizedProposers::<T>::contains_key(&who),
+                    Error::<T>::NotAuthorizedProposer
+                );
+            }
+            Self::ensure_admissible(slot, snapshot_hash)?;
+
+            // Same proposal as the last accepted one: already done, no event.
+            let key = Self::submission_key(slot, &snapshot_hash, total_score, &matches);
+            if SubmittedHashes::<T>::get(slot, &who) == Some(key) {
+                return Ok(());
+            }
+
+            let submissions = Self::ensure_submission_quota(slot, &who)?;
+
+            let proposal = Self::build_proposal(total_score, matches)?;
+            let total_score = proposal.total_score;
+            let matches_len: u32 = proposal.matches.len() as u32;
+
+            if bonded {
+                Self::reserve_bond(slot, &who)?;
+            }
+
+            // שמירת ההצעה ברשימת ההצעות של הסלוט (רשומה אחת לכל מציע),
+            // ועדכון BestProposal לפי הבחירה הדטרמיניסטית מתוך הרשימה.
+            let is_best = Proposals::<T>::try_mutate(slot, |entries| -> Result<bool, DispatchError> {
+                match entries.iter_mut().find(|(p, _)| p == &who) {
+                    Some(entry) => {
+                        let needed = entry
+                            .1
+                            .total_score
+                            .checked_add(T::MinImprovementDelta::get())
+                            .ok_or(Error::<T>::ScoreOverflow)?;
+                        ensure!(total_score >= needed, Error::<T>::InsufficientImprovement);
+                        entry.1 = proposal.clone();
+                    }
+                    None => entries
+                        .try_push((who.clone(), proposal.clone()))
+                        .map_err(|_| Error::<T>::TooManyProposers)?,
+                }
+
+                Ok(Self::refresh_best(slot, entries).as_ref() == Some(&who))
+            })?;
+            SubmissionCount::<T>::insert(slot, &who, submissions.saturating_add(1));
+            SubmittedHashes::<T>::insert(slot, &who, key);
+            ProposerStatsOf::<T>::mutate(&who, |stats| {
+                stats.submitted = stats.submitted.saturating_add(1)
+            });
+
+            // 🔔 אירוע תמידי – כל הגשה נרשמת, כולל מי הגיש
+            Self::deposit_event(Event::ProposalSubmitted {
+                slot,
+                total_score,
+                matches: matches_len,
+                proposer: who,
+                is_best,
+            });
+
+            Ok(())
+        }
+
+        /// Convert raw matches into a bounded proposal: drops or rejects
+        /// already matched requests, checks prices against `PriceBands` and
+        /// requires `total_score` to be the (non-overflowing) sum of partials.
+        fn build_proposal(
+            total_score: i64,
+            matches: Vec<([u8; 16], [u8; 16], u32, i64)>,
+        ) -> Result<ProposalOf<T>, DispatchError> {
+            // Convert tuples → Match → BoundedVec
+            let filter_matched = T::FilterMatchedRequests::get();
+            let mut total_score = total_score;
+            let mut tmp: Vec<Match> = Vec::with_capacity(matches.len());
+            for (rq, of, price, part) in matches.into_iter() {
+                ensure!(
+                    (0..=T::MaxPartialScore::get()).contains(&part),
+                    Error::<T>::InvalidPartialScore
+                );
+                // Requests finalized in an earlier slot are off the market.
+                if MatchedRequests::<T>::contains_key(rq) {
+                    ensure!(filter_matched, Error::<T>::RequestAlreadyMatched);
+                    total_score = total_score.checked_sub(part).ok_or(Error::<T>::ScoreOverflow)?;
+                    continue;
+                }
+                Self::ensure_price_in_band(&rq, &of, price)?;
+                tmp.push(Match {
+                    request_uuid: rq,
+                    offer_uuid: of,
+                    agreed_price_cents: price,
+                    partial_score: part,
+                });
+            }
+            let bounded: MatchesOf<T> =
+                BoundedVec::try_from(tmp).map_err(|_| Error::<T>::TooManyMatches)?;
+
+            // Reject empty proposals
+            ensure!(!bounded.is_empty(), Error::<T>::EmptyMatches);
+
+            let sum = Self::partial_sum(&bounded).ok_or(Error::<T>::ScoreOverflow)?;
+            ensure!(sum == total_score, Error::<T>::ScoreMismatch);
+
+            Ok(Proposal { total_score, matches: bounded })
+        }
+
+        /// Bytes an authority signs for `submit_proposal_unsigned`.
+        pub fn unsigned_payload(
+            slot: u64,
+            snapshot_hash: &T::Hash,
+            total_score: i64,
+            matches: &[([u8; 16], [u8; 16], u32, i64)],
+            authority_index: u32,
+        ) -> Vec<u8> {
+            (KEY_TYPE, slot, snapshot_hash, total_score, matches, authority_index).encode()
+        }
+
+        /// Sign a proposal with the first local key found in `Authorities`.
+        /// Returns the authority index and signature to pass to
+        /// `submit_proposal_unsigned`; needs a keystore with a `KEY_TYPE` key.
+        pub fn sign_proposal(
+            slot: u64,
+            snapshot_hash: &T::Hash,
+            total_score: i64,
+            matches: &[([u8; 16], [u8; 16], u32, i64)],
+        ) -> Option<(u32, AuthoritySignatureOf<T>)> {
+            let local = T::AuthorityId::all();
+            let authorities = Authorities::<T>::get();
+            let (index, (key, _)) =
+                authorities.iter().enumerate().find(|(_, (key, _))| local.contains(key))?;
+            let index = index as u32;
+            let payload = Self::unsigned_payload(slot, snapshot_hash, total_score, matches, index);
+            key.sign(&payload).map(|signature| (index, signature))
+        }
+
+        /// Commitment `reveal_proposal` checks against.
+        pub fn commitment_of(
+            total_score: i64,
+            matches: &[([u8; 16], [u8; 16], u32, i64)],
+            salt: &[u8; 32],
+        ) -> T::Hash {
+            T::Hashing::hash_of(&(total_score, matches, salt))
+        }
+
+        /// Idempotency key of a submission: the hash of its whole payload,
+        /// `(slot, snapshot_hash, total_score, matches)`, with the matches
+        /// sorted so their order does not matter.
+        pub fn submission_key(
+            slot: u64,
+            snapshot_hash: &T::Hash,
+            total_score: i64,
+            matches: &[([u8; 16], [u8; 16], u32, i64)],
+        ) -> T::Hash {
+            let mut sorted = matches.to_vec();
+            sorted.sort();
+            T::Hashing::hash_of(&(slot, snapshot_hash, total_score, sorted))
+        }
+
+        /// Reserve the bond once per (slot, proposer); resubmissions reuse it.
+        fn reserve_bond(slot: u64, who: &T::AccountId) -> DispatchResult {
+            if !Bonds::<T>::contains_key(slot, who) {
+                let bond = T::ProposalBond::get();
+                T::Currency::reserve(who, bond).map_err(|_| Error::<T>::InsufficientBond)?;
+                Bonds::<T>::insert(slot, who, bond);
+                Self::deposit_event(Event::BondReserved { slot, who: who.clone(), amount: bond });
+            }
+            Ok(())
+        }
+
+        /// Reject finalizing a slot whose reveal window is still running.
+        fn ensure_reveal_over(slot: u64) -> DispatchResult {
+            if let Some((_, reveal_until)) = RevealWindows::<T>::get(slot) {
+                let now = frame_system::Pallet::<T>::block_number();
+                ensure!(now >= reveal_until, Error::<T>::RevealPeriodActive);
+            }
+            Ok(())
+        }
+
+        /// Slash the bonds behind commitments of `slot` that were never revealed.
+        fn forfeit_unrevealed(slot: u64) {
+            RevealWindows::<T>::remove(slot);
+            for (who, _) in Commitments::<T>::drain_prefix(slot) {
+                if let Some(amount) = Bonds::<T>::take(slot, &who) {
+                    let _ = T::Currency::slash_reserved(&who, amount);
+                    Self::deposit_event(Event::CommitmentForfeited { slot, who, amount });
+                }
+            }
+        }
+
+        /// Pay the bond held for (slot, who) to `challenger`, if any.
+        fn slash_bond_to(slot: u64, who: &T::AccountId, challenger: &T::AccountId) {
+            if let Some(amount) = Bonds::<T>::take(slot, who) {
+                let _ =
+                    T::Currency::repatriate_reserved(who, challenger, amount, BalanceStatus::Free);
+                Self::deposit_event(Event::BondSlashed {
+                    slot,
+                    who: who.clone(),
+                    challenger: challenger.clone(),
+                    amount,
+                });
+            }
+        }
+
+        /// Slash `SlashFraction` of `who`'s bond for `slot`, pay `ReporterShare`
+        /// of it to `reporter`, burn the rest and return what is left.
+        /// Returns `(slashed, reward)`.
+        fn slash_winner_bond(
+            slot: u64,
+            who: &T::AccountId,
+            reporter: &T::AccountId,
+        ) -> (BalanceOf<T>, BalanceOf<T>) {
+            let Some(bond) = Bonds::<T>::take(slot, who) else {
+                return (Zero::zero(), Zero::zero());
+            };
+            let slashed = T::SlashFraction::get() * bond;
+            let reward = T::ReporterShare::get() * slashed;
+
+            let _ = T::Currency::repatriate_reserved(who, reporter, reward, BalanceStatus::Free);
+            let _ = T::Currency::slash_reserved(who, slashed.saturating_sub(reward));
+            T::Currency::unreserve(who, bond.saturating_sub(slashed));
+            (slashed, reward)
+        }
+
+        /// Unreserve and forget the bond held for (slot, who), if any.
+        fn return_bond(slot: u64, who: &T::AccountId) {
+            if let Some(amount) = Bonds::<T>::take(slot, who) {
+                T::Currency::unreserve(who, amount);
+                Self::deposit_event(Event::BondReturned { slot, who: who.clone(), amount });
+            }
+        }
+    }
+}
 
 // Re-export for `impl pallet_poba::Config for Runtime`
 pub use pallet::*;
+
+/// Origin check passing for signed accounts in `AuthorizedProposers`.
+///
+/// Lets the proposer set double as the finalizer set, e.g. combined with
+/// root via `EitherOfDiverse`.
+pub struct EnsureAuthorizedProposer<T>(PhantomData<T>);
+
+impl<T: Config> EnsureOrigin<T::RuntimeOrigin> for EnsureAuthorizedProposer<T> {
+    type Success = T::AccountId;
+
+    fn try_origin(o: T::RuntimeOrigin) -> Result<Self::Success, T::RuntimeOrigin> {
+        o.into().and_then(|o| match o {
+            frame_system::RawOrigin::Signed(who) if AuthorizedProposers::<T>::contains_key(&who) =>
+                Ok(who),
+            r => Err(T::RuntimeOrigin::from(r)),
+        })
+    }
+
+    #[cfg(feature = "runtime-benchmarks")]
+    fn try_successful_origin() -> Result<T::RuntimeOrigin, ()> {
+        Err(())
+    }
+}
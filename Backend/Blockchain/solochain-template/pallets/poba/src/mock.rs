@@ -0,0 +1,312 @@
+use crate as pallet_poba;
+use frame_support::{
+    derive_impl, parameter_types,
+    traits::{ConstI64, ConstU32, ConstU64, EqualPrivilegeOnly},
+    weights::Weight,
+};
+use sp_core::H256;
+use sp_runtime::{testing::UintAuthorityId, BuildStorage, Perbill};
+
+type Block = frame_system::mocking::MockBlock<Test>;
+
+#[frame_support::runtime]
+mod runtime {
+    // The main runtime
+    #[runtime::runtime]
+    // Runtime Types to be generated
+    #[runtime::derive(
+        RuntimeCall,
+        RuntimeEvent,
+        RuntimeError,
+        RuntimeOrigin,
+        RuntimeFreezeReason,
+        RuntimeHoldReason,
+        RuntimeSlashReason,
+        RuntimeLockId,
+        RuntimeTask,
+        RuntimeViewFunction
+    )]
+    pub struct Test;
+
+    #[runtime::pallet_index(0)]
+    pub type System = frame_system::Pallet<Test>;
+
+    #[runtime::pallet_index(1)]
+    pub type Balances = pallet_balances::Pallet<Test>;
+
+    #[runtime::pallet_index(2)]
+    pub type PoBA = pallet_poba::Pallet<Test>;
+
+    #[runtime::pallet_index(3)]
+    pub type Escrow = pallet_escrow::Pallet<Test>;
+
+    #[runtime::pallet_index(4)]
+    pub type Scheduler = pallet_scheduler::Pallet<Test>;
+}
+
+#[derive_impl(frame_system::config_preludes::TestDefaultConfig)]
+impl frame_system::Config for Test {
+    type Block = Block;
+    type AccountData = pallet_balances::AccountData<u64>;
+}
+
+#[derive_impl(pallet_balances::config_preludes::TestDefaultConfig)]
+impl pallet_balances::Config for Test {
+    type AccountStore = System;
+}
+
+parameter_types! {
+    /// Room for every timeout scheduled in a test block.
+    pub MaximumSchedulerWeight: Weight = Weight::from_parts(1_000_000_000_000, u64::MAX);
+}
+
+impl pallet_scheduler::Config for Test {
+    type RuntimeEvent = RuntimeEvent;
+    type RuntimeOrigin = RuntimeOrigin;
+    type PalletsOrigin = OriginCaller;
+    type RuntimeCall = RuntimeCall;
+    type MaximumWeight = MaximumSchedulerWeight;
+    type ScheduleOrigin = frame_system::EnsureRoot<u64>;
+    type MaxScheduledPerBlock = ConstU32<16>;
+    type WeightInfo = ();
+    type OriginPrivilegeCmp = EqualPrivilegeOnly;
+    type Preimages = ();
+}
+
+/// Bond reserved per (slot, proposer) in tests.
+pub const BOND: u64 = 10;
+/// Accepted distance (in slots) around the current/finalized slot.
+pub const MAX_SLOT_LAG: u64 = 5;
+/// Distinct proposers kept per slot.
+pub const MAX_PROPOSERS: u32 = 3;
+/// Reward accrued by the winner of each finalized slot.
+pub const REWARD: u64 = 5;
+/// Matches allowed in one proposal.
+pub const MAX_MATCHES: u32 = 8;
+/// Accepted submissions per (slot, proposer).
+pub const MAX_SUBMISSIONS: u32 = 3;
+/// Score gain a resubmission needs over the previous one.
+pub const MIN_IMPROVEMENT: i64 = 5;
+/// Finalized slots kept below the last finalized one.
+pub const RETAIN_FINALIZED: u64 = 3;
+/// Unmatched request UUIDs stored per finalized slot.
+pub const MAX_UNMATCHED: u32 = 2;
+/// Slot length in milliseconds of `MockTime`.
+pub const SLOT_MS: u64 = 6_000;
+
+parameter_types! {
+    /// Challenge window; zero (single-phase finalize) unless a test sets it.
+    pub static ChallengeWindow: u64 = 0;
+    /// Commit window; zero (plain `submit_proposal`) unless a test sets it.
+    pub static CommitWindow: u64 = 0;
+    /// Reveal window following the commit window.
+    pub static RevealWindow: u64 = 0;
+    /// Minimum age of the best proposal before finalization; zero unless set.
+    pub static MinProposalAge: u64 = 0;
+    /// Whether empty slots are skipped instead of rejected.
+    pub static AllowEmptySlots: bool = false;
+    /// Whether already matched requests are filtered instead of rejected.
+    pub static FilterMatchedRequests: bool = false;
+    /// Account rewards are paid from; `POT` (funded) unless a test sets it.
+    pub static RewardPot: u64 = POT;
+    /// Slot window around the current slot; unbounded unless a test sets it.
+    pub static SlotTolerance: u64 = u64::MAX;
+    /// Finalization thresholds; off unless a test sets them.
+    pub static MinFinalizeScore: i64 = i64::MIN;
+    pub static MinFinalizeMatches: u32 = 0;
+    /// Partial score bound; four maximal matches still fit in an i64.
+    pub static MaxPartialScore: i64 = i64::MAX / 4;
+    /// Submission window per slot; zero (no phases) unless a test sets it.
+    pub static SubmissionWindow: u64 = 0;
+    /// Report window after finalization; zero (bond returned) unless set.
+    pub static ReportWindow: u64 = 0;
+    /// Milliseconds reported by `MockTime`; unset, one slot per block.
+    pub static Now: Option<u64> = None;
+    /// Expected block time; one slot unless a test sets it.
+    pub static BlockTime: u64 = SLOT_MS;
+    /// Half of a reported winner's bond is slashed, 40% of that rewarded.
+    pub SlashFraction: Perbill = Perbill::from_percent(50);
+    pub ReporterShare: Perbill = Perbill::from_percent(40);
+}
+
+impl pallet_poba::Config for Test {
+    type RuntimeEvent = RuntimeEvent;
+    type Currency = Balances;
+    type ProposalBond = ConstU64<BOND>;
+    type MaxSlotLag = ConstU64<MAX_SLOT_LAG>;
+    type MaxMatchesPerProposal = ConstU32<MAX_MATCHES>;
+    type MaxProposersPerSlot = ConstU32<MAX_PROPOSERS>;
+    type WinnerReward = ConstU64<REWARD>;
+    type RewardSource = RewardPot;
+    type AdminOrigin = frame_system::EnsureRoot<u64>;
+    type ChallengeWindowBlocks = ChallengeWindow;
+    type FinalizeOrigin = frame_support::traits::EitherOfDiverse<
+        frame_system::EnsureRoot<u64>,
+        pallet_poba::EnsureAuthorizedProposer<Test>,
+    >;
+    type MinProposalAge = MinProposalAge;
+    type RetainFinalizedSlots = ConstU64<RETAIN_FINALIZED>;
+    type AllowEmptySlots = AllowEmptySlots;
+    type OnSlotFinalized = EscrowOnSlotFinalized;
+    type FilterMatchedRequests = FilterMatchedRequests;
+    type MaxSubmissionsPerSlot = ConstU32<MAX_SUBMISSIONS>;
+    type MinImprovementDelta = ConstI64<MIN_IMPROVEMENT>;
+    type PriceBands = TestPriceBands;
+    type CommitWindowBlocks = CommitWindow;
+    type RevealWindowBlocks = RevealWindow;
+    type SlotTolerance = SlotTolerance;
+    type Time = MockTime;
+    type SlotDurationMs = ConstU64<SLOT_MS>;
+    type BlockTimeMs = BlockTime;
+    type MinFinalizeScore = MinFinalizeScore;
+    type MinFinalizeMatches = MinFinalizeMatches;
+    type AuthorityId = UintAuthorityId;
+    type MaxAuthorities = ConstU32<4>;
+    type UnsignedPriority = ConstU64<100>;
+    type MaxSnapshotRequests = ConstU32<8>;
+    type MaxUnmatched = ConstU32<MAX_UNMATCHED>;
+    type MaxPartialScore = MaxPartialScore;
+    type SubmissionWindowSlots = SubmissionWindow;
+    type ReportWindowBlocks = ReportWindow;
+    type SlashFraction = SlashFraction;
+    type ReporterShare = ReporterShare;
+}
+
+/// Clock of the mock: `Now` when a test sets it, else the start of the slot
+/// numbered like the current block, so tests can drive slots with
+/// `System::set_block_number`.
+pub struct MockTime;
+
+impl frame_support::traits::Time for MockTime {
+    type Moment = u64;
+
+    fn now() -> u64 {
+        Now::get().unwrap_or_else(|| System::block_number() * SLOT_MS)
+    }
+}
+
+/// Bids with first byte `BANDED` have known prices: offer minimum
+/// `BAND_MIN`, request maximum `BAND_MAX`. Other bids are unchecked.
+pub struct TestPriceBands;
+
+pub const BANDED: u8 = 200;
+pub const BAND_MIN: u32 = 500;
+pub const BAND_MAX: u32 = 1_500;
+
+impl pallet_poba::PriceBands for TestPriceBands {
+    fn offer_min_price(offer: &[u8; 16]) -> Option<u32> {
+        (offer[0] == BANDED).then_some(BAND_MIN)
+    }
+    fn request_max_price(request: &[u8; 16]) -> Option<u32> {
+        (request[0] == BANDED).then_some(BAND_MAX)
+    }
+}
+
+/// Creates escrows for finalized matches and flags them on revocation,
+/// mirroring the runtime adapter.
+pub struct EscrowOnSlotFinalized;
+
+impl pallet_poba::OnSlotFinalized<u64> for EscrowOnSlotFinalized {
+    fn on_slot_finalized(slot: u64, matches: &[pallet_poba::Match]) {
+        for m in matches {
+            Escrow::create_for_match(
+                slot,
+                m.request_uuid,
+                m.offer_uuid,
+                m.agreed_price_cents.into(),
+            );
+        }
+    }
+
+    fn on_slot_revoked(slot: u64, matches: &[pallet_poba::Match]) {
+        for m in matches {
+            Escrow::flag_revoked_match(slot, m.request_uuid);
+        }
+    }
+}
+
+/// Test parties: the driver is the offer's first byte, the payer is
+/// `PAYER_BASE` + the request's first byte. Request byte 0 is unknown.
+pub struct TestMatchParties;
+
+/// Offset for payer accounts derived from request uuids.
+pub const PAYER_BASE: u64 = 100;
+
+impl pallet_escrow::MatchParties<u64> for TestMatchParties {
+    fn parties(request: &[u8; 16], offer: &[u8; 16]) -> Option<(u64, u64)> {
+        (request[0] != 0).then(|| (offer[0] as u64, PAYER_BASE + request[0] as u64))
+    }
+}
+
+impl pallet_escrow::Config for Test {
+    type RuntimeEvent = RuntimeEvent;
+    type Balance = u64;
+    type ConfirmationTimeoutBlocks = ConstU64<10>;
+    type MatchParties = TestMatchParties;
+    type MaxBatchSize = ConstU32<16>;
+    type EarlyBonusBps = ConstU32<0>;
+    type EarlyBonusMarginBlocks = ConstU64<0>;
+    type AdminOrigin = frame_system::EnsureRoot<u64>;
+    type RequireMarketConsistency = frame_support::traits::ConstBool<false>;
+    type RuntimeCall = RuntimeCall;
+    type PalletsOrigin = OriginCaller;
+    type Scheduler = Scheduler;
+}
+
+pub const ALICE: u64 = 1;
+pub const BOB: u64 = 2;
+pub const CHARLIE: u64 = 3;
+/// Funded but not an authorized proposer at genesis.
+pub const DAVE: u64 = 4;
+/// Account without any funds.
+pub const POOR: u64 = 9;
+/// Funded reward pot.
+pub const POT: u64 = 50;
+/// Reward pot without any funds.
+pub const EMPTY_POT: u64 = 51;
+
+/// Market snapshot hash every test slot below `OPEN_SLOTS` starts with.
+pub const SNAPSHOT: H256 = H256::repeat_byte(7);
+/// Slots that get `SNAPSHOT`, as the backend would publish at slot open.
+pub const OPEN_SLOTS: u64 = 64;
+
+// Build genesis storage according to the mock runtime.
+pub fn new_test_ext() -> sp_io::TestExternalities {
+    let mut t = frame_system::GenesisConfig::<Test>::default().build_storage().unwrap();
+    pallet_balances::GenesisConfig::<Test> {
+        balances: vec![(ALICE, 1_000), (BOB, 1_000), (CHARLIE, 1_000), (DAVE, 1_000), (POT, 1_000)],
+        ..Default::default()
+    }
+    .assign_storage(&mut t)
+    .unwrap();
+    pallet_poba::GenesisConfig::<Test> {
+        proposers: vec![ALICE, BOB, CHARLIE],
+        // Index 0 signs for ALICE, index 1 for BOB.
+        authorities: vec![(UintAuthorityId(1), ALICE), (UintAuthorityId(2), BOB)],
+        finalized: vec![],
+    }
+    .assign_storage(&mut t)
+    .unwrap();
+
+    let mut ext: sp_io::TestExternalities = t.into();
+    // Go past genesis block so events get deposited
+    ext.execute_with(|| {
+        System::set_block_number(1);
+        for slot in 0..OPEN_SLOTS {
+            pallet_poba::MarketSnapshot::<Test>::insert(
+                slot,
+                pallet_poba::MarketSnapshotInfo {
+                    snapshot_hash: SNAPSHOT,
+                    request_count: 1,
+                    offer_count: 1,
+                },
+            );
+        }
+    });
+    ext
+}
+
+/// Build a match tuple as accepted by `submit_proposal`.
+pub fn m(request: u8, offer: u8, partial_score: i64) -> ([u8; 16], [u8; 16], u32, i64) {
+    ([request; 16], [offer; 16], 1_000, partial_score)
+}
@@ -0,0 +1,2010 @@
+use crate::{
+    mock::*, BestProposal, BestProposer, Bonds, ChallengeEvidence, ChallengeReason, Commitments,
+    Error, Event, FinalizedDigest, FinalizedProposal, InvalidityProof, MarketSnapshot,
+    MarketSnapshotInfo, MatchedRequests, PendingFinalizations, PhaseSlot, Proposals, ProposerStats,
+    Rewards, SlotPhase, SnapshotRequests, SubmissionCount, SubmittedHashes, UnmatchedRequests,
+};
+use codec::Encode;
+use frame_support::{assert_noop, assert_ok, traits::Hooks};
+use sp_core::H256;
+use sp_runtime::{
+    testing::{TestSignature, UintAuthorityId},
+    traits::{Hash, ValidateUnsigned},
+    transaction_validity::{InvalidTransaction, TransactionSource},
+    BuildStorage, DispatchError, RuntimeAppPublic,
+};
+
+#[test]
+fn first_submission_reserves_bond() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(PoBA::submit_proposal(RuntimeOrigin::signed(ALICE), 1, SNAPSHOT, 50, vec![m(1, 1, 50)]));
+
+        assert_eq!(Bonds::<Test>::get(1, ALICE), Some(BOND));
+        assert_eq!(Balances::reserved_balance(ALICE), BOND);
+        System::assert_has_event(Event::BondReserved { slot: 1, who: ALICE, amount: BOND }.into());
+
+        // Resubmitting for the same slot does not reserve a second bond.
+        assert_ok!(PoBA::submit_proposal(RuntimeOrigin::signed(ALICE), 1, SNAPSHOT, 60, vec![m(1, 1, 60)]));
+        assert_eq!(Balances::reserved_balance(ALICE), BOND);
+    });
+}
+
+#[test]
+fn submission_without_funds_fails() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(PoBA::add_proposer(RuntimeOrigin::root(), POOR));
+        assert_noop!(
+            PoBA::submit_proposal(RuntimeOrigin::signed(POOR), 1, SNAPSHOT, 50, vec![m(1, 1, 50)]),
+            Error::<Test>::InsufficientBond
+        );
+    });
+}
+
+#[test]
+fn best_proposal_follows_highest_score() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(PoBA::submit_proposal(RuntimeOrigin::signed(ALICE), 1, SNAPSHOT, 50, vec![m(1, 1, 50)]));
+        assert_eq!(BestProposer::<Test>::get(1), Some(ALICE));
+        assert_eq!(BestProposal::<Test>::get(1).unwrap().total_score, 50);
+
+        // A higher score takes the lead.
+        assert_ok!(PoBA::submit_proposal(RuntimeOrigin::signed(BOB), 1, SNAPSHOT, 80, vec![m(1, 2, 80)]));
+        assert_eq!(BestProposer::<Test>::get(1), Some(BOB));
+        assert_eq!(BestProposal::<Test>::get(1).unwrap().total_score, 80);
+
+        // A lower one is recorded and announced, but does not lead.
+        assert_ok!(PoBA::submit_proposal(RuntimeOrigin::signed(CHARLIE), 1, SNAPSHOT, 60, vec![m(1, 3, 60)]));
+        assert_eq!(BestProposer::<Test>::get(1), Some(BOB));
+        System::assert_last_event(
+            Event::ProposalSubmitted { slot: 1, total_score: 60, matches: 1, proposer: CHARLIE, is_best: false }
+                .into(),
+        );
+    });
+}
+
+#[test]
+fn slot_finalized_names_the_winning_proposer() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(PoBA::submit_proposal(RuntimeOrigin::signed(ALICE), 1, SNAPSHOT, 40, vec![m(1, 1, 40)]));
+        assert_ok!(PoBA::submit_proposal(RuntimeOrigin::signed(BOB), 1, SNAPSHOT, 60, vec![m(1, 2, 60)]));
+        assert_ok!(PoBA::submit_proposal(RuntimeOrigin::signed(CHARLIE), 1, SNAPSHOT, 50, vec![m(1, 3, 50)]));
+
+        // Finalized by Alice, won by Bob.
+        assert_ok!(PoBA::finalize_slot(RuntimeOrigin::signed(ALICE), 1));
+        System::assert_last_event(
+            Event::SlotFinalized {
+                slot: 1,
+                total_score: 60,
+                matches: 1,
+                snapshot_hash: SNAPSHOT,
+                winner: BOB,
+            }
+            .into(),
+        );
+    });
+}
+
+#[test]
+fn equal_score_with_more_matches_takes_the_lead() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(PoBA::submit_proposal(RuntimeOrigin::signed(ALICE), 1, SNAPSHOT, 80, vec![m(1, 1, 80)]));
+
+        // Same score, more requests served: Bob leads.
+        assert_ok!(PoBA::submit_proposal(
+            RuntimeOrigin::signed(BOB),
+            1,
+            SNAPSHOT,
+            80,
+            vec![m(1, 2, 40), m(2, 2, 40)]
+        ));
+        assert_eq!(BestProposer::<Test>::get(1), Some(BOB));
+        System::assert_last_event(
+            Event::ProposalSubmitted { slot: 1, total_score: 80, matches: 2, proposer: BOB, is_best: true }
+                .into(),
+        );
+
+        // More matches do not make up for a lower score.
+        assert_ok!(PoBA::submit_proposal(
+            RuntimeOrigin::signed(CHARLIE),
+            1,
+            SNAPSHOT,
+            79,
+            vec![m(1, 3, 26), m(2, 3, 26), m(3, 3, 27)]
+        ));
+        assert_eq!(BestProposer::<Test>::get(1), Some(BOB));
+
+        assert_ok!(PoBA::finalize_slot(RuntimeOrigin::signed(ALICE), 1));
+        assert_eq!(FinalizedProposal::<Test>::get(1).unwrap().matches.len(), 2);
+    });
+}
+
+#[test]
+fn finalize_moves_best_to_finalized() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(PoBA::submit_proposal(RuntimeOrigin::signed(ALICE), 3, SNAPSHOT, 50, vec![m(1, 1, 50)]));
+        let best = BestProposal::<Test>::get(3).unwrap();
+
+        assert_ok!(PoBA::finalize_slot(RuntimeOrigin::signed(ALICE), 3));
+
+        assert_eq!(FinalizedProposal::<Test>::get(3), Some(best));
+        assert_eq!(BestProposal::<Test>::get(3), None);
+        assert_eq!(BestProposer::<Test>::get(3), None);
+        assert_eq!(PoBA::last_finalized_slot(), 3);
+    });
+}
+
+#[test]
+fn finalize_refunds_winner_and_losers_reclaim() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(PoBA::submit_proposal(RuntimeOrigin::signed(ALICE), 1, SNAPSHOT, 50, vec![m(1, 1, 50)]));
+        assert_ok!(PoBA::submit_proposal(RuntimeOrigin::signed(BOB), 1, SNAPSHOT, 80, vec![m(1, 2, 80)]));
+        assert_eq!(BestProposer::<Test>::get(1), Some(BOB));
+
+        assert_ok!(PoBA::finalize_slot(RuntimeOrigin::signed(CHARLIE), 1));
+
+        // Winner's bond is returned immediately.
+        assert_eq!(Balances::reserved_balance(BOB), 0);
+        assert_eq!(Bonds::<Test>::get(1, BOB), None);
+        System::assert_has_event(Event::BondReturned { slot: 1, who: BOB, amount: BOND }.into());
+
+        // Loser's bond stays reserved until reclaimed.
+        assert_eq!(Balances::reserved_balance(ALICE), BOND);
+        assert_ok!(PoBA::reclaim_bond(RuntimeOrigin::signed(ALICE), 1));
+        assert_eq!(Balances::reserved_balance(ALICE), 0);
+        System::assert_last_event(Event::BondReturned { slot: 1, who: ALICE, amount: BOND }.into());
+    });
+}
+
+#[test]
+fn reclaim_requires_finalized_slot() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(PoBA::submit_proposal(RuntimeOrigin::signed(ALICE), 1, SNAPSHOT, 50, vec![m(1, 1, 50)]));
+        assert_noop!(
+            PoBA::reclaim_bond(RuntimeOrigin::signed(ALICE), 1),
+            Error::<Test>::SlotNotFinalized
+        );
+    });
+}
+
+#[test]
+fn double_reclaim_is_rejected() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(PoBA::submit_proposal(RuntimeOrigin::signed(ALICE), 1, SNAPSHOT, 50, vec![m(1, 1, 50)]));
+        assert_ok!(PoBA::submit_proposal(RuntimeOrigin::signed(BOB), 1, SNAPSHOT, 80, vec![m(1, 2, 80)]));
+        assert_ok!(PoBA::finalize_slot(RuntimeOrigin::signed(CHARLIE), 1));
+
+        assert_ok!(PoBA::reclaim_bond(RuntimeOrigin::signed(ALICE), 1));
+        assert_noop!(PoBA::reclaim_bond(RuntimeOrigin::signed(ALICE), 1), Error::<Test>::NoBond);
+        // The winner's bond was already returned by finalize_slot.
+        assert_noop!(PoBA::reclaim_bond(RuntimeOrigin::signed(BOB), 1), Error::<Test>::NoBond);
+        assert_eq!(Balances::reserved_balance(ALICE), 0);
+        assert_eq!(Balances::free_balance(ALICE), 1_000);
+    });
+}
+
+#[test]
+fn submission_to_finalized_slot_is_rejected() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(PoBA::submit_proposal(RuntimeOrigin::signed(ALICE), 1, SNAPSHOT, 50, vec![m(1, 1, 50)]));
+        assert_ok!(PoBA::finalize_slot(RuntimeOrigin::signed(ALICE), 1));
+
+        assert_noop!(
+            PoBA::submit_proposal(RuntimeOrigin::signed(BOB), 1, SNAPSHOT, 90, vec![m(1, 2, 90)]),
+            Error::<Test>::SlotAlreadyFinalized
+        );
+    });
+}
+
+#[test]
+fn submission_outside_slot_window_is_rejected() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(20);
+
+        // Far in the future.
+        assert_noop!(
+            PoBA::submit_proposal(RuntimeOrigin::signed(ALICE), 20 + MAX_SLOT_LAG + 1, SNAPSHOT, 50, vec![m(1, 1, 50)]),
+            Error::<Test>::SlotOutOfRange
+        );
+
+        // Too far behind the last finalized slot.
+        assert_ok!(PoBA::submit_proposal(RuntimeOrigin::signed(ALICE), 20, SNAPSHOT, 50, vec![m(1, 1, 50)]));
+        assert_ok!(PoBA::finalize_slot(RuntimeOrigin::signed(ALICE), 20));
+        assert_noop!(
+            PoBA::submit_proposal(RuntimeOrigin::signed(BOB), 20 - MAX_SLOT_LAG - 1, SNAPSHOT, 50, vec![m(2, 2, 50)]),
+            Error::<Test>::SlotOutOfRange
+        );
+    });
+}
+
+#[test]
+fn slot_must_be_within_tolerance_of_block_number() {
+    new_test_ext().execute_with(|| {
+        SlotTolerance::set(2);
+        System::set_block_number(10);
+
+        // Both edges of [8, 12] are accepted.
+        assert_ok!(PoBA::submit_proposal(RuntimeOrigin::signed(ALICE), 8, SNAPSHOT, 50, vec![m(1, 1, 50)]));
+        assert_ok!(PoBA::submit_proposal(RuntimeOrigin::signed(ALICE), 12, SNAPSHOT, 50, vec![m(1, 1, 50)]));
+
+        for slot in [7, 13, 0, 1_000] {
+            assert_noop!(
+                PoBA::submit_proposal(RuntimeOrigin::signed(ALICE), slot, SNAPSHOT, 50, vec![m(1, 1, 50)]),
+                Error::<Test>::SlotOutsideWindow
+            );
+        }
+    });
+}
+
+#[test]
+fn submission_inside_slot_window_is_accepted() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(20);
+        assert_ok!(PoBA::submit_proposal(RuntimeOrigin::signed(ALICE), 20, SNAPSHOT, 50, vec![m(1, 1, 50)]));
+        assert_ok!(PoBA::finalize_slot(RuntimeOrigin::signed(ALICE), 20));
+
+        // Both edges of the window are still accepted.
+        assert_ok!(PoBA::submit_proposal(RuntimeOrigin::signed(BOB), 20 - MAX_SLOT_LAG, SNAPSHOT, 50, vec![m(2, 2, 50)]));
+        assert_ok!(PoBA::submit_proposal(RuntimeOrigin::signed(BOB), 20 + MAX_SLOT_LAG, SNAPSHOT, 50, vec![m(3, 3, 50)]));
+    });
+}
+
+#[test]
+fn proposals_keep_one_entry_per_proposer() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(PoBA::submit_proposal(RuntimeOrigin::signed(ALICE), 1, SNAPSHOT, 50, vec![m(1, 1, 50)]));
+        assert_ok!(PoBA::submit_proposal(RuntimeOrigin::signed(BOB), 1, SNAPSHOT, 70, vec![m(1, 2, 70)]));
+        assert_eq!(BestProposer::<Test>::get(1), Some(BOB));
+        // Alice resubmits: her own entry is overwritten, not appended.
+        assert_ok!(PoBA::submit_proposal(RuntimeOrigin::signed(ALICE), 1, SNAPSHOT, 80, vec![m(1, 1, 80)]));
+
+        let entries = PoBA::proposals(1);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].0, ALICE);
+        assert_eq!(entries[0].1.total_score, 80);
+        assert_eq!(entries[1].0, BOB);
+
+        // Best follows the current entries: Alice now leads.
+        assert_eq!(BestProposer::<Test>::get(1), Some(ALICE));
+        assert_eq!(BestProposal::<Test>::get(1).unwrap().total_score, 80);
+    });
+}
+
+#[test]
+fn too_many_proposers_is_rejected() {
+    new_test_ext().execute_with(|| {
+        for who in [ALICE, BOB, CHARLIE] {
+            assert_ok!(PoBA::submit_proposal(RuntimeOrigin::signed(who), 1, SNAPSHOT, 10, vec![m(1, 1, 10)]));
+        }
+        assert_ok!(PoBA::add_proposer(RuntimeOrigin::root(), DAVE));
+        assert_noop!(
+            PoBA::submit_proposal(RuntimeOrigin::signed(DAVE), 1, SNAPSHOT, 99, vec![m(1, 1, 99)]),
+            Error::<Test>::TooManyProposers
+        );
+    });
+}
+
+#[test]
+fn finalize_picks_max_with_earliest_tie_break() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(PoBA::submit_proposal(RuntimeOrigin::signed(ALICE), 1, SNAPSHOT, 70, vec![m(1, 1, 70)]));
+        assert_ok!(PoBA::submit_proposal(RuntimeOrigin::signed(BOB), 1, SNAPSHOT, 70, vec![m(1, 2, 70)]));
+        assert_ok!(PoBA::submit_proposal(RuntimeOrigin::signed(CHARLIE), 1, SNAPSHOT, 60, vec![m(1, 3, 60)]));
+
+        assert_ok!(PoBA::finalize_slot(RuntimeOrigin::signed(ALICE), 1));
+
+        // Alice reached 70 first, so she wins the tie.
+        let finalized = FinalizedProposal::<Test>::get(1).unwrap();
+        assert_eq!(finalized.matches[0].offer_uuid, [1; 16]);
+        assert!(Proposals::<Test>::get(1).is_empty());
+        assert_eq!(BestProposal::<Test>::get(1), None);
+    });
+}
+
+#[test]
+fn rewards_accrue_from_the_pot_and_can_be_claimed() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(3);
+        let alice_before = Balances::free_balance(ALICE);
+        for slot in 1..=3 {
+            let rq = slot as u8;
+            assert_ok!(PoBA::submit_proposal(RuntimeOrigin::signed(ALICE), slot, SNAPSHOT, 50, vec![m(rq, 1, 50)]));
+            assert_ok!(PoBA::submit_proposal(RuntimeOrigin::signed(BOB), slot, SNAPSHOT, 40, vec![m(rq, 2, 40)]));
+            assert_ok!(PoBA::finalize_slot(RuntimeOrigin::signed(CHARLIE), slot));
+            System::assert_has_event(Event::RewardAccrued { slot, who: ALICE, amount: REWARD }.into());
+        }
+
+        assert_eq!(Rewards::<Test>::get(ALICE), 3 * REWARD);
+        assert_eq!(Rewards::<Test>::get(BOB), 0);
+        // Set aside in the pot until claimed.
+        assert_eq!(Balances::reserved_balance(POT), 3 * REWARD);
+        assert_eq!(Balances::free_balance(ALICE), alice_before);
+
+        assert_ok!(PoBA::claim_rewards(RuntimeOrigin::signed(ALICE)));
+        assert_eq!(Balances::free_balance(ALICE), alice_before + 3 * REWARD);
+        assert_eq!(Rewards::<Test>::get(ALICE), 0);
+        assert_eq!(Balances::total_balance(&POT), 1_000 - 3 * REWARD);
+        System::assert_last_event(Event::RewardClaimed { who: ALICE, amount: 3 * REWARD }.into());
+    });
+}
+
+#[test]
+fn claim_without_rewards_fails() {
+    new_test_ext().execute_with(|| {
+        assert_noop!(PoBA::claim_rewards(RuntimeOrigin::signed(BOB)), Error::<Test>::NoRewards);
+    });
+}
+
+#[test]
+fn proposer_stats_track_submissions_and_wins() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(3);
+        // Slot 1: Alice submits twice and wins.
+        assert_ok!(PoBA::submit_proposal(RuntimeOrigin::signed(ALICE), 1, SNAPSHOT, 50, vec![m(1, 1, 50)]));
+        assert_ok!(PoBA::submit_proposal(RuntimeOrigin::signed(BOB), 1, SNAPSHOT, 40, vec![m(1, 2, 40)]));
+        assert_ok!(PoBA::submit_proposal(RuntimeOrigin::signed(ALICE), 1, SNAPSHOT, 60, vec![m(1, 1, 60)]));
+        assert_ok!(PoBA::finalize_slot(RuntimeOrigin::signed(CHARLIE), 1));
+        // Slots 2 and 3: Bob wins.
+        for slot in 2..=3 {
+            let rq = slot as u8;
+            assert_ok!(PoBA::submit_proposal(RuntimeOrigin::signed(ALICE), slot, SNAPSHOT, 30, vec![m(rq, 1, 30)]));
+            assert_ok!(PoBA::submit_proposal(RuntimeOrigin::signed(BOB), slot, SNAPSHOT, 70, vec![m(rq, 2, 70)]));
+            assert_ok!(PoBA::finalize_slot(RuntimeOrigin::signed(CHARLIE), slot));
+        }
+
+        let alice = ProposerStats { submitted: 4, wins: 1, total_winning_score: 60 };
+        let bob = ProposerStats { submitted: 3, wins: 2, total_winning_score: 140 };
+        assert_eq!(PoBA::proposer_stats(ALICE), alice);
+        assert_eq!(PoBA::proposer_stats(BOB), bob);
+        assert_eq!(PoBA::proposer_stats(CHARLIE), ProposerStats::default());
+
+        assert_eq!(PoBA::top_proposers(10), vec![(BOB, bob.clone()), (ALICE, alice)]);
+        assert_eq!(PoBA::top_proposers(1), vec![(BOB, bob)]);
+    });
+}
+
+#[test]
+fn empty_pot_skips_reward_without_failing_finalization() {
+    new_test_ext().execute_with(|| {
+        RewardPot::set(EMPTY_POT);
+        let alice_before = Balances::free_balance(ALICE);
+
+        assert_ok!(PoBA::submit_proposal(RuntimeOrigin::signed(ALICE), 1, SNAPSHOT, 50, vec![m(1, 1, 50)]));
+        assert_ok!(PoBA::finalize_slot(RuntimeOrigin::signed(CHARLIE), 1));
+
+        System::assert_has_event(Event::RewardSkipped { slot: 1, who: ALICE, amount: REWARD }.into());
+        assert!(FinalizedProposal::<Test>::get(1).is_some());
+        assert_eq!(Balances::free_balance(ALICE), alice_before);
+        assert_eq!(Rewards::<Test>::get(ALICE), 0);
+    });
+}
+
+/// Externalities whose PoBA genesis seeds `finalized` (and nothing else).
+fn ext_with_history(
+    finalized: Vec<(u64, i64, Vec<([u8; 16], [u8; 16], u32, i64)>)>,
+) -> sp_io::TestExternalities {
+    let mut t = frame_system::GenesisConfig::<Test>::default().build_storage().unwrap();
+    crate::GenesisConfig::<Test> { finalized, ..Default::default() }
+        .assign_storage(&mut t)
+        .unwrap();
+    t.into()
+}
+
+#[test]
+fn genesis_seeds_finalized_history() {
+    ext_with_history(vec![(4, 50, vec![m(1, 1, 50)]), (9, 70, vec![m(2, 1, 30), m(3, 2, 40)])])
+        .execute_with(|| {
+            assert_eq!(PoBA::last_finalized_slot(), 9);
+            assert_eq!(FinalizedProposal::<Test>::get(4).unwrap().total_score, 50);
+            assert_eq!(FinalizedProposal::<Test>::get(9).unwrap().matches.len(), 2);
+            assert_eq!(FinalizedDigest::<Test>::get(9).unwrap().matches_count, 2);
+            assert_eq!(MatchedRequests::<Test>::get([3; 16]), Some(9));
+            assert!(PoBA::is_finalized(4));
+        });
+}
+
+#[test]
+#[should_panic(expected = "unique and increasing")]
+fn genesis_history_slots_must_increase() {
+    ext_with_history(vec![(9, 50, vec![m(1, 1, 50)]), (9, 60, vec![m(2, 1, 60)])]);
+}
+
+#[test]
+#[should_panic(expected = "sum of partial scores")]
+fn genesis_history_scores_must_add_up() {
+    ext_with_history(vec![(4, 99, vec![m(1, 1, 50)])]);
+}
+
+#[test]
+fn genesis_proposers_are_authorized() {
+    new_test_ext().execute_with(|| {
+        for who in [ALICE, BOB, CHARLIE] {
+            assert_eq!(PoBA::is_authorized_proposer(who), Some(()));
+        }
+        assert_eq!(PoBA::is_authorized_proposer(DAVE), None);
+    });
+}
+
+#[test]
+fn unauthorized_proposer_is_rejected() {
+    new_test_ext().execute_with(|| {
+        assert_noop!(
+            PoBA::submit_proposal(RuntimeOrigin::signed(DAVE), 1, SNAPSHOT, 50, vec![m(1, 1, 50)]),
+            Error::<Test>::NotAuthorizedProposer
+        );
+    });
+}
+
+#[test]
+fn admin_can_add_and_remove_proposers() {
+    new_test_ext().execute_with(|| {
+        assert_noop!(
+            PoBA::add_proposer(RuntimeOrigin::signed(ALICE), DAVE),
+            DispatchError::BadOrigin
+        );
+
+        assert_ok!(PoBA::add_proposer(RuntimeOrigin::root(), DAVE));
+        System::assert_last_event(Event::ProposerAdded { who: DAVE }.into());
+        assert_noop!(
+            PoBA::add_proposer(RuntimeOrigin::root(), DAVE),
+            Error::<Test>::AlreadyAuthorizedProposer
+        );
+        assert_ok!(PoBA::submit_proposal(RuntimeOrigin::signed(DAVE), 1, SNAPSHOT, 50, vec![m(1, 1, 50)]));
+
+        assert_ok!(PoBA::remove_proposer(RuntimeOrigin::root(), DAVE));
+        System::assert_last_event(Event::ProposerRemoved { who: DAVE }.into());
+        assert_noop!(
+            PoBA::submit_proposal(RuntimeOrigin::signed(DAVE), 1, SNAPSHOT, 60, vec![m(1, 1, 60)]),
+            Error::<Test>::NotAuthorizedProposer
+        );
+        assert_noop!(
+            PoBA::remove_proposer(RuntimeOrigin::root(), DAVE),
+            Error::<Test>::NotAuthorizedProposer
+        );
+    });
+}
+
+#[test]
+fn clean_finalization_after_challenge_window() {
+    new_test_ext().execute_with(|| {
+        ChallengeWindow::set(3);
+        assert_ok!(PoBA::submit_proposal(RuntimeOrigin::signed(ALICE), 1, SNAPSHOT, 50, vec![m(1, 1, 50)]));
+
+        // Two-phase flow: finalize needs a proposed finalization first.
+        assert_noop!(
+            PoBA::finalize_slot(RuntimeOrigin::signed(CHARLIE), 1),
+            Error::<Test>::NoPendingFinalization
+        );
+        assert_ok!(PoBA::propose_finalization(RuntimeOrigin::signed(CHARLIE), 1));
+        System::assert_last_event(
+            Event::FinalizationProposed { slot: 1, proposer: ALICE, total_score: 50, challenge_until: 4 }
+                .into(),
+        );
+
+        System::set_block_number(3);
+        assert_noop!(
+            PoBA::finalize_slot(RuntimeOrigin::signed(CHARLIE), 1),
+            Error::<Test>::ChallengePeriodActive
+        );
+        // A valid proposal cannot be challenged.
+        assert_noop!(
+            PoBA::challenge_finalization(
+                RuntimeOrigin::signed(BOB),
+                1,
+                ChallengeEvidence::Violation(ChallengeReason::ScoreMismatch)
+            ),
+            Error::<Test>::ChallengeRejected
+        );
+
+        System::set_block_number(4);
+        assert_ok!(PoBA::finalize_slot(RuntimeOrigin::signed(CHARLIE), 1));
+        assert_eq!(FinalizedProposal::<Test>::get(1).unwrap().total_score, 50);
+        assert_eq!(PendingFinalizations::<Test>::get(1), None);
+    });
+}
+
+#[test]
+fn successful_challenge_cancels_pending_finalization() {
+    new_test_ext().execute_with(|| {
+        ChallengeWindow::set(3);
+        // Alice inflates her score by matching request 1 twice.
+        assert_ok!(PoBA::submit_proposal(
+            RuntimeOrigin::signed(ALICE),
+            1,
+            SNAPSHOT,
+            130,
+            vec![m(1, 1, 50), m(1, 3, 80)]
+        ));
+        assert_ok!(PoBA::submit_proposal(RuntimeOrigin::signed(BOB), 1, SNAPSHOT, 80, vec![m(1, 2, 80)]));
+        assert_ok!(PoBA::propose_finalization(RuntimeOrigin::signed(CHARLIE), 1));
+
+        assert_noop!(
+            PoBA::challenge_finalization(
+                RuntimeOrigin::signed(BOB),
+                1,
+                ChallengeEvidence::Violation(ChallengeReason::ScoreMismatch)
+            ),
+            Error::<Test>::ChallengeRejected
+        );
+        assert_ok!(PoBA::challenge_finalization(
+            RuntimeOrigin::signed(BOB),
+            1,
+            ChallengeEvidence::Violation(ChallengeReason::DuplicateRequest)
+        ));
+        System::assert_last_event(
+            Event::FinalizationChallenged {
+                slot: 1,
+                challenger: BOB,
+                proposer: ALICE,
+                reason: ChallengeReason::DuplicateRequest,
+            }
+            .into(),
+        );
+
+        // Alice's bond went to Bob.
+        System::assert_has_event(
+            Event::BondSlashed { slot: 1, who: ALICE, challenger: BOB, amount: BOND }.into(),
+        );
+        assert_eq!(Balances::reserved_balance(ALICE), 0);
+        assert_eq!(Balances::free_balance(ALICE), 1_000 - BOND);
+        assert_eq!(Balances::free_balance(BOB), 1_000);
+
+        // Alice's proposal is gone; Bob now leads and can be finalized.
+        assert_eq!(PendingFinalizations::<Test>::get(1), None);
+        assert_eq!(BestProposer::<Test>::get(1), Some(BOB));
+        assert_ok!(PoBA::propose_finalization(RuntimeOrigin::signed(CHARLIE), 1));
+        System::set_block_number(4);
+        assert_ok!(PoBA::finalize_slot(RuntimeOrigin::signed(CHARLIE), 1));
+        assert_eq!(FinalizedProposal::<Test>::get(1).unwrap().total_score, 80);
+    });
+}
+
+#[test]
+fn challenge_after_window_is_rejected() {
+    new_test_ext().execute_with(|| {
+        ChallengeWindow::set(2);
+        assert_ok!(PoBA::submit_proposal(
+            RuntimeOrigin::signed(ALICE),
+            1,
+            SNAPSHOT,
+            100,
+            vec![m(1, 1, 50), m(1, 2, 50)]
+        ));
+        assert_ok!(PoBA::propose_finalization(RuntimeOrigin::signed(CHARLIE), 1));
+
+        System::set_block_number(3);
+        assert_noop!(
+            PoBA::challenge_finalization(
+                RuntimeOrigin::signed(BOB),
+                1,
+                ChallengeEvidence::Violation(ChallengeReason::DuplicateRequest)
+            ),
+            Error::<Test>::ChallengePeriodOver
+        );
+    });
+}
+
+#[test]
+fn better_proposal_replaces_pending_winner() {
+    new_test_ext().execute_with(|| {
+        ChallengeWindow::set(3);
+        assert_ok!(PoBA::submit_proposal(RuntimeOrigin::signed(ALICE), 1, SNAPSHOT, 50, vec![m(1, 1, 50)]));
+        assert_ok!(PoBA::propose_finalization(RuntimeOrigin::signed(CHARLIE), 1));
+
+        // Bob never submitted but found a better matching during the window.
+        System::set_block_number(2);
+        assert_ok!(PoBA::challenge_finalization(
+            RuntimeOrigin::signed(BOB),
+            1,
+            ChallengeEvidence::BetterProposal {
+                snapshot_hash: SNAPSHOT,
+                total_score: 90,
+                matches: vec![m(1, 1, 40), m(2, 2, 50)],
+            }
+        ));
+        System::assert_last_event(
+            Event::FinalizationChallenged {
+                slot: 1,
+                challenger: BOB,
+                proposer: ALICE,
+                reason: ChallengeReason::Outscored,
+            }
+            .into(),
+        );
+
+        // Bob is now the pending winner, bonded, with a fresh window.
+        let pending = PendingFinalizations::<Test>::get(1).unwrap();
+        assert_eq!(pending.proposer, BOB);
+        assert_eq!(pending.proposal.total_score, 90);
+        assert_eq!(pending.challenge_until, 5);
+        assert_eq!(BestProposer::<Test>::get(1), Some(BOB));
+        assert_eq!(Balances::reserved_balance(BOB), BOND);
+        assert_eq!(Balances::free_balance(BOB), 1_000);
+        assert_eq!(Balances::free_balance(ALICE), 1_000 - BOND);
+
+        System::set_block_number(4);
+        assert_noop!(
+            PoBA::finalize_slot(RuntimeOrigin::signed(CHARLIE), 1),
+            Error::<Test>::ChallengePeriodActive
+        );
+        System::set_block_number(5);
+        assert_ok!(PoBA::finalize_slot(RuntimeOrigin::signed(CHARLIE), 1));
+        assert_eq!(FinalizedProposal::<Test>::get(1).unwrap().total_score, 90);
+        assert_eq!(Balances::reserved_balance(BOB), 0);
+    });
+}
+
+#[test]
+fn better_proposal_must_be_valid_and_strictly_higher() {
+    new_test_ext().execute_with(|| {
+        ChallengeWindow::set(3);
+        assert_ok!(PoBA::submit_proposal(RuntimeOrigin::signed(ALICE), 1, SNAPSHOT, 50, vec![m(1, 1, 50)]));
+        assert_ok!(PoBA::propose_finalization(RuntimeOrigin::signed(CHARLIE), 1));
+
+        let challenge = |who, total_score, matches| {
+            PoBA::challenge_finalization(
+                RuntimeOrigin::signed(who),
+                1,
+                ChallengeEvidence::BetterProposal { snapshot_hash: SNAPSHOT, total_score, matches },
+            )
+        };
+        // Equal score is not an improvement.
+        assert_noop!(challenge(BOB, 50, vec![m(1, 2, 50)]), Error::<Test>::ChallengeRejected);
+        // Claimed score does not match the partials.
+        assert_noop!(challenge(BOB, 90, vec![m(1, 2, 50)]), Error::<Test>::ScoreMismatch);
+        // Same request matched twice.
+        assert_noop!(
+            challenge(BOB, 90, vec![m(1, 1, 40), m(1, 2, 50)]),
+            Error::<Test>::ChallengeRejected
+        );
+        // Only authorized proposers can take over a slot.
+        assert_noop!(challenge(DAVE, 90, vec![m(1, 2, 90)]), Error::<Test>::NotAuthorizedProposer);
+        // Nor can the pending winner challenge itself.
+        assert_noop!(challenge(ALICE, 90, vec![m(1, 2, 90)]), Error::<Test>::ChallengeRejected);
+        // A valid proposal is not out of band.
+        assert_noop!(
+            PoBA::challenge_finalization(
+                RuntimeOrigin::signed(BOB),
+                1,
+                ChallengeEvidence::Violation(ChallengeReason::PriceOutOfBand)
+            ),
+            Error::<Test>::ChallengeRejected
+        );
+
+        assert_eq!(PendingFinalizations::<Test>::get(1).unwrap().proposer, ALICE);
+        assert_eq!(Balances::reserved_balance(ALICE), BOND);
+    });
+}
+
+#[test]
+fn better_proposal_passes_the_submission_checks() {
+    new_test_ext().execute_with(|| {
+        ChallengeWindow::set(3);
+        let better = |snapshot_hash| ChallengeEvidence::BetterProposal {
+            snapshot_hash,
+            total_score: 90,
+            matches: vec![m(1, 2, 90)],
+        };
+        assert_ok!(PoBA::submit_proposal(RuntimeOrigin::signed(ALICE), 1, SNAPSHOT, 50, vec![m(1, 1, 50)]));
+        // Bob used up his submissions on slot 1 with weaker proposals.
+        for score in [10, 20, 30] {
+            assert_ok!(PoBA::submit_proposal(RuntimeOrigin::signed(BOB), 1, SNAPSHOT, score, vec![m(2, 2, score)]));
+        }
+        assert_ok!(PoBA::propose_finalization(RuntimeOrigin::signed(CHARLIE), 1));
+
+        assert_noop!(
+            PoBA::challenge_finalization(RuntimeOrigin::signed(CHARLIE), 1, better(H256::zero())),
+            Error::<Test>::SnapshotMismatch
+        );
+        assert_noop!(
+            PoBA::challenge_finalization(RuntimeOrigin::signed(BOB), 1, better(SNAPSHOT)),
+            Error::<Test>::TooManySubmissions
+        );
+        assert_ok!(PoBA::challenge_finalization(RuntimeOrigin::signed(CHARLIE), 1, better(SNAPSHOT)));
+        assert_eq!(SubmissionCount::<Test>::get(1, CHARLIE), 1);
+    });
+}
+
+#[test]
+fn better_proposal_is_rejected_after_the_submission_window() {
+    new_test_ext().execute_with(|| {
+        ChallengeWindow::set(3);
+        SubmissionWindow::set(2);
+        assert_ok!(PoBA::submit_proposal(RuntimeOrigin::signed(ALICE), 1, SNAPSHOT, 50, vec![m(1, 1, 50)]));
+        System::set_block_number(3);
+        assert_ok!(PoBA::propose_finalization(RuntimeOrigin::signed(CHARLIE), 1));
+
+        assert_noop!(
+            PoBA::challenge_finalization(
+                RuntimeOrigin::signed(BOB),
+                1,
+                ChallengeEvidence::BetterProposal {
+                    snapshot_hash: SNAPSHOT,
+                    total_score: 90,
+                    matches: vec![m(1, 2, 90)],
+                }
+            ),
+            Error::<Test>::SubmissionWindowClosed
+        );
+    });
+}
+
+#[test]
+fn finalize_skips_slot_below_min_score() {
+    new_test_ext().execute_with(|| {
+        MinFinalizeScore::set(100);
+        // Below: the slot is skipped, not finalized, and the proposal stays.
+        assert_ok!(PoBA::submit_proposal(RuntimeOrigin::signed(ALICE), 1, SNAPSHOT, 99, vec![m(1, 1, 99)]));
+        assert_ok!(PoBA::finalize_slot(RuntimeOrigin::signed(CHARLIE), 1));
+        System::assert_last_event(Event::SlotBelowThreshold { slot: 1, best_score: 99 }.into());
+        assert_eq!(FinalizedProposal::<Test>::get(1), None);
+        assert_eq!(BestProposer::<Test>::get(1), Some(ALICE));
+        assert_eq!(PoBA::last_finalized_slot(), 0);
+
+        // At: improved to exactly the threshold, the same slot finalizes.
+        assert_ok!(PoBA::submit_proposal(RuntimeOrigin::signed(ALICE), 1, SNAPSHOT, 100, vec![m(1, 1, 100)]));
+        assert_ok!(PoBA::finalize_slot(RuntimeOrigin::signed(CHARLIE), 1));
+        assert_eq!(FinalizedProposal::<Test>::get(1).unwrap().total_score, 100);
+
+        // Above.
+        assert_ok!(PoBA::submit_proposal(RuntimeOrigin::signed(BOB), 2, SNAPSHOT, 150, vec![m(2, 2, 150)]));
+        assert_ok!(PoBA::finalize_slot(RuntimeOrigin::signed(CHARLIE), 2));
+        assert_eq!(FinalizedProposal::<Test>::get(2).unwrap().total_score, 150);
+    });
+}
+
+#[test]
+fn finalize_skips_slot_below_min_matches() {
+    new_test_ext().execute_with(|| {
+        MinFinalizeMatches::set(2);
+        assert_ok!(PoBA::submit_proposal(RuntimeOrigin::signed(ALICE), 1, SNAPSHOT, 90, vec![m(1, 1, 90)]));
+        assert_ok!(PoBA::finalize_slot(RuntimeOrigin::signed(CHARLIE), 1));
+        System::assert_last_event(Event::SlotBelowThreshold { slot: 1, best_score: 90 }.into());
+        assert_eq!(FinalizedProposal::<Test>::get(1), None);
+
+        assert_ok!(PoBA::submit_proposal(
+            RuntimeOrigin::signed(ALICE),
+            1,
+            SNAPSHOT,
+            100,
+            vec![m(1, 1, 50), m(2, 1, 50)]
+        ));
+        assert_ok!(PoBA::finalize_slot(RuntimeOrigin::signed(CHARLIE), 1));
+        assert_eq!(FinalizedProposal::<Test>::get(1).unwrap().matches.len(), 2);
+    });
+}
+
+#[test]
+fn below_threshold_keeps_the_pending_finalization() {
+    new_test_ext().execute_with(|| {
+        ChallengeWindow::set(2);
+        MinFinalizeScore::set(100);
+        assert_ok!(PoBA::submit_proposal(RuntimeOrigin::signed(ALICE), 1, SNAPSHOT, 60, vec![m(1, 1, 60)]));
+        assert_ok!(PoBA::propose_finalization(RuntimeOrigin::signed(CHARLIE), 1));
+        System::set_block_number(3);
+        assert_ok!(PoBA::finalize_slot(RuntimeOrigin::signed(CHARLIE), 1));
+        System::assert_last_event(Event::SlotBelowThreshold { slot: 1, best_score: 60 }.into());
+        let pending = PendingFinalizations::<Test>::get(1).unwrap();
+        assert_eq!((pending.proposer, pending.challenge_until), (ALICE, 5));
+        assert_noop!(
+            PoBA::propose_finalization(RuntimeOrigin::signed(CHARLIE), 1),
+            Error::<Test>::FinalizationAlreadyPending
+        );
+
+        // The reopened window lets a better proposal take over.
+        assert_ok!(PoBA::challenge_finalization(
+            RuntimeOrigin::signed(BOB),
+            1,
+            ChallengeEvidence::BetterProposal {
+                snapshot_hash: SNAPSHOT,
+                total_score: 120,
+                matches: vec![m(1, 2, 120)],
+            }
+        ));
+        System::set_block_number(5);
+        assert_ok!(PoBA::finalize_slot(RuntimeOrigin::signed(CHARLIE), 1));
+        assert_eq!(FinalizedProposal::<Test>::get(1).unwrap().total_score, 120);
+        assert_eq!(PendingFinalizations::<Test>::get(1), None);
+    });
+}
+
+#[test]
+fn finalize_requires_finalize_origin() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(PoBA::submit_proposal(RuntimeOrigin::signed(ALICE), 1, SNAPSHOT, 50, vec![m(1, 1, 50)]));
+
+        // DAVE is not an authorized proposer and cannot finalize.
+        assert_noop!(PoBA::finalize_slot(RuntimeOrigin::signed(DAVE), 1), DispatchError::BadOrigin);
+        assert_noop!(PoBA::finalize_slot(RuntimeOrigin::none(), 1), DispatchError::BadOrigin);
+
+        assert_ok!(PoBA::finalize_slot(RuntimeOrigin::root(), 1));
+        assert!(FinalizedProposal::<Test>::get(1).is_some());
+    });
+}
+
+#[test]
+fn finalize_waits_for_min_proposal_age() {
+    new_test_ext().execute_with(|| {
+        MinProposalAge::set(1);
+        assert_ok!(PoBA::submit_proposal(RuntimeOrigin::signed(ALICE), 1, SNAPSHOT, 50, vec![m(1, 1, 50)]));
+
+        // Same block the best proposal landed in.
+        assert_noop!(
+            PoBA::finalize_slot(RuntimeOrigin::signed(BOB), 1),
+            Error::<Test>::ProposalTooRecent
+        );
+
+        // A new leader in block 2 restarts the clock.
+        System::set_block_number(2);
+        assert_ok!(PoBA::submit_proposal(RuntimeOrigin::signed(BOB), 1, SNAPSHOT, 80, vec![m(1, 2, 80)]));
+        assert_noop!(
+            PoBA::finalize_slot(RuntimeOrigin::signed(BOB), 1),
+            Error::<Test>::ProposalTooRecent
+        );
+
+        System::set_block_number(3);
+        assert_ok!(PoBA::finalize_slot(RuntimeOrigin::signed(BOB), 1));
+        assert_eq!(FinalizedProposal::<Test>::get(1).unwrap().total_score, 80);
+    });
+}
+
+#[test]
+fn old_finalized_proposals_are_pruned() {
+    new_test_ext().execute_with(|| {
+        for slot in 1..=10u64 {
+            System::set_block_number(slot);
+            assert_ok!(PoBA::submit_proposal(RuntimeOrigin::signed(ALICE), slot, SNAPSHOT, 50, vec![m(slot as u8, 1, 50)]));
+            if slot == 2 {
+                assert_ok!(PoBA::submit_proposal(RuntimeOrigin::signed(BOB), 2, SNAPSHOT, 10, vec![m(2, 2, 10)]));
+            }
+            assert_ok!(PoBA::finalize_slot(RuntimeOrigin::signed(ALICE), slot));
+        }
+
+        PoBA::on_initialize(System::block_number());
+        System::assert_last_event(Event::FinalizedPruned { removed: 6 }.into());
+
+        for slot in 1..=6u64 {
+            assert!(FinalizedProposal::<Test>::get(slot).is_none());
+            assert!(PoBA::is_finalized(slot));
+        }
+        for slot in 7..=10u64 {
+            assert!(FinalizedProposal::<Test>::get(slot).is_some());
+        }
+        assert_eq!(PoBA::last_finalized_slot(), 10);
+
+        // Pruned slots still count as finalized for bond reclaim.
+        assert_ok!(PoBA::reclaim_bond(RuntimeOrigin::signed(BOB), 2));
+
+        // Nothing left to prune until another slot is finalized.
+        let events = System::events().len();
+        PoBA::on_initialize(System::block_number());
+        assert_eq!(System::events().len(), events);
+    });
+}
+
+#[test]
+fn prune_finalized_respects_retention_boundary() {
+    new_test_ext().execute_with(|| {
+        for slot in 1..=6u64 {
+            System::set_block_number(slot);
+            assert_ok!(PoBA::submit_proposal(RuntimeOrigin::signed(ALICE), slot, SNAPSHOT, 50, vec![m(slot as u8, 1, 50)]));
+            assert_ok!(PoBA::finalize_slot(RuntimeOrigin::signed(ALICE), slot));
+        }
+
+        // last = 6, retention = 3: slots below 3 may go, 3..=6 stay.
+        assert_noop!(
+            PoBA::prune_finalized(RuntimeOrigin::signed(DAVE), 3),
+            Error::<Test>::SlotWithinRetention
+        );
+        assert_ok!(PoBA::prune_finalized(RuntimeOrigin::signed(DAVE), 2));
+        System::assert_last_event(Event::ProposalPruned { slot: 2 }.into());
+        assert_noop!(
+            PoBA::prune_finalized(RuntimeOrigin::signed(DAVE), 2),
+            Error::<Test>::NothingToPrune
+        );
+
+        assert!(FinalizedProposal::<Test>::get(2).is_none());
+        assert!(FinalizedProposal::<Test>::get(3).is_some());
+        assert!(PoBA::is_finalized(2));
+
+        // The digest still pins down the pruned matches.
+        let digest = FinalizedDigest::<Test>::get(2).unwrap();
+        let matches: crate::MatchesOf<Test> = vec![crate::Match {
+            request_uuid: [2; 16],
+            offer_uuid: [1; 16],
+            agreed_price_cents: 1_000,
+            partial_score: 50,
+        }]
+        .try_into()
+        .unwrap();
+        assert_eq!(digest.total_score, 50);
+        assert_eq!(digest.matches_count, 1);
+        assert_eq!(digest.matches_hash, <Test as frame_system::Config>::Hashing::hash_of(&matches));
+    });
+}
+
+#[test]
+fn empty_slot_is_skipped_when_allowed() {
+    new_test_ext().execute_with(|| {
+        AllowEmptySlots::set(true);
+        System::set_block_number(3);
+
+        assert_ok!(PoBA::finalize_slot(RuntimeOrigin::signed(ALICE), 2));
+        System::assert_last_event(Event::SlotSkipped { slot: 2 }.into());
+        assert_eq!(PoBA::last_finalized_slot(), 2);
+        assert!(FinalizedProposal::<Test>::get(2).is_none());
+        assert_eq!(Balances::free_balance(POT), 1_000);
+    });
+}
+
+#[test]
+fn empty_slot_errors_when_not_allowed() {
+    new_test_ext().execute_with(|| {
+        assert_noop!(
+            PoBA::finalize_slot(RuntimeOrigin::signed(ALICE), 1),
+            Error::<Test>::NoProposalForSlot
+        );
+        assert_eq!(PoBA::last_finalized_slot(), 0);
+    });
+}
+
+#[test]
+fn mixed_skip_and_finalize_keeps_cursor_monotonic() {
+    new_test_ext().execute_with(|| {
+        AllowEmptySlots::set(true);
+        System::set_block_number(4);
+
+        assert_ok!(PoBA::finalize_slot(RuntimeOrigin::signed(ALICE), 1));
+        assert_ok!(PoBA::submit_proposal(RuntimeOrigin::signed(BOB), 2, SNAPSHOT, 30, vec![m(1, 1, 30)]));
+        assert_ok!(PoBA::finalize_slot(RuntimeOrigin::signed(ALICE), 2));
+        System::assert_has_event(
+            Event::SlotFinalized {
+                slot: 2,
+                total_score: 30,
+                matches: 1,
+                snapshot_hash: SNAPSHOT,
+                winner: BOB,
+            }
+            .into(),
+        );
+        assert_ok!(PoBA::finalize_slot(RuntimeOrigin::signed(ALICE), 4));
+        assert_eq!(PoBA::last_finalized_slot(), 4);
+
+        // Skipping an older empty slot does not move the cursor back.
+        assert_ok!(PoBA::finalize_slot(RuntimeOrigin::signed(ALICE), 3));
+        assert_eq!(PoBA::last_finalized_slot(), 4);
+
+        // Already finalized slots cannot be skipped again.
+        assert_noop!(
+            PoBA::finalize_slot(RuntimeOrigin::signed(ALICE), 2),
+            Error::<Test>::SlotAlreadyFinalized
+        );
+
+        // Nor does finalizing an older slot that has a winner.
+        System::set_block_number(8);
+        assert_ok!(PoBA::submit_proposal(RuntimeOrigin::signed(BOB), 6, SNAPSHOT, 30, vec![m(2, 1, 30)]));
+        assert_ok!(PoBA::finalize_slot(RuntimeOrigin::signed(ALICE), 6));
+        assert_ok!(PoBA::submit_proposal(RuntimeOrigin::signed(BOB), 5, SNAPSHOT, 30, vec![m(3, 1, 30)]));
+        assert_ok!(PoBA::finalize_slot(RuntimeOrigin::signed(ALICE), 5));
+        assert!(PoBA::is_finalized(5));
+        assert_eq!(PoBA::last_finalized_slot(), 6);
+    });
+}
+
+#[test]
+fn finalizing_a_slot_creates_escrows_for_its_matches() {
+    new_test_ext().execute_with(|| {
+        // Request 7 already has an escrow from an earlier flow.
+        assert_ok!(Escrow::create_escrow(RuntimeOrigin::signed(ALICE), [7; 16], [2; 16], 2, 107, 1));
+
+        assert_ok!(PoBA::submit_proposal(
+            RuntimeOrigin::signed(ALICE),
+            1,
+            SNAPSHOT,
+            90,
+            vec![m(5, 1, 30), m(7, 3, 30), m(0, 4, 30)]
+        ));
+        assert_ok!(PoBA::finalize_slot(RuntimeOrigin::signed(BOB), 1));
+
+        let id = Escrow::request_to_escrow([5; 16]).unwrap();
+        let escrow = Escrow::escrows(id).unwrap();
+        assert_eq!((escrow.driver, escrow.payer, escrow.amount), (1, PAYER_BASE + 5, 1_000));
+
+        System::assert_has_event(
+            pallet_escrow::Event::MatchEscrowSkipped {
+                slot: 1,
+                request_uuid: [7; 16],
+                offer_uuid: [3; 16],
+                reason: pallet_escrow::MatchSkipReason::RequestAlreadyAssigned,
+            }
+            .into(),
+        );
+        System::assert_has_event(
+            pallet_escrow::Event::MatchEscrowSkipped {
+                slot: 1,
+                request_uuid: [0; 16],
+                offer_uuid: [4; 16],
+                reason: pallet_escrow::MatchSkipReason::UnknownParties,
+            }
+            .into(),
+        );
+        assert_eq!(Escrow::request_to_escrow([0; 16]), None);
+        assert_eq!(FinalizedProposal::<Test>::get(1).unwrap().matches.len(), 3);
+    });
+}
+
+#[test]
+fn request_matched_in_finalized_slot_is_rejected() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(2);
+        assert_ok!(PoBA::submit_proposal(RuntimeOrigin::signed(ALICE), 1, SNAPSHOT, 50, vec![m(1, 1, 50)]));
+        assert_ok!(PoBA::finalize_slot(RuntimeOrigin::signed(ALICE), 1));
+        assert_eq!(MatchedRequests::<Test>::get([1; 16]), Some(1));
+
+        assert_noop!(
+            PoBA::submit_proposal(RuntimeOrigin::signed(BOB), 2, SNAPSHOT, 90, vec![m(2, 2, 40), m(1, 2, 50)]),
+            Error::<Test>::RequestAlreadyMatched
+        );
+        assert_ok!(PoBA::submit_proposal(RuntimeOrigin::signed(BOB), 2, SNAPSHOT, 40, vec![m(2, 2, 40)]));
+    });
+}
+
+#[test]
+fn request_matched_in_finalized_slot_is_filtered() {
+    new_test_ext().execute_with(|| {
+        FilterMatchedRequests::set(true);
+        System::set_block_number(2);
+        assert_ok!(PoBA::submit_proposal(RuntimeOrigin::signed(ALICE), 1, SNAPSHOT, 50, vec![m(1, 1, 50)]));
+        assert_ok!(PoBA::finalize_slot(RuntimeOrigin::signed(ALICE), 1));
+
+        // The stale match is dropped and the total follows the kept ones.
+        assert_ok!(PoBA::submit_proposal(RuntimeOrigin::signed(BOB), 2, SNAPSHOT, 90, vec![m(2, 2, 40), m(1, 2, 50)]));
+        let (_, proposal) = &Proposals::<Test>::get(2)[0];
+        assert_eq!(proposal.total_score, 40);
+        assert_eq!(proposal.matches.len(), 1);
+        assert_eq!(proposal.matches[0].request_uuid, [2; 16]);
+
+        // Nothing left after filtering.
+        assert_noop!(
+            PoBA::submit_proposal(RuntimeOrigin::signed(CHARLIE), 2, SNAPSHOT, 50, vec![m(1, 3, 50)]),
+            Error::<Test>::EmptyMatches
+        );
+    });
+}
+
+#[test]
+fn unmatched_request_returns_to_market() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(2);
+        assert_ok!(PoBA::submit_proposal(RuntimeOrigin::signed(ALICE), 1, SNAPSHOT, 50, vec![m(1, 1, 50)]));
+        assert_ok!(PoBA::finalize_slot(RuntimeOrigin::signed(ALICE), 1));
+
+        assert_noop!(
+            PoBA::unmatch_request(RuntimeOrigin::signed(ALICE), [1; 16]),
+            DispatchError::BadOrigin
+        );
+        assert_noop!(
+            PoBA::unmatch_request(RuntimeOrigin::root(), [9; 16]),
+            Error::<Test>::RequestNotMatched
+        );
+        assert_ok!(PoBA::unmatch_request(RuntimeOrigin::root(), [1; 16]));
+        System::assert_last_event(Event::RequestUnmatched { request_uuid: [1; 16], slot: 1 }.into());
+
+        assert_ok!(PoBA::submit_proposal(RuntimeOrigin::signed(BOB), 2, SNAPSHOT, 50, vec![m(1, 2, 50)]));
+    });
+}
+
+#[test]
+fn submissions_per_slot_are_capped() {
+    new_test_ext().execute_with(|| {
+        for score in [10, 20, 30] {
+            assert_ok!(PoBA::submit_proposal(RuntimeOrigin::signed(ALICE), 1, SNAPSHOT, score, vec![m(1, 1, score)]));
+        }
+        assert_eq!(SubmissionCount::<Test>::get(1, ALICE), MAX_SUBMISSIONS);
+
+        assert_noop!(
+            PoBA::submit_proposal(RuntimeOrigin::signed(ALICE), 1, SNAPSHOT, 90, vec![m(1, 1, 90)]),
+            Error::<Test>::TooManySubmissions
+        );
+        // The cap is per proposer and per slot.
+        assert_ok!(PoBA::submit_proposal(RuntimeOrigin::signed(BOB), 1, SNAPSHOT, 90, vec![m(1, 2, 90)]));
+        assert_ok!(PoBA::submit_proposal(RuntimeOrigin::signed(ALICE), 2, SNAPSHOT, 90, vec![m(2, 1, 90)]));
+    });
+}
+
+#[test]
+fn resubmission_must_improve_by_min_delta() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(PoBA::submit_proposal(RuntimeOrigin::signed(ALICE), 1, SNAPSHOT, 50, vec![m(1, 1, 50)]));
+
+        assert_noop!(
+            PoBA::submit_proposal(RuntimeOrigin::signed(ALICE), 1, SNAPSHOT, 50 + MIN_IMPROVEMENT - 1, vec![m(1, 1, 54)]),
+            Error::<Test>::InsufficientImprovement
+        );
+        assert_noop!(
+            PoBA::submit_proposal(RuntimeOrigin::signed(ALICE), 1, SNAPSHOT, 40, vec![m(1, 1, 40)]),
+            Error::<Test>::InsufficientImprovement
+        );
+        assert_ok!(PoBA::submit_proposal(
+            RuntimeOrigin::signed(ALICE),
+            1,
+            SNAPSHOT,
+            50 + MIN_IMPROVEMENT,
+            vec![m(1, 1, 55)]
+        ));
+        assert_eq!(PoBA::proposals(1)[0].1.total_score, 55);
+        assert_eq!(SubmissionCount::<Test>::get(1, ALICE), 2);
+    });
+}
+
+#[test]
+fn identical_resubmission_is_a_no_op() {
+    new_test_ext().execute_with(|| {
+        let matches = vec![m(1, 1, 30), m(2, 1, 20)];
+        assert_ok!(PoBA::submit_proposal(RuntimeOrigin::signed(ALICE), 1, SNAPSHOT, 50, matches.clone()));
+        let events = System::events().len();
+
+        // A retry of the same proposal, matches in any order: no event, not counted.
+        let reordered = vec![matches[1], matches[0]];
+        assert_ok!(PoBA::submit_proposal(RuntimeOrigin::signed(ALICE), 1, SNAPSHOT, 50, reordered));
+        assert_eq!(System::events().len(), events);
+        assert_eq!(SubmissionCount::<Test>::get(1, ALICE), 1);
+        assert_eq!(
+            SubmittedHashes::<Test>::get(1, ALICE),
+            Some(PoBA::submission_key(1, &SNAPSHOT, 50, &matches))
+        );
+
+        // Same matches under another total is a new payload and gets checked.
+        assert_noop!(
+            PoBA::submit_proposal(RuntimeOrigin::signed(ALICE), 1, SNAPSHOT, 60, matches.clone()),
+            Error::<Test>::ScoreMismatch
+        );
+
+        // The same proposal from another proposer is its own submission.
+        assert_ok!(PoBA::submit_proposal(RuntimeOrigin::signed(BOB), 1, SNAPSHOT, 50, matches));
+        System::assert_last_event(
+            Event::ProposalSubmitted { slot: 1, total_score: 50, matches: 2, proposer: BOB, is_best: false }
+                .into(),
+        );
+
+        assert_ok!(PoBA::finalize_slot(RuntimeOrigin::signed(ALICE), 1));
+        assert_eq!(SubmittedHashes::<Test>::get(1, ALICE), None);
+    });
+}
+
+#[test]
+fn submission_counters_are_cleared_on_finalize_and_prune() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(2);
+        // Slot 1 is finalized, slot 2 never is.
+        assert_ok!(PoBA::submit_proposal(RuntimeOrigin::signed(ALICE), 1, SNAPSHOT, 50, vec![m(1, 1, 50)]));
+        assert_ok!(PoBA::submit_proposal(RuntimeOrigin::signed(BOB), 2, SNAPSHOT, 50, vec![m(2, 2, 50)]));
+        assert_ok!(PoBA::finalize_slot(RuntimeOrigin::signed(ALICE), 1));
+        assert_eq!(SubmissionCount::<Test>::get(1, ALICE), 0);
+        assert_eq!(SubmissionCount::<Test>::get(2, BOB), 1);
+
+        // Push slot 2 out of the retention window and let the sweep run.
+        System::set_block_number(10);
+        assert_ok!(PoBA::submit_proposal(RuntimeOrigin::signed(ALICE), 10, SNAPSHOT, 50, vec![m(3, 1, 50)]));
+        assert_ok!(PoBA::finalize_slot(RuntimeOrigin::signed(ALICE), 10));
+        PoBA::on_initialize(10);
+        assert_eq!(SubmissionCount::<Test>::get(2, BOB), 0);
+    });
+}
+
+#[test]
+fn partial_scores_must_be_in_range_and_sum_to_total() {
+    new_test_ext().execute_with(|| {
+        let submit = |total_score, matches| {
+            PoBA::submit_proposal(RuntimeOrigin::signed(ALICE), 1, SNAPSHOT, total_score, matches)
+        };
+        assert_noop!(submit(-1, vec![m(1, 1, -1)]), Error::<Test>::InvalidPartialScore);
+        let max = MaxPartialScore::get();
+        assert_noop!(submit(max + 1, vec![m(1, 1, max + 1)]), Error::<Test>::InvalidPartialScore);
+        assert_noop!(submit(60, vec![m(1, 1, 50)]), Error::<Test>::ScoreMismatch);
+        assert_noop!(
+            submit(i64::MAX, (1..=5).map(|r| m(r, 1, max)).collect()),
+            Error::<Test>::ScoreOverflow
+        );
+
+        // Four maximal partials fit, but no resubmission can improve on them.
+        let top = 4 * max;
+        assert_ok!(submit(top, (1..=4).map(|r| m(r, 1, max)).collect()));
+        assert_noop!(submit(top, (1..=4).map(|r| m(r, 1, max)).collect()), Error::<Test>::ScoreOverflow);
+    });
+}
+
+/// xorshift64, enough to vary the generated proposals deterministically.
+fn next_random(state: &mut u64) -> u64 {
+    *state ^= *state << 13;
+    *state ^= *state >> 7;
+    *state ^= *state << 17;
+    *state
+}
+
+#[test]
+fn random_scores_near_i64_limits_are_checked() {
+    let max = MaxPartialScore::get();
+    let mut seed = 0x5EED_u64;
+    for _ in 0..256 {
+        let len = 1 + next_random(&mut seed) % MAX_MATCHES as u64;
+        let matches: Vec<_> = (0..len)
+            .map(|i| {
+                let r = next_random(&mut seed);
+                // Mostly near the bound, sometimes just outside the range.
+                let part = match r % 8 {
+                    0 => -(((r >> 8) % 3) as i64),
+                    1 => max + ((r >> 8) % 3) as i64,
+                    2 => ((r >> 8) % 100) as i64,
+                    _ => max - ((r >> 8) % 1_000) as i64,
+                };
+                m(i as u8 + 1, 1, part)
+            })
+            .collect();
+        let claim_wrong = next_random(&mut seed) % 4 == 0;
+
+        let parts: Vec<i64> = matches.iter().map(|m| m.3).collect();
+        let sum = parts.iter().try_fold(0i64, |acc, p| acc.checked_add(*p));
+        let total = match sum {
+            Some(s) if claim_wrong => s.wrapping_add(1),
+            Some(s) => s,
+            None => i64::MAX,
+        };
+        let expected: Result<(), DispatchError> = if parts.iter().any(|p| *p < 0 || *p > max) {
+            Err(Error::<Test>::InvalidPartialScore.into())
+        } else if sum.is_none() {
+            Err(Error::<Test>::ScoreOverflow.into())
+        } else if claim_wrong {
+            Err(Error::<Test>::ScoreMismatch.into())
+        } else {
+            Ok(())
+        };
+
+        new_test_ext().execute_with(|| {
+            assert_eq!(
+                PoBA::submit_proposal(RuntimeOrigin::signed(ALICE), 1, SNAPSHOT, total, matches.clone()),
+                expected,
+                "matches {matches:?}, total {total}"
+            );
+        });
+    }
+}
+
+#[test]
+fn agreed_price_must_lie_within_bid_band() {
+    new_test_ext().execute_with(|| {
+        let banded = |price: u32| ([BANDED; 16], [BANDED; 16], price, 10);
+
+        assert_noop!(
+            PoBA::submit_proposal(RuntimeOrigin::signed(ALICE), 1, SNAPSHOT, 10, vec![banded(BAND_MIN - 1)]),
+            Error::<Test>::PriceOutOfBand
+        );
+        assert_noop!(
+            PoBA::submit_proposal(RuntimeOrigin::signed(ALICE), 1, SNAPSHOT, 10, vec![banded(BAND_MAX + 1)]),
+            Error::<Test>::PriceOutOfBand
+        );
+        // One bad match rejects the whole proposal.
+        assert_noop!(
+            PoBA::submit_proposal(
+                RuntimeOrigin::signed(ALICE),
+                1,
+                SNAPSHOT,
+                60,
+                vec![m(1, 1, 50), banded(BAND_MAX + 1)]
+            ),
+            Error::<Test>::PriceOutOfBand
+        );
+
+        assert_ok!(PoBA::submit_proposal(RuntimeOrigin::signed(ALICE), 1, SNAPSHOT, 10, vec![banded(BAND_MIN)]));
+        assert_ok!(PoBA::submit_proposal(RuntimeOrigin::signed(BOB), 1, SNAPSHOT, 10, vec![banded(BAND_MAX)]));
+    });
+}
+
+#[test]
+fn matched_requests_since_filters_by_slot() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(3);
+        for slot in 1..=3u64 {
+            assert_ok!(PoBA::submit_proposal(
+                RuntimeOrigin::signed(ALICE),
+                slot,
+                SNAPSHOT,
+                50,
+                vec![m(slot as u8, 1, 50)]
+            ));
+            assert_ok!(PoBA::finalize_slot(RuntimeOrigin::signed(ALICE), slot));
+        }
+
+        let mut since_two = PoBA::matched_requests_since(2);
+        since_two.sort();
+        assert_eq!(since_two, vec![[2u8; 16], [3u8; 16]]);
+        assert_eq!(PoBA::matched_requests_since(4), Vec::<[u8; 16]>::new());
+    });
+}
+
+#[test]
+fn proposals_are_bounded_by_max_matches() {
+    new_test_ext().execute_with(|| {
+        let too_many: Vec<_> = (1..=MAX_MATCHES as u8 + 1).map(|r| m(r, r, 1)).collect();
+        assert_noop!(
+            PoBA::submit_proposal(RuntimeOrigin::signed(ALICE), 1, SNAPSHOT, too_many.len() as i64, too_many),
+            Error::<Test>::TooManyMatches
+        );
+
+        let full: Vec<_> = (1..=MAX_MATCHES as u8).map(|r| m(r, r, 1)).collect();
+        assert_ok!(PoBA::submit_proposal(RuntimeOrigin::signed(ALICE), 1, SNAPSHOT, MAX_MATCHES as i64, full));
+        assert_eq!(PoBA::best_proposal(1).unwrap().matches.len(), MAX_MATCHES as usize);
+    });
+}
+
+fn commit(who: u64, slot: u64, score: i64, matches: &[([u8; 16], [u8; 16], u32, i64)], salt: [u8; 32]) {
+    let commitment = PoBA::commitment_of(score, matches, &salt);
+    assert_ok!(PoBA::commit_proposal(RuntimeOrigin::signed(who), slot, commitment));
+}
+
+#[test]
+fn commit_reveal_happy_path() {
+    new_test_ext().execute_with(|| {
+        CommitWindow::set(2);
+        RevealWindow::set(3);
+        System::set_block_number(1);
+        let matches = vec![m(1, 1, 50)];
+
+        assert_noop!(
+            PoBA::submit_proposal(RuntimeOrigin::signed(ALICE), 1, SNAPSHOT, 50, matches.clone()),
+            Error::<Test>::CommitRevealRequired
+        );
+
+        commit(ALICE, 1, 50, &matches, [7; 32]);
+        System::assert_last_event(
+            Event::ProposalCommitted { slot: 1, who: ALICE, reveal_from: 3, reveal_until: 6 }.into(),
+        );
+        assert_eq!(Bonds::<Test>::get(1, ALICE), Some(BOND));
+        // Nothing competes until revealed.
+        assert_eq!(BestProposal::<Test>::get(1), None);
+
+        assert_noop!(
+            PoBA::reveal_proposal(RuntimeOrigin::signed(ALICE), 1, SNAPSHOT, 50, matches.clone(), [7; 32]),
+            Error::<Test>::RevealWindowClosed
+        );
+
+        System::set_block_number(3);
+        assert_ok!(PoBA::reveal_proposal(RuntimeOrigin::signed(ALICE), 1, SNAPSHOT, 50, matches, [7; 32]));
+        assert_eq!(Commitments::<Test>::get(1, ALICE), None);
+        assert_eq!(BestProposer::<Test>::get(1), Some(ALICE));
+
+        assert_noop!(
+            PoBA::finalize_slot(RuntimeOrigin::signed(ALICE), 1),
+            Error::<Test>::RevealPeriodActive
+        );
+        System::set_block_number(6);
+        assert_ok!(PoBA::finalize_slot(RuntimeOrigin::signed(ALICE), 1));
+        assert!(FinalizedProposal::<Test>::get(1).is_some());
+        assert_eq!(Bonds::<Test>::get(1, ALICE), None);
+        assert_eq!(Balances::reserved_balance(ALICE), 0);
+    });
+}
+
+#[test]
+fn front_running_a_reveal_fails() {
+    new_test_ext().execute_with(|| {
+        CommitWindow::set(2);
+        RevealWindow::set(3);
+        System::set_block_number(1);
+        let matches = vec![m(1, 1, 50)];
+
+        commit(ALICE, 1, 50, &matches, [7; 32]);
+        // Bob commits early without knowing Alice's matches.
+        commit(BOB, 1, 10, &[m(1, 2, 10)], [9; 32]);
+
+        // Alice's reveal is now public; Bob copies it and bumps the score.
+        System::set_block_number(3);
+        assert_ok!(PoBA::reveal_proposal(RuntimeOrigin::signed(ALICE), 1, SNAPSHOT, 50, matches.clone(), [7; 32]));
+
+        let sniped = vec![([1; 16], [1; 16], 1_000, 51)];
+        assert_noop!(
+            PoBA::commit_proposal(
+                RuntimeOrigin::signed(BOB),
+                1,
+                PoBA::commitment_of(51, &sniped, &[9; 32])
+            ),
+            Error::<Test>::CommitWindowClosed
+        );
+        assert_noop!(
+            PoBA::reveal_proposal(RuntimeOrigin::signed(BOB), 1, SNAPSHOT, 51, sniped.clone(), [9; 32]),
+            Error::<Test>::RevealMismatch
+        );
+        assert_noop!(
+            PoBA::reveal_proposal(RuntimeOrigin::signed(CHARLIE), 1, SNAPSHOT, 51, sniped, [9; 32]),
+            Error::<Test>::NothingCommitted
+        );
+        assert_eq!(BestProposer::<Test>::get(1), Some(ALICE));
+
+        // Bob never revealed his own commitment: his bond is slashed, not returned.
+        let bob_free = Balances::free_balance(BOB);
+        System::set_block_number(6);
+        assert_ok!(PoBA::finalize_slot(RuntimeOrigin::signed(ALICE), 1));
+        System::assert_has_event(
+            Event::CommitmentForfeited { slot: 1, who: BOB, amount: BOND }.into(),
+        );
+        assert_eq!(Balances::reserved_balance(BOB), 0);
+        assert_eq!(Balances::free_balance(BOB), bob_free);
+        assert_eq!(Commitments::<Test>::get(1, BOB), None);
+    });
+}
+
+#[test]
+fn market_snapshot_is_set_once_by_admin() {
+    new_test_ext().execute_with(|| {
+        let slot = OPEN_SLOTS;
+        let hash = H256::repeat_byte(9);
+        assert_noop!(
+            PoBA::set_market_snapshot(RuntimeOrigin::signed(ALICE), slot, hash, 4, 2, vec![]),
+            DispatchError::BadOrigin
+        );
+        assert_ok!(PoBA::set_market_snapshot(RuntimeOrigin::root(), slot, hash, 4, 2, vec![]));
+        System::assert_last_event(
+            Event::MarketSnapshotSet { slot, snapshot_hash: hash, request_count: 4, offer_count: 2 }
+                .into(),
+        );
+        assert_eq!(
+            MarketSnapshot::<Test>::get(slot),
+            Some(MarketSnapshotInfo { snapshot_hash: hash, request_count: 4, offer_count: 2 })
+        );
+        assert_noop!(
+            PoBA::set_market_snapshot(RuntimeOrigin::root(), slot, SNAPSHOT, 4, 2, vec![]),
+            Error::<Test>::SnapshotAlreadySet
+        );
+    });
+}
+
+#[test]
+fn proposals_must_reference_the_slot_snapshot() {
+    new_test_ext().execute_with(|| {
+        // Wrong hash.
+        assert_noop!(
+            PoBA::submit_proposal(
+                RuntimeOrigin::signed(ALICE),
+                1,
+                H256::repeat_byte(8),
+                50,
+                vec![m(1, 1, 50)]
+            ),
+            Error::<Test>::SnapshotMismatch
+        );
+
+        // No snapshot published for the slot yet.
+        MarketSnapshot::<Test>::remove(2);
+        assert_noop!(
+            PoBA::submit_proposal(RuntimeOrigin::signed(ALICE), 2, SNAPSHOT, 50, vec![m(1, 1, 50)]),
+            Error::<Test>::SnapshotMismatch
+        );
+    });
+}
+
+#[test]
+fn finalize_requires_snapshot_and_reports_its_hash() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(PoBA::submit_proposal(RuntimeOrigin::signed(ALICE), 1, SNAPSHOT, 50, vec![m(1, 1, 50)]));
+
+        // Snapshot gone (e.g. never published): the slot cannot be finalized.
+        MarketSnapshot::<Test>::remove(1);
+        assert_noop!(
+            PoBA::finalize_slot(RuntimeOrigin::signed(CHARLIE), 1),
+            Error::<Test>::MissingSnapshot
+        );
+
+        let hash = H256::repeat_byte(3);
+        assert_ok!(PoBA::set_market_snapshot(RuntimeOrigin::root(), 1, hash, 1, 1, vec![]));
+        assert_ok!(PoBA::finalize_slot(RuntimeOrigin::signed(CHARLIE), 1));
+        System::assert_last_event(
+            Event::SlotFinalized {
+                slot: 1,
+                total_score: 50,
+                matches: 1,
+                snapshot_hash: hash,
+                winner: ALICE,
+            }
+            .into(),
+        );
+    });
+}
+
+#[test]
+fn finalize_records_unmatched_snapshot_requests() {
+    new_test_ext().execute_with(|| {
+        MarketSnapshot::<Test>::remove(1);
+        let requests = vec![[1; 16], [2; 16], [3; 16]];
+        assert_ok!(PoBA::set_market_snapshot(RuntimeOrigin::root(), 1, SNAPSHOT, 3, 2, requests.clone()));
+        assert_eq!(SnapshotRequests::<Test>::get(1).into_inner(), requests);
+
+        // The winner covers request 1 only.
+        assert_ok!(PoBA::submit_proposal(RuntimeOrigin::signed(ALICE), 1, SNAPSHOT, 50, vec![m(1, 1, 50)]));
+        assert_ok!(PoBA::finalize_slot(RuntimeOrigin::signed(CHARLIE), 1));
+
+        assert_eq!(PoBA::unmatched_requests(1).into_inner(), vec![[2; 16], [3; 16]]);
+        System::assert_has_event(Event::UnmatchedCount { slot: 1, count: 2 }.into());
+    });
+}
+
+#[test]
+fn unmatched_requests_are_bounded_and_pruned() {
+    new_test_ext().execute_with(|| {
+        MarketSnapshot::<Test>::remove(1);
+        let requests: Vec<[u8; 16]> = (1..=5).map(|b| [b; 16]).collect();
+        assert_ok!(PoBA::set_market_snapshot(RuntimeOrigin::root(), 1, SNAPSHOT, 5, 1, requests));
+        assert_ok!(PoBA::submit_proposal(RuntimeOrigin::signed(ALICE), 1, SNAPSHOT, 50, vec![m(2, 1, 50)]));
+        assert_ok!(PoBA::finalize_slot(RuntimeOrigin::signed(CHARLIE), 1));
+
+        // Four requests left over; only the first MAX_UNMATCHED are kept.
+        System::assert_has_event(Event::UnmatchedCount { slot: 1, count: 4 }.into());
+        assert_eq!(UnmatchedRequests::<Test>::get(1).len() as u32, MAX_UNMATCHED);
+        assert_eq!(UnmatchedRequests::<Test>::get(1).into_inner(), vec![[1; 16], [3; 16]]);
+
+        // A snapshot without a request list reports nothing unmatched.
+        assert_ok!(PoBA::submit_proposal(RuntimeOrigin::signed(ALICE), 2, SNAPSHOT, 50, vec![m(1, 1, 50)]));
+        assert_ok!(PoBA::finalize_slot(RuntimeOrigin::signed(CHARLIE), 2));
+        System::assert_has_event(Event::UnmatchedCount { slot: 2, count: 0 }.into());
+        assert!(UnmatchedRequests::<Test>::get(2).is_empty());
+
+        // Pruned along with the finalized proposal.
+        for slot in 3..=2 + RETAIN_FINALIZED {
+            let request = slot as u8 + 10;
+            assert_ok!(PoBA::submit_proposal(RuntimeOrigin::signed(ALICE), slot, SNAPSHOT, 50, vec![m(request, 1, 50)]));
+            assert_ok!(PoBA::finalize_slot(RuntimeOrigin::signed(CHARLIE), slot));
+        }
+        PoBA::on_initialize(System::block_number());
+        assert!(UnmatchedRequests::<Test>::get(1).is_empty());
+        assert!(SnapshotRequests::<Test>::get(1).is_empty());
+    });
+}
+
+/// `submit_proposal_unsigned` call for `authority_index`, signed by `key`.
+fn unsigned_call(
+    slot: u64,
+    matches: Vec<([u8; 16], [u8; 16], u32, i64)>,
+    authority_index: u32,
+    key: u64,
+) -> crate::Call<Test> {
+    let total_score = matches.iter().map(|m| m.3).sum();
+    let payload = PoBA::unsigned_payload(slot, &SNAPSHOT, total_score, &matches, authority_index);
+    crate::Call::submit_proposal_unsigned {
+        slot,
+        snapshot_hash: SNAPSHOT,
+        total_score,
+        matches,
+        authority_index,
+        _signature: UintAuthorityId(key).sign(&payload).unwrap(),
+    }
+}
+
+fn dispatch_unsigned(call: crate::Call<Test>) -> sp_runtime::DispatchResult {
+    let crate::Call::submit_proposal_unsigned {
+        slot,
+        snapshot_hash,
+        total_score,
+        matches,
+        authority_index,
+        _signature: signature,
+    } = call
+    else {
+        unreachable!()
+    };
+    PoBA::submit_proposal_unsigned(
+        RuntimeOrigin::none(),
+        slot,
+        snapshot_hash,
+        total_score,
+        matches,
+        authority_index,
+        signature,
+    )
+}
+
+#[test]
+fn unsigned_proposal_requires_authority_signature() {
+    new_test_ext().execute_with(|| {
+        // Index 0 is key 1 (ALICE); key 2 belongs to index 1.
+        let forged = unsigned_call(1, vec![m(1, 1, 50)], 0, 2);
+        assert_eq!(
+            PoBA::validate_unsigned(TransactionSource::External, &forged),
+            InvalidTransaction::BadProof.into()
+        );
+        let unknown = unsigned_call(1, vec![m(1, 1, 50)], 7, 1);
+        assert_eq!(
+            PoBA::validate_unsigned(TransactionSource::External, &unknown),
+            InvalidTransaction::BadSigner.into()
+        );
+
+        let call = unsigned_call(1, vec![m(1, 1, 50)], 0, 1);
+        let valid = PoBA::validate_unsigned(TransactionSource::External, &call).unwrap();
+        assert_eq!(valid.provides, vec![("poba", 1u64, 0u32).encode()]);
+        assert_eq!(valid.priority, 100);
+
+        assert_ok!(dispatch_unsigned(call));
+        assert_eq!(PoBA::best_proposer(1), Some(ALICE));
+        // The authority signature stands in for the bond.
+        assert_eq!(Bonds::<Test>::get(1, ALICE), None);
+        assert_eq!(Balances::reserved_balance(ALICE), 0);
+    });
+}
+
+#[test]
+fn unfunded_authority_can_submit_unsigned() {
+    new_test_ext().execute_with(|| {
+        // Key 3 signs for an account with no funds and no proposer entry.
+        let poor = 9;
+        assert_ok!(PoBA::set_authorities(
+            RuntimeOrigin::root(),
+            vec![(UintAuthorityId(1), ALICE), (UintAuthorityId(3), poor)]
+        ));
+        assert_eq!(Balances::free_balance(poor), 0);
+        assert_noop!(
+            PoBA::submit_proposal(RuntimeOrigin::signed(poor), 1, SNAPSHOT, 50, vec![m(1, 1, 50)]),
+            Error::<Test>::NotAuthorizedProposer
+        );
+
+        let call = unsigned_call(1, vec![m(1, 1, 50)], 1, 3);
+        assert_ok!(PoBA::validate_unsigned(TransactionSource::External, &call));
+        assert_ok!(dispatch_unsigned(call));
+        assert_eq!(PoBA::best_proposer(1), Some(poor));
+        assert_eq!(Bonds::<Test>::get(1, poor), None);
+    });
+}
+
+#[test]
+fn unsigned_proposal_is_rejected_on_replay() {
+    new_test_ext().execute_with(|| {
+        let call = unsigned_call(1, vec![m(1, 1, 50)], 1, 2);
+        assert_ok!(dispatch_unsigned(call.clone()));
+
+        // The same signed payload, or a new one, for the same slot is stale.
+        assert_eq!(
+            PoBA::validate_unsigned(TransactionSource::External, &call),
+            InvalidTransaction::Stale.into()
+        );
+        let better = unsigned_call(1, vec![m(1, 1, 80)], 1, 2);
+        assert_eq!(
+            PoBA::validate_unsigned(TransactionSource::External, &better),
+            InvalidTransaction::Stale.into()
+        );
+        assert_noop!(dispatch_unsigned(better), Error::<Test>::AlreadySubmittedUnsigned);
+
+        // Another slot is fine.
+        assert_ok!(PoBA::validate_unsigned(
+            TransactionSource::External,
+            &unsigned_call(2, vec![m(1, 1, 50)], 1, 2)
+        ));
+    });
+}
+
+#[test]
+fn sign_proposal_uses_local_authority_key() {
+    new_test_ext().execute_with(|| {
+        let matches = vec![m(1, 1, 50)];
+        UintAuthorityId::set_all_keys(vec![UintAuthorityId(9)]);
+        assert_eq!(PoBA::sign_proposal(1, &SNAPSHOT, 50, &matches), None);
+
+        UintAuthorityId::set_all_keys(vec![UintAuthorityId(2)]);
+        let (index, signature) = PoBA::sign_proposal(1, &SNAPSHOT, 50, &matches).unwrap();
+        assert_eq!(index, 1);
+        assert_eq!(signature, TestSignature(2, PoBA::unsigned_payload(1, &SNAPSHOT, 50, &matches, 1)));
+    });
+}
+
+#[test]
+fn submissions_close_before_finalization_opens() {
+    new_test_ext().execute_with(|| {
+        SubmissionWindow::set(3);
+        let submit = |score| {
+            PoBA::submit_proposal(RuntimeOrigin::signed(ALICE), 2, SNAPSHOT, score, vec![m(1, 1, score)])
+        };
+
+        // Slot 2 accepts proposals in blocks 2, 3 and 4 (and earlier).
+        assert_ok!(submit(50));
+        for block in 2..5 {
+            System::set_block_number(block);
+            assert_eq!(PoBA::slot_phase(2), Some(SlotPhase::Submission));
+            assert_noop!(
+                PoBA::finalize_slot(RuntimeOrigin::signed(CHARLIE), 2),
+                Error::<Test>::SubmissionWindowStillOpen
+            );
+        }
+        assert_ok!(submit(60));
+
+        System::set_block_number(5);
+        assert_eq!(PoBA::slot_phase(2), Some(SlotPhase::Quiet));
+        assert_noop!(submit(70), Error::<Test>::SubmissionWindowClosed);
+        let late = unsigned_call(2, vec![m(1, 1, 70)], 0, 1);
+        assert_eq!(
+            PoBA::validate_unsigned(TransactionSource::External, &late),
+            InvalidTransaction::Stale.into()
+        );
+        assert_ok!(PoBA::finalize_slot(RuntimeOrigin::signed(CHARLIE), 2));
+        assert_eq!(FinalizedProposal::<Test>::get(2).unwrap().total_score, 60);
+    });
+}
+
+#[test]
+fn deadline_priority_rises_as_the_slot_closes() {
+    use crate::Call;
+
+    new_test_ext().execute_with(|| {
+        SubmissionWindow::set(3);
+        let submit = Call::submit_proposal {
+            slot: 2,
+            snapshot_hash: SNAPSHOT,
+            total_score: 50,
+            matches: vec![m(1, 1, 50)],
+        };
+        let finalize = Call::finalize_slot { slot: 2 };
+        let validity = |call: &Call<Test>| PoBA::deadline_validity(&ALICE, call).unwrap();
+
+        // Slot 2 closes at block 5: three blocks left at block 2, one at block 4.
+        System::set_block_number(2);
+        assert_eq!(PoBA::blocks_until_close(2), 3);
+        let early = validity(&submit);
+        assert_eq!((early.priority, early.longevity), (33, 3));
+
+        System::set_block_number(4);
+        let late = validity(&submit);
+        assert_eq!((late.priority, late.longevity), (100, 1));
+
+        // Finalization is most urgent once the slot closed and does not expire.
+        System::set_block_number(6);
+        assert_eq!(PoBA::blocks_until_close(2), 0);
+        let due = validity(&finalize);
+        assert_eq!((due.priority, due.longevity), (100, u64::MAX));
+
+        // Other calls and unauthorized senders get no boost.
+        assert!(PoBA::deadline_validity(&ALICE, &Call::prune_finalized { slot: 2 }).is_none());
+        assert!(PoBA::deadline_validity(&DAVE, &submit).is_none());
+    });
+}
+
+#[test]
+fn phase_changes_are_announced_once_per_slot() {
+    new_test_ext().execute_with(|| {
+        PoBA::on_finalize(1);
+        assert!(System::events().is_empty());
+
+        SubmissionWindow::set(2);
+        for block in 1..=3u64 {
+            System::reset_events();
+            System::set_block_number(block);
+            PoBA::on_finalize(block);
+            System::assert_has_event(
+                Event::SlotPhaseChanged { slot: block, phase: SlotPhase::Submission }.into(),
+            );
+            let closed = System::events().iter().any(|r| {
+                matches!(r.event, RuntimeEvent::PoBA(Event::SlotPhaseChanged { phase: SlotPhase::Quiet, .. }))
+            });
+            // Block 3 closes slot 1; nothing closes before.
+            assert_eq!(closed, block == 3);
+        }
+        System::assert_has_event(Event::SlotPhaseChanged { slot: 1, phase: SlotPhase::Quiet }.into());
+
+        // A second block within slot 3 changes no phase.
+        System::reset_events();
+        Now::set(Some(3 * SLOT_MS + SLOT_MS / 2));
+        System::set_block_number(4);
+        PoBA::on_finalize(4);
+        assert!(System::events().is_empty());
+        assert_eq!(PhaseSlot::<Test>::get(), Some(3));
+    });
+}
+
+#[test]
+fn longevity_counts_blocks_not_slots() {
+    use crate::Call;
+
+    new_test_ext().execute_with(|| {
+        SubmissionWindow::set(3);
+        BlockTime::set(SLOT_MS / 2);
+        let submit = Call::submit_proposal {
+            slot: 2,
+            snapshot_hash: SNAPSHOT,
+            total_score: 50,
+            matches: vec![m(1, 1, 50)],
+        };
+
+        // Slot 2 closes at slot 5: three slots, six blocks of half a slot.
+        System::set_block_number(2);
+        assert_eq!(PoBA::blocks_until_close(2), 6);
+        assert_eq!(PoBA::deadline_validity(&ALICE, &submit).unwrap().longevity, 6);
+
+        // Half-way through slot 4 one block is left.
+        Now::set(Some(4 * SLOT_MS + SLOT_MS / 2));
+        assert_eq!(PoBA::blocks_until_close(2), 1);
+    });
+}
+
+/// Submit `matches` for `slot` as `who` and finalize the slot.
+fn finalize_with(who: u64, slot: u64, matches: Vec<([u8; 16], [u8; 16], u32, i64)>) {
+    let total = matches.iter().map(|(_, _, _, score)| score).sum();
+    assert_ok!(PoBA::submit_proposal(RuntimeOrigin::signed(who), slot, SNAPSHOT, total, matches));
+    assert_ok!(PoBA::finalize_slot(RuntimeOrigin::signed(CHARLIE), slot));
+}
+
+fn report(who: u64, slot: u64, proof: InvalidityProof) -> sp_runtime::DispatchResult {
+    PoBA::report_invalid_finalization(RuntimeOrigin::signed(who), slot, proof)
+}
+
+#[test]
+fn winner_bond_is_held_for_the_report_window() {
+    new_test_ext().execute_with(|| {
+        ReportWindow::set(5);
+        assert_ok!(PoBA::submit_proposal(RuntimeOrigin::signed(BOB), 1, SNAPSHOT, 40, vec![m(1, 2, 40)]));
+        finalize_with(ALICE, 1, vec![m(1, 1, 50)]);
+
+        assert_eq!(PoBA::report_window(1), Some((ALICE, 6)));
+        assert_eq!(Balances::reserved_balance(ALICE), BOND);
+        assert_noop!(
+            PoBA::reclaim_bond(RuntimeOrigin::signed(ALICE), 1),
+            Error::<Test>::ReportWindowActive
+        );
+        // Losers are not held.
+        assert_ok!(PoBA::reclaim_bond(RuntimeOrigin::signed(BOB), 1));
+
+        System::set_block_number(6);
+        assert_ok!(PoBA::reclaim_bond(RuntimeOrigin::signed(ALICE), 1));
+        assert_eq!(Balances::reserved_balance(ALICE), 0);
+        assert_eq!(PoBA::report_window(1), None);
+    });
+}
+
+#[test]
+fn proven_duplicate_slashes_winner_and_revokes_slot() {
+    new_test_ext().execute_with(|| {
+        ReportWindow::set(5);
+        // Alice inflates her score by matching request 5 twice.
+        finalize_with(ALICE, 1, vec![m(5, 1, 50), m(5, 3, 80)]);
+        let escrow_id = Escrow::request_to_escrow([5; 16]).unwrap();
+        let issuance = Balances::total_issuance();
+
+        let dup = || InvalidityProof::Violation(ChallengeReason::DuplicateRequest);
+        assert_noop!(
+            report(BOB, 1, InvalidityProof::Violation(ChallengeReason::ScoreMismatch)),
+            Error::<Test>::ReportRejected
+        );
+        assert_noop!(report(ALICE, 1, dup()), Error::<Test>::ReportRejected);
+        assert_ok!(report(BOB, 1, dup()));
+        System::assert_last_event(
+            Event::FinalizationRevoked { slot: 1, proposer: ALICE, reporter: BOB, slashed: 5, reward: 2 }
+                .into(),
+        );
+
+        // Half the bond is slashed, 40% of that paid to Bob, the rest burned.
+        assert_eq!(Balances::reserved_balance(ALICE), 0);
+        assert_eq!(Balances::free_balance(ALICE), 1_000 - 5);
+        assert_eq!(Rewards::<Test>::get(ALICE), REWARD);
+        assert_eq!(Balances::free_balance(BOB), 1_002);
+        assert_eq!(Balances::total_issuance(), issuance - 3);
+        assert_eq!(Bonds::<Test>::get(1, ALICE), None);
+
+        // The slot stays finalized but revoked; its request is free again.
+        assert_eq!(PoBA::revoked_by(1), Some(BOB));
+        assert!(FinalizedProposal::<Test>::get(1).is_some());
+        assert_eq!(MatchedRequests::<Test>::get([5; 16]), None);
+        assert_eq!(PoBA::proposer_stats(ALICE).wins, 0);
+        assert_eq!(Escrow::flagged_escrow(escrow_id), Some(1));
+        System::assert_has_event(pallet_escrow::Event::EscrowFlagged { escrow_id, slot: 1 }.into());
+
+        assert_noop!(report(CHARLIE, 1, dup()), Error::<Test>::FinalizationAlreadyRevoked);
+    });
+}
+
+#[test]
+fn price_and_score_violations_can_be_reported() {
+    new_test_ext().execute_with(|| {
+        ReportWindow::set(5);
+        finalize_with(ALICE, 1, vec![m(1, 1, 50)]);
+        finalize_with(BOB, 2, vec![m(2, 2, 40)]);
+        // Proposals accepted before these rules were checked at submission.
+        FinalizedProposal::<Test>::mutate(1, |p| {
+            let p = p.as_mut().unwrap();
+            p.matches[0].request_uuid = [BANDED; 16];
+            p.matches[0].agreed_price_cents = BAND_MAX + 1;
+        });
+        FinalizedProposal::<Test>::mutate(2, |p| p.as_mut().unwrap().total_score = 99);
+
+        assert_noop!(
+            report(CHARLIE, 1, InvalidityProof::Violation(ChallengeReason::ScoreMismatch)),
+            Error::<Test>::ReportRejected
+        );
+        assert_noop!(
+            report(CHARLIE, 1, InvalidityProof::Violation(ChallengeReason::Outscored)),
+            Error::<Test>::ReportRejected
+        );
+        assert_ok!(report(CHARLIE, 1, InvalidityProof::Violation(ChallengeReason::PriceOutOfBand)));
+        assert_ok!(report(CHARLIE, 2, InvalidityProof::Violation(ChallengeReason::ScoreMismatch)));
+        assert_eq!(PoBA::revoked_by(1), Some(CHARLIE));
+        assert_eq!(PoBA::revoked_by(2), Some(CHARLIE));
+    });
+}
+
+#[test]
+fn request_matched_in_an_earlier_slot_can_be_reported() {
+    new_test_ext().execute_with(|| {
+        ReportWindow::set(5);
+        finalize_with(ALICE, 1, vec![m(1, 1, 50)]);
+        // Released by mistake, then matched again by Bob.
+        assert_ok!(PoBA::unmatch_request(RuntimeOrigin::root(), [1; 16]));
+        finalize_with(BOB, 2, vec![m(1, 2, 40), m(2, 2, 30)]);
+
+        let twice = |request: u8, other_slot| InvalidityProof::RequestMatchedTwice {
+            request_uuid: [request; 16],
+            other_slot,
+        };
+        // Only the later slot is at fault, and both must hold the request.
+        assert_noop!(report(CHARLIE, 1, twice(1, 2)), Error::<Test>::ReportRejected);
+        assert_noop!(report(CHARLIE, 2, twice(2, 1)), Error::<Test>::ReportRejected);
+        assert_noop!(report(CHARLIE, 2, twice(1, 2)), Error::<Test>::ReportRejected);
+        assert_ok!(report(CHARLIE, 2, twice(1, 1)));
+
+        assert_eq!(PoBA::revoked_by(2), Some(CHARLIE));
+        assert_eq!(PoBA::revoked_by(1), None);
+        assert_eq!(MatchedRequests::<Test>::get([2; 16]), None);
+        assert_eq!(Balances::reserved_balance(BOB), 0);
+        assert_eq!(Balances::reserved_balance(ALICE), BOND);
+    });
+}
+
+#[test]
+fn reports_need_an_open_report_window() {
+    new_test_ext().execute_with(|| {
+        let dup = || InvalidityProof::Violation(ChallengeReason::DuplicateRequest);
+        // Reports are off by default: the winner's bond is already returned.
+        finalize_with(ALICE, 1, vec![m(1, 1, 50), m(1, 3, 80)]);
+        assert_eq!(Balances::reserved_balance(ALICE), 0);
+        assert_noop!(report(BOB, 1, dup()), Error::<Test>::ReportWindowClosed);
+
+        ReportWindow::set(2);
+        finalize_with(ALICE, 2, vec![m(2, 1, 50), m(2, 3, 80)]);
+        assert_noop!(report(BOB, 3, dup()), Error::<Test>::ReportWindowClosed);
+
+        System::set_block_number(3);
+        assert_noop!(report(BOB, 2, dup()), Error::<Test>::ReportWindowClosed);
+        assert_eq!(Balances::reserved_balance(ALICE), BOND);
+    });
+}
+
+#[test]
+fn current_slot_follows_block_number() {
+    new_test_ext().execute_with(|| {
+        assert_eq!(PoBA::current_slot(), 1);
+        System::set_block_number(42);
+        assert_eq!(PoBA::current_slot(), 42);
+
+        // Submissions are checked against the same value.
+        SlotTolerance::set(1);
+        assert_noop!(
+            PoBA::submit_proposal(RuntimeOrigin::signed(ALICE), 40, SNAPSHOT, 50, vec![m(1, 1, 50)]),
+            Error::<Test>::SlotOutsideWindow
+        );
+        assert_ok!(PoBA::submit_proposal(RuntimeOrigin::signed(ALICE), 41, SNAPSHOT, 50, vec![m(1, 1, 50)]));
+    });
+}
+
+#[test]
+fn current_slot_advances_as_the_timestamp_crosses_windows() {
+    new_test_ext().execute_with(|| {
+        // The block number plays no part once the clock is set.
+        System::set_block_number(7);
+        Now::set(Some(40 * SLOT_MS - 1));
+        assert_eq!(PoBA::current_slot(), 39);
+        Now::set(Some(40 * SLOT_MS));
+        assert_eq!(PoBA::current_slot(), 40);
+        Now::set(Some(41 * SLOT_MS - 1));
+        assert_eq!(PoBA::current_slot(), 40);
+
+        SlotTolerance::set(0);
+        assert_ok!(PoBA::submit_proposal(RuntimeOrigin::signed(ALICE), 40, SNAPSHOT, 50, vec![m(1, 1, 50)]));
+
+        Now::set(Some(41 * SLOT_MS));
+        assert_eq!(PoBA::current_slot(), 41);
+        assert_noop!(
+            PoBA::submit_proposal(RuntimeOrigin::signed(BOB), 40, SNAPSHOT, 50, vec![m(1, 1, 50)]),
+            Error::<Test>::SlotOutsideWindow
+        );
+    });
+}
@@ -22,9 +22,14 @@ use sp_version::RuntimeVersion;
 use crate::InherentDataExt;
 
 use crate::{
-    AccountId, Balance, Block, Executive, Nonce, VERSION,
-    Aura, Grandpa, Runtime, RuntimeCall, RuntimeGenesisConfig, SessionKeys, System, TransactionPayment,
+    AccountId, Balance, Block, BlockNumber, Contracts, Escrow, Executive, Hash, Nonce, PoBA, PoBAMaxMatches, VERSION,
+    Aura, Grandpa, Runtime, RuntimeCall, RuntimeEvent, RuntimeGenesisConfig, SessionKeys, System,
+    TransactionPayment,
 };
+use crate::configs::RuntimeBlockWeights;
+
+/// Event record type returned by the contracts runtime API.
+type EventRecord = frame_system::EventRecord<RuntimeEvent, Hash>;
 
 impl_runtime_apis! {
     impl sp_api::Core<Block> for Runtime {
@@ -194,6 +199,118 @@ impl_runtime_apis! {
         }
     }
 
+    impl pallet_contracts::ContractsApi<Block, AccountId, Balance, BlockNumber, Hash, EventRecord>
+        for Runtime
+    {
+        fn call(
+            origin: AccountId,
+            dest: AccountId,
+            value: Balance,
+            gas_limit: Option<Weight>,
+            storage_deposit_limit: Option<Balance>,
+            input_data: Vec<u8>,
+        ) -> pallet_contracts::ContractExecResult<Balance, EventRecord> {
+            let gas_limit = gas_limit.unwrap_or(RuntimeBlockWeights::get().max_block);
+            Contracts::bare_call(
+                origin,
+                dest,
+                value,
+                gas_limit,
+                storage_deposit_limit,
+                input_data,
+                pallet_contracts::DebugInfo::UnsafeDebug,
+                pallet_contracts::CollectEvents::UnsafeCollect,
+                pallet_contracts::Determinism::Enforced,
+            )
+        }
+
+        fn instantiate(
+            origin: AccountId,
+            value: Balance,
+            gas_limit: Option<Weight>,
+            storage_deposit_limit: Option<Balance>,
+            code: pallet_contracts::Code<Hash>,
+            data: Vec<u8>,
+            salt: Vec<u8>,
+        ) -> pallet_contracts::ContractInstantiateResult<AccountId, Balance, EventRecord> {
+            let gas_limit = gas_limit.unwrap_or(RuntimeBlockWeights::get().max_block);
+            Contracts::bare_instantiate(
+                origin,
+                value,
+                gas_limit,
+                storage_deposit_limit,
+                code,
+                data,
+                salt,
+                pallet_contracts::DebugInfo::UnsafeDebug,
+                pallet_contracts::CollectEvents::UnsafeCollect,
+            )
+        }
+
+        fn upload_code(
+            origin: AccountId,
+            code: Vec<u8>,
+            storage_deposit_limit: Option<Balance>,
+            determinism: pallet_contracts::Determinism,
+        ) -> pallet_contracts::CodeUploadResult<Hash, Balance> {
+            Contracts::bare_upload_code(origin, code, storage_deposit_limit, determinism)
+        }
+
+        fn get_storage(
+            address: AccountId,
+            key: Vec<u8>,
+        ) -> pallet_contracts::GetStorageResult {
+            Contracts::get_storage(address, key)
+        }
+    }
+
+    impl pallet_escrow::EscrowApi<Block, AccountId, Balance, BlockNumber> for Runtime {
+        fn escrow(
+            escrow_id: pallet_escrow::EscrowId,
+        ) -> Option<pallet_escrow::AssignmentEscrow<AccountId, Balance, BlockNumber>> {
+            Escrow::escrows(escrow_id)
+        }
+
+        fn escrow_for_request(
+            request_uuid: pallet_escrow::RequestUuid,
+        ) -> Option<pallet_escrow::EscrowId> {
+            Escrow::request_to_escrow(request_uuid)
+        }
+
+        fn allowed_transitions(
+            escrow_id: pallet_escrow::EscrowId,
+            role: pallet_escrow::EscrowRole,
+        ) -> Vec<pallet_escrow::DeliveryStatus> {
+            Escrow::allowed_transitions(escrow_id, role)
+        }
+    }
+
+    impl pallet_poba_runtime_api::PobaApi<Block, PoBAMaxMatches, AccountId> for Runtime {
+        fn best_proposal(slot: u64) -> Option<pallet_poba::Proposal<PoBAMaxMatches>> {
+            PoBA::best_proposal(slot)
+        }
+
+        fn finalized_proposal(slot: u64) -> Option<pallet_poba::Proposal<PoBAMaxMatches>> {
+            PoBA::finalized_proposal(slot)
+        }
+
+        fn last_finalized_slot() -> u64 {
+            PoBA::last_finalized_slot()
+        }
+
+        fn matched_requests_since(slot: u64) -> Vec<[u8; 16]> {
+            PoBA::matched_requests_since(slot)
+        }
+
+        fn top_proposers(n: u32) -> Vec<(AccountId, pallet_poba::ProposerStats)> {
+            PoBA::top_proposers(n)
+        }
+
+        fn current_slot() -> u64 {
+            PoBA::current_slot()
+        }
+    }
+
     #[cfg(feature = "runtime-benchmarks")]
     impl frame_benchmarking::Benchmark<Block> for Runtime {
         fn benchmark_metadata(extra: bool) -> (
@@ -31,4 +31,7 @@ frame_benchmarking::define_benchmarks!(
 	[pallet_timestamp, Timestamp]
 	[pallet_sudo, Sudo]
 	[pallet_template, Template]
+	[pallet_utility, Utility]
+	[pallet_proxy, Proxy]
+	[pallet_scheduler, Scheduler]
 );
\ No newline at end of file
@@ -1,8 +1,9 @@
 // This is free and unencumbered software released into the public domain.
 
+use codec::{Decode, DecodeWithMemTracking, Encode, MaxEncodedLen};
 use frame_support::{
     derive_impl, parameter_types,
-    traits::{ConstBool, ConstU32, ConstU64, ConstU8},
+    traits::{ConstBool, ConstU32, ConstU64, ConstU8, EqualPrivilegeOnly, InstanceFilter, Nothing},
     weights::{
         constants::{RocksDbWeight, WEIGHT_REF_TIME_PER_SECOND},
         IdentityFee, Weight,
@@ -11,13 +12,19 @@ use frame_support::{
 use frame_system::limits::{BlockLength, BlockWeights};
 use pallet_transaction_payment::{ConstFeeMultiplier, FungibleAdapter, Multiplier};
 use sp_consensus_aura::sr25519::AuthorityId as AuraId;
-use sp_runtime::{traits::One, Perbill};
+use scale_info::TypeInfo;
+use sp_runtime::{
+    traits::{BlakeTwo256, One},
+    Perbill, RuntimeDebug,
+};
 use sp_version::RuntimeVersion;
 
 // Pull runtime items from the parent module (runtime/src/lib.rs)
 use crate::{
-    AccountId, Balance, Block, BlockNumber, Hash, Nonce, VERSION, SLOT_DURATION, EXISTENTIAL_DEPOSIT,
-    Aura, Balances, Runtime, RuntimeCall, RuntimeEvent, System, PalletInfo, RuntimeOrigin,
+    AccountId, Balance, Block, BlockNumber, Hash, Nonce, VERSION, MINIMUM_PERIOD, EXISTENTIAL_DEPOSIT,
+    MICRO_UNIT, MILLI_UNIT,
+    Aura, Balances, RandomnessCollectiveFlip, Runtime, RuntimeCall, RuntimeEvent,
+    RuntimeHoldReason, System, Timestamp, PalletInfo, RuntimeOrigin, OriginCaller,
 };
 
 const NORMAL_DISPATCH_RATIO: Perbill = Perbill::from_percent(75);
@@ -92,20 +99,19 @@ impl pallet_grandpa::Config for Runtime {
 }
 
 /// Timestamp config:
-/// - **IMPORTANT**: `MinimumPeriod = SLOT_DURATION / 2`.
+/// - **IMPORTANT**: `MinimumPeriod = SLOT_DURATION / 2` (`MINIMUM_PERIOD`).
 ///   With `SLOT_DURATION = 6000` (in lib.rs), MinimumPeriod = 3000ms,
-///   so Aura slot = 3000*2 = **6000ms**.
+///   so Aura slot = 3000*2 = **6000ms**; lib.rs asserts this at compile time.
 impl pallet_timestamp::Config for Runtime {
     type Moment = u64;
     type OnTimestampSet = Aura;
-    type MinimumPeriod = ConstU64<{ SLOT_DURATION / 2 }>;
+    type MinimumPeriod = ConstU64<MINIMUM_PERIOD>;
     type WeightInfo = ();
 }
 
 /// Balances config (basic).
-/// NOTE: We do **not** use Freeze/Hold reasons in this runtime to keep things simple.
-///       If you later add `RuntimeHoldReason`/`RuntimeFreezeReason` derives in the runtime macro,
-///       you can re-enable the related associated types here.
+/// NOTE: Hold reasons are wired to the runtime's `RuntimeHoldReason` because
+///       pallet-contracts holds storage deposits. Freeze reasons are still unused.
 impl pallet_balances::Config for Runtime {
     type MaxLocks = ConstU32<50>;
     type MaxReserves = ();
@@ -122,7 +128,7 @@ impl pallet_balances::Config for Runtime {
     // No freeze/hold reasons in this minimal setup:
     type FreezeIdentifier = ();
     type MaxFreezes = ();
-    type RuntimeHoldReason = ();
+    type RuntimeHoldReason = RuntimeHoldReason;
     type RuntimeFreezeReason = ();
     type DoneSlashHandler = ();
 }
@@ -153,4 +159,199 @@ impl pallet_sudo::Config for Runtime {
 impl pallet_template::Config for Runtime {
     type RuntimeEvent = RuntimeEvent;
     type WeightInfo = pallet_template::weights::SubstrateWeight<Runtime>;
-}
\ No newline at end of file
+}
+
+/// Randomness source required by pallet-contracts.
+/// NOTE: insecure; fine for a dev/pilot chain, not for value-bearing randomness.
+impl pallet_insecure_randomness_collective_flip::Config for Runtime {}
+
+/// Storage deposit helper for pallet-contracts and pallet-proxy (per item + per byte).
+const fn deposit(items: u32, bytes: u32) -> Balance {
+    items as Balance * 15 * MILLI_UNIT + (bytes as Balance) * 6 * MICRO_UNIT
+}
+
+parameter_types! {
+    pub const DepositPerItem: Balance = deposit(1, 0);
+    pub const DepositPerByte: Balance = deposit(0, 1);
+    pub const DefaultDepositLimit: Balance = deposit(1024, 1024 * 1024);
+    pub const CodeHashLockupDepositPercent: Perbill = Perbill::from_percent(30);
+    pub ContractsSchedule: pallet_contracts::Schedule<Runtime> = Default::default();
+}
+
+/// Contracts config (hosts the ink! escrow contract).
+/// - Contracts may not dispatch runtime calls (`CallFilter = Nothing`).
+/// - Storage deposits are held from the caller via `Balances`.
+/// - Remaining items use the pallet's default config prelude.
+#[derive_impl(pallet_contracts::config_preludes::TestDefaultConfig)]
+impl pallet_contracts::Config for Runtime {
+    type Time = Timestamp;
+    type Randomness = RandomnessCollectiveFlip;
+    type Currency = Balances;
+    type RuntimeEvent = RuntimeEvent;
+    type RuntimeCall = RuntimeCall;
+    type RuntimeHoldReason = RuntimeHoldReason;
+    type CallFilter = Nothing;
+    type DepositPerItem = DepositPerItem;
+    type DepositPerByte = DepositPerByte;
+    type DefaultDepositLimit = DefaultDepositLimit;
+    type CodeHashLockupDepositPercent = CodeHashLockupDepositPercent;
+    type Schedule = ContractsSchedule;
+    type WeightPrice = pallet_transaction_payment::Pallet<Self>;
+    type WeightInfo = pallet_contracts::weights::SubstrateWeight<Self>;
+    type MaxCodeLen = ConstU32<{ 123 * 1024 }>;
+    type MaxStorageKeyLen = ConstU32<128>;
+    type UnsafeUnstableInterface = ConstBool<false>;
+    type UploadOrigin = frame_system::EnsureSigned<Self::AccountId>;
+    type InstantiateOrigin = frame_system::EnsureSigned<Self::AccountId>;
+}
+
+/// Utility (batching). The backend wraps finalize + escrow creation in
+/// `batch_all` so they land atomically.
+impl pallet_utility::Config for Runtime {
+    type RuntimeEvent = RuntimeEvent;
+    type RuntimeCall = RuntimeCall;
+    type PalletsOrigin = OriginCaller;
+    type WeightInfo = pallet_utility::weights::SubstrateWeight<Runtime>;
+}
+
+/// What a proxy account may dispatch on behalf of its delegator.
+/// There is no bids pallet, so the market is PoBA plus Escrow.
+#[derive(
+    Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Encode, Decode, DecodeWithMemTracking,
+    RuntimeDebug, MaxEncodedLen, TypeInfo, Default,
+)]
+pub enum ProxyType {
+    /// Any call.
+    #[default]
+    Any,
+    /// PoBA calls (proposals, finalization).
+    PobaOnly,
+    /// Escrow calls.
+    EscrowOnly,
+    /// PoBA and Escrow calls: a hot key for the backend's market operations.
+    MarketOnly,
+}
+
+impl InstanceFilter<RuntimeCall> for ProxyType {
+    fn filter(&self, c: &RuntimeCall) -> bool {
+        // Utility is allowed so batches work; its inner calls are filtered too.
+        match self {
+            ProxyType::Any => true,
+            ProxyType::PobaOnly => {
+                matches!(c, RuntimeCall::PoBA(..) | RuntimeCall::Utility(..))
+            },
+            ProxyType::EscrowOnly => {
+                matches!(c, RuntimeCall::Escrow(..) | RuntimeCall::Utility(..))
+            },
+            ProxyType::MarketOnly => matches!(
+                c,
+                RuntimeCall::PoBA(..) | RuntimeCall::Escrow(..) | RuntimeCall::Utility(..)
+            ),
+        }
+    }
+
+    fn is_superset(&self, o: &Self) -> bool {
+        match (self, o) {
+            (x, y) if x == y => true,
+            (ProxyType::Any, _) => true,
+            (_, ProxyType::Any) => false,
+            (ProxyType::MarketOnly, _) => true,
+            _ => false,
+        }
+    }
+}
+
+parameter_types! {
+    // One storage item plus 33 bytes (AccountId + ProxyType) per proxy.
+    pub const ProxyDepositBase: Balance = deposit(1, 8);
+    pub const ProxyDepositFactor: Balance = deposit(0, 33);
+    pub const AnnouncementDepositBase: Balance = deposit(1, 8);
+    pub const AnnouncementDepositFactor: Balance = deposit(0, 66);
+}
+
+/// Proxy config: lets a cold backend key delegate a restricted hot key.
+impl pallet_proxy::Config for Runtime {
+    type RuntimeEvent = RuntimeEvent;
+    type RuntimeCall = RuntimeCall;
+    type Currency = Balances;
+    type ProxyType = ProxyType;
+    type ProxyDepositBase = ProxyDepositBase;
+    type ProxyDepositFactor = ProxyDepositFactor;
+    type MaxProxies = ConstU32<32>;
+    type WeightInfo = pallet_proxy::weights::SubstrateWeight<Runtime>;
+    type MaxPending = ConstU32<32>;
+    type CallHasher = BlakeTwo256;
+    type AnnouncementDepositBase = AnnouncementDepositBase;
+    type AnnouncementDepositFactor = AnnouncementDepositFactor;
+    type BlockNumberProvider = System;
+}
+
+parameter_types! {
+    /// Scheduled tasks (escrow timeouts) may use up to 80% of a block.
+    pub MaximumSchedulerWeight: Weight =
+        Perbill::from_percent(80) * RuntimeBlockWeights::get().max_block;
+}
+
+/// Scheduler: runs escrow timeouts at their deadline block. Calls are kept
+/// inline, so no preimage pallet is needed.
+impl pallet_scheduler::Config for Runtime {
+    type RuntimeEvent = RuntimeEvent;
+    type RuntimeOrigin = RuntimeOrigin;
+    type PalletsOrigin = OriginCaller;
+    type RuntimeCall = RuntimeCall;
+    type MaximumWeight = MaximumSchedulerWeight;
+    type ScheduleOrigin = frame_system::EnsureRoot<AccountId>;
+    type MaxScheduledPerBlock = ConstU32<64>;
+    type WeightInfo = pallet_scheduler::weights::SubstrateWeight<Runtime>;
+    type OriginPrivilegeCmp = EqualPrivilegeOnly;
+    type Preimages = ();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn poba_call() -> RuntimeCall {
+        RuntimeCall::PoBA(pallet_poba::Call::finalize_slot { slot: 1 })
+    }
+
+    fn escrow_call() -> RuntimeCall {
+        RuntimeCall::Escrow(pallet_escrow::Call::mark_delivered { escrow_id: 0 })
+    }
+
+    fn balances_call() -> RuntimeCall {
+        RuntimeCall::Balances(pallet_balances::Call::transfer_keep_alive {
+            dest: AccountId::from([1u8; 32]).into(),
+            value: 1,
+        })
+    }
+
+    fn batch_call() -> RuntimeCall {
+        RuntimeCall::Utility(pallet_utility::Call::batch_all { calls: vec![poba_call()] })
+    }
+
+    #[test]
+    fn proxy_types_filter_their_pallets() {
+        let cases = [
+            (ProxyType::Any, [true, true, true, true]),
+            (ProxyType::PobaOnly, [true, false, false, true]),
+            (ProxyType::EscrowOnly, [false, true, false, true]),
+            (ProxyType::MarketOnly, [true, true, false, true]),
+        ];
+        for (proxy, [poba, escrow, balances, batch]) in cases {
+            assert_eq!(proxy.filter(&poba_call()), poba, "{proxy:?} poba");
+            assert_eq!(proxy.filter(&escrow_call()), escrow, "{proxy:?} escrow");
+            assert_eq!(proxy.filter(&balances_call()), balances, "{proxy:?} balances");
+            assert_eq!(proxy.filter(&batch_call()), batch, "{proxy:?} batch");
+        }
+    }
+
+    #[test]
+    fn market_only_covers_the_narrower_proxies() {
+        assert!(ProxyType::Any.is_superset(&ProxyType::MarketOnly));
+        assert!(ProxyType::MarketOnly.is_superset(&ProxyType::PobaOnly));
+        assert!(ProxyType::MarketOnly.is_superset(&ProxyType::EscrowOnly));
+        assert!(!ProxyType::MarketOnly.is_superset(&ProxyType::Any));
+        assert!(!ProxyType::PobaOnly.is_superset(&ProxyType::EscrowOnly));
+    }
+}
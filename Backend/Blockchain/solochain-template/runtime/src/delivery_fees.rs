@@ -0,0 +1,212 @@
+//runtime/src/delivery_fees.rs
+
+//! Feeless courier lifecycle calls.
+//!
+//! `SkipFeeForDeliveryCalls` wraps `ChargeTransactionPayment` in the
+//! `TxExtension` tuple. For `Escrow::mark_picked_up`, `mark_delivered` and
+//! `confirm_received` sent by the escrow's own driver/payer
+//! (`pallet_escrow::Pallet::is_feeless`) no fee is withdrawn; every other
+//! transaction, including those calls from unrelated accounts, pays as before.
+//!
+//! The wrapper keeps the inner identifier, encoding and metadata, so clients
+//! sign exactly the same payload as without it.
+
+use alloc::vec::Vec;
+use codec::{Decode, DecodeWithMemTracking, Encode};
+use frame_support::weights::Weight;
+use pallet_transaction_payment::ChargeTransactionPayment;
+use scale_info::TypeInfo;
+use sp_runtime::{
+    traits::{
+        AsSystemOriginSigner, DispatchInfoOf, Implication, PostDispatchInfoOf,
+        TransactionExtension, TransactionExtensionMetadata, ValidateResult,
+    },
+    transaction_validity::{TransactionSource, TransactionValidityError, ValidTransaction},
+    DispatchResult,
+};
+
+use crate::{Runtime, RuntimeCall, RuntimeOrigin};
+
+type Inner = ChargeTransactionPayment<Runtime>;
+
+/// `ChargeTransactionPayment`, skipped for feeless courier calls.
+#[derive(Clone, Eq, PartialEq, Encode, Decode, DecodeWithMemTracking, TypeInfo, Debug)]
+pub struct SkipFeeForDeliveryCalls(pub Inner);
+
+impl From<Inner> for SkipFeeForDeliveryCalls {
+    fn from(inner: Inner) -> Self {
+        Self(inner)
+    }
+}
+
+impl SkipFeeForDeliveryCalls {
+    /// Wraps `ChargeTransactionPayment::from(tip)`.
+    pub fn from_tip(tip: crate::Balance) -> Self {
+        Self(Inner::from(tip))
+    }
+}
+
+/// Whether `origin` sends a courier call it should not pay for.
+fn is_feeless(origin: &RuntimeOrigin, call: &RuntimeCall) -> bool {
+    let RuntimeCall::Escrow(call) = call else {
+        return false;
+    };
+    origin
+        .as_system_origin_signer()
+        .is_some_and(|who| pallet_escrow::Pallet::<Runtime>::is_feeless(who, call))
+}
+
+impl TransactionExtension<RuntimeCall> for SkipFeeForDeliveryCalls {
+    const IDENTIFIER: &'static str = <Inner as TransactionExtension<RuntimeCall>>::IDENTIFIER;
+    type Implicit = <Inner as TransactionExtension<RuntimeCall>>::Implicit;
+    // `None`: the fee was skipped.
+    type Val = Option<<Inner as TransactionExtension<RuntimeCall>>::Val>;
+    type Pre = Option<<Inner as TransactionExtension<RuntimeCall>>::Pre>;
+
+    fn metadata() -> Vec<TransactionExtensionMetadata> {
+        <Inner as TransactionExtension<RuntimeCall>>::metadata()
+    }
+
+    fn implicit(&self) -> Result<Self::Implicit, TransactionValidityError> {
+        self.0.implicit()
+    }
+
+    fn weight(&self, call: &RuntimeCall) -> Weight {
+        self.0.weight(call)
+    }
+
+    fn validate(
+        &self,
+        origin: RuntimeOrigin,
+        call: &RuntimeCall,
+        info: &DispatchInfoOf<RuntimeCall>,
+        len: usize,
+        self_implicit: Self::Implicit,
+        inherited_implication: &impl Implication,
+        source: TransactionSource,
+    ) -> ValidateResult<Self::Val, RuntimeCall> {
+        if is_feeless(&origin, call) {
+            return Ok((ValidTransaction::default(), None, origin));
+        }
+        let (valid, val, origin) = self.0.validate(
+            origin,
+            call,
+            info,
+            len,
+            self_implicit,
+            inherited_implication,
+            source,
+        )?;
+        Ok((valid, Some(val), origin))
+    }
+
+    fn prepare(
+        self,
+        val: Self::Val,
+        origin: &RuntimeOrigin,
+        call: &RuntimeCall,
+        info: &DispatchInfoOf<RuntimeCall>,
+        len: usize,
+    ) -> Result<Self::Pre, TransactionValidityError> {
+        match val {
+            Some(val) => self.0.prepare(val, origin, call, info, len).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn post_dispatch_details(
+        pre: Self::Pre,
+        info: &DispatchInfoOf<RuntimeCall>,
+        post_info: &PostDispatchInfoOf<RuntimeCall>,
+        len: usize,
+        result: &DispatchResult,
+    ) -> Result<Weight, TransactionValidityError> {
+        match pre {
+            Some(pre) => Inner::post_dispatch_details(pre, info, post_info, len, result),
+            None => Ok(Weight::zero()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        test_utils::{self, signed},
+        Balance, Balances, Executive, EXISTENTIAL_DEPOSIT, UNIT,
+    };
+    use frame_support::sp_io;
+    use sp_keyring::Sr25519Keyring::{self, Alice, Bob, Charlie};
+
+    const DRIVER: Sr25519Keyring = Alice;
+    const PAYER: Sr25519Keyring = Bob;
+    const STRANGER: Sr25519Keyring = Charlie;
+
+    fn new_test_ext() -> sp_io::TestExternalities {
+        // The driver holds only the existential deposit: too little to pay any fee.
+        let balances = vec![(DRIVER, EXISTENTIAL_DEPOSIT), (PAYER, UNIT), (STRANGER, UNIT)];
+        test_utils::new_test_ext(balances)
+    }
+
+    fn free(who: Sr25519Keyring) -> Balance {
+        Balances::free_balance(who.to_account_id())
+    }
+
+    fn escrow_call(call: pallet_escrow::Call<Runtime>) -> RuntimeCall {
+        RuntimeCall::Escrow(call)
+    }
+
+    #[test]
+    fn participants_make_courier_calls_for_free() {
+        new_test_ext().execute_with(|| {
+            let create = escrow_call(pallet_escrow::Call::create_escrow {
+                request_uuid: [1; 16],
+                offer_uuid: [1; 16],
+                driver: DRIVER.to_account_id(),
+                payer: PAYER.to_account_id(),
+                amount: 100,
+            });
+            assert_eq!(Executive::apply_extrinsic(signed(PAYER, create)), Ok(Ok(())));
+            let escrow_id = pallet_escrow::Pallet::<Runtime>::request_to_escrow([1; 16]).unwrap();
+
+            let driver_before = free(DRIVER);
+            for call in [
+                pallet_escrow::Call::mark_picked_up { escrow_id },
+                pallet_escrow::Call::mark_delivered { escrow_id },
+            ] {
+                let xt = signed(DRIVER, escrow_call(call));
+                assert_eq!(Executive::apply_extrinsic(xt), Ok(Ok(())));
+            }
+            assert_eq!(free(DRIVER), driver_before);
+
+            let payer_before = free(PAYER);
+            let confirm = escrow_call(pallet_escrow::Call::confirm_received { escrow_id });
+            assert_eq!(Executive::apply_extrinsic(signed(PAYER, confirm)), Ok(Ok(())));
+            assert_eq!(free(PAYER), payer_before);
+        });
+    }
+
+    #[test]
+    fn unrelated_accounts_still_pay() {
+        new_test_ext().execute_with(|| {
+            let create = escrow_call(pallet_escrow::Call::create_escrow {
+                request_uuid: [1; 16],
+                offer_uuid: [1; 16],
+                driver: DRIVER.to_account_id(),
+                payer: PAYER.to_account_id(),
+                amount: 100,
+            });
+            let payer_before = free(PAYER);
+            assert_eq!(Executive::apply_extrinsic(signed(PAYER, create)), Ok(Ok(())));
+            assert!(free(PAYER) < payer_before);
+            let escrow_id = pallet_escrow::Pallet::<Runtime>::request_to_escrow([1; 16]).unwrap();
+
+            // Included and charged, then rejected by the pallet.
+            let stranger_before = free(STRANGER);
+            let pick_up = escrow_call(pallet_escrow::Call::mark_picked_up { escrow_id });
+            let outcome = Executive::apply_extrinsic(signed(STRANGER, pick_up)).unwrap();
+            assert!(outcome.is_err());
+            assert!(free(STRANGER) < stranger_before);
+        });
+    }
+}
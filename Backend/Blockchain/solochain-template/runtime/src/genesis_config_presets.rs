@@ -15,10 +15,13 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::{AccountId, BalancesConfig, RuntimeGenesisConfig, SudoConfig};
+use crate::{
+	AccountId, BalancesConfig, PoBAConfig, PoBARewardPot, Runtime, RuntimeGenesisConfig,
+	SudoConfig,
+};
 
 use alloc::{vec, vec::Vec};
-use frame_support::build_struct_json_patch;
+use frame_support::{build_struct_json_patch, traits::Get};
 use serde_json::Value;
 use sp_consensus_aura::sr25519::AuthorityId as AuraId;
 use sp_consensus_grandpa::AuthorityId as GrandpaId;
@@ -30,12 +33,15 @@ fn testnet_genesis(
 	initial_authorities: Vec<(AuraId, GrandpaId)>,
 	endowed_accounts: Vec<AccountId>,
 	root: AccountId,
+	proposers: Vec<AccountId>,
+	finalized: Vec<(u64, i64, Vec<([u8; 16], [u8; 16], u32, i64)>)>,
 ) -> Value {
 	build_struct_json_patch!(RuntimeGenesisConfig {
 		balances: BalancesConfig {
 			balances: endowed_accounts
 				.iter()
 				.cloned()
+				.chain(core::iter::once(PoBARewardPot::get()))
 				.map(|k| (k, 1u128 << 60))
 				.collect::<Vec<_>>(),
 		},
@@ -46,6 +52,7 @@ fn testnet_genesis(
 			authorities: initial_authorities.iter().map(|x| (x.1.clone(), 1)).collect::<Vec<_>>(),
 		},
 		sudo: SudoConfig { key: Some(root) },
+		po_ba: PoBAConfig { proposers, authorities: poba_authorities(), finalized },
 	})
 }
 
@@ -63,6 +70,33 @@ pub fn development_config_genesis() -> Value {
 			Sr25519Keyring::BobStash.to_account_id(),
 		],
 		sp_keyring::Sr25519Keyring::Alice.to_account_id(),
+		poba_proposers(),
+		vec![],
+	)
+}
+
+/// Preset id of the development chain with preloaded PoBA history.
+pub const STAGING_HISTORY_PRESET: &str = "staging_history";
+
+/// Development config whose PoBA pallet already finalized a few slots, for
+/// exercising backend reconciliation without replaying them. The history ends
+/// shortly after `STAGING_HISTORY_EPOCH_MS`, before the timestamp-derived slots
+/// of any chain started since, so live slots stay open.
+pub fn staging_history_config_genesis() -> Value {
+	testnet_genesis(
+		vec![(
+			sp_keyring::Sr25519Keyring::Alice.public().into(),
+			sp_keyring::Ed25519Keyring::Alice.public().into(),
+		)],
+		vec![
+			Sr25519Keyring::Alice.to_account_id(),
+			Sr25519Keyring::Bob.to_account_id(),
+			Sr25519Keyring::AliceStash.to_account_id(),
+			Sr25519Keyring::BobStash.to_account_id(),
+		],
+		sp_keyring::Sr25519Keyring::Alice.to_account_id(),
+		poba_proposers(),
+		poba_history(),
 	)
 }
 
@@ -84,14 +118,55 @@ pub fn local_config_genesis() -> Value {
 			.map(|v| v.to_account_id())
 			.collect::<Vec<_>>(),
 		Sr25519Keyring::Alice.to_account_id(),
+		poba_proposers(),
+		vec![],
 	)
 }
 
+/// Unix time (ms) the staging history is anchored to: 2026-01-01T00:00Z.
+pub const STAGING_HISTORY_EPOCH_MS: u64 = 1_767_225_600_000;
+
+/// First PoBA slot at or after `STAGING_HISTORY_EPOCH_MS`.
+pub fn staging_history_base_slot() -> u64 {
+	let slot_ms: u64 = <Runtime as pallet_poba::Config>::SlotDurationMs::get();
+	STAGING_HISTORY_EPOCH_MS.div_ceil(slot_ms)
+}
+
+/// Three finalized PoBA slots (base + 10, 20, 30) with one or two matches each.
+fn poba_history() -> Vec<(u64, i64, Vec<([u8; 16], [u8; 16], u32, i64)>)> {
+	let base = staging_history_base_slot();
+	let m = |request: u8, offer: u8, price: u32, score: i64| ([request; 16], [offer; 16], price, score);
+	vec![
+		(base + 10, 900_000, vec![m(1, 1, 2_500, 900_000)]),
+		(base + 20, 1_700_000, vec![m(2, 1, 1_800, 950_000), m(3, 2, 3_200, 750_000)]),
+		(base + 30, 800_000, vec![m(4, 2, 4_000, 800_000)]),
+	]
+}
+
+/// Authority accounts allowed to submit PoBA proposals (alice, bob, charlie).
+fn poba_proposers() -> Vec<AccountId> {
+	vec![
+		Sr25519Keyring::Alice.to_account_id(),
+		Sr25519Keyring::Bob.to_account_id(),
+		Sr25519Keyring::Charlie.to_account_id(),
+	]
+}
+
+/// PoBA signing keys (`poba` key type) for unsigned proposals, paired with
+/// the proposer accounts above.
+fn poba_authorities() -> Vec<(pallet_poba::crypto::AuthorityId, AccountId)> {
+	[Sr25519Keyring::Alice, Sr25519Keyring::Bob, Sr25519Keyring::Charlie]
+		.into_iter()
+		.map(|k| (k.public().into(), k.to_account_id()))
+		.collect()
+}
+
 /// Provides the JSON representation of predefined genesis config for given `id`.
 pub fn get_preset(id: &PresetId) -> Option<Vec<u8>> {
 	let patch = match id.as_ref() {
 		sp_genesis_builder::DEV_RUNTIME_PRESET => development_config_genesis(),
 		sp_genesis_builder::LOCAL_TESTNET_RUNTIME_PRESET => local_config_genesis(),
+		STAGING_HISTORY_PRESET => staging_history_config_genesis(),
 		_ => return None,
 	};
 	Some(
@@ -106,5 +181,44 @@ pub fn preset_names() -> Vec<PresetId> {
 	vec![
 		PresetId::from(sp_genesis_builder::DEV_RUNTIME_PRESET),
 		PresetId::from(sp_genesis_builder::LOCAL_TESTNET_RUNTIME_PRESET),
+		PresetId::from(STAGING_HISTORY_PRESET),
 	]
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		test_utils::{preset_test_ext, signed},
+		Executive, RuntimeCall,
+	};
+
+	type PoBA = pallet_poba::Pallet<Runtime>;
+
+	#[test]
+	fn staging_history_accepts_the_first_live_slot() {
+		preset_test_ext(STAGING_HISTORY_PRESET).execute_with(|| {
+			let last = staging_history_base_slot() + 30;
+			assert!(PoBA::is_finalized(last));
+			assert_eq!(PoBA::last_finalized_slot(), last);
+
+			// A chain started today: its slots are far past the history.
+			pallet_timestamp::Now::<Runtime>::put(1_792_108_800_000);
+			let slot = PoBA::current_slot();
+			assert!(slot > last);
+			let snapshot_hash = sp_core::H256::repeat_byte(7);
+			pallet_poba::MarketSnapshot::<Runtime>::insert(
+				slot,
+				pallet_poba::MarketSnapshotInfo { snapshot_hash, request_count: 1, offer_count: 1 },
+			);
+			let submit = RuntimeCall::PoBA(pallet_poba::Call::submit_proposal {
+				slot,
+				snapshot_hash,
+				total_score: 10,
+				matches: vec![([9; 16], [9; 16], 1_500, 10)],
+			});
+			let xt = signed(Sr25519Keyring::Alice, submit);
+			assert_eq!(Executive::apply_extrinsic(xt), Ok(Ok(())));
+		});
+	}
+}
@@ -9,6 +9,10 @@ pub mod apis;
 #[cfg(feature = "runtime-benchmarks")]
 mod benchmarks;
 pub mod configs;
+pub mod delivery_fees;
+pub mod poba_priority;
+#[cfg(test)]
+mod test_utils;
 pub mod genesis_config_presets;
 
 extern crate alloc;
@@ -54,7 +58,7 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
     spec_name: alloc::borrow::Cow::Borrowed("solochain-template-runtime"),
     impl_name: alloc::borrow::Cow::Borrowed("solochain-template-runtime"),
     authoring_version: 1,
-    spec_version: 104, 
+    spec_version: 106,
     impl_version: 1,
     apis: apis::RUNTIME_API_VERSIONS,
     transaction_version: 1,
@@ -62,9 +66,14 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
 };
 
 // ----------------------------- Block time (6s) ------------------------------
+/// `MILLI_SECS_PER_BLOCK` is the only value to edit when retuning block time:
+/// Aura's slot, Timestamp's `MinimumPeriod`, the time units below, PoBA's slot
+/// length and the PoBA worker's fallback timer all derive from it.
 mod block_times {
     pub const MILLI_SECS_PER_BLOCK: u64 = 6000;
     pub const SLOT_DURATION: u64 = MILLI_SECS_PER_BLOCK;
+    /// Timestamp's `MinimumPeriod`; Aura's slot is twice this.
+    pub const MINIMUM_PERIOD: u64 = SLOT_DURATION / 2;
 }
 pub use block_times::*;
 
@@ -74,6 +83,18 @@ pub const MINUTES: BlockNumber = 60_000 / (MILLI_SECS_PER_BLOCK as BlockNumber);
 pub const HOURS: BlockNumber = MINUTES * 60;
 pub const DAYS: BlockNumber = HOURS * 24;
 
+// Compile-time checks that a retuned block time stays coherent.
+const _: () = {
+    // Aura (`MinimumPeriodTimesTwo`) must land exactly on the block time.
+    assert!(MINIMUM_PERIOD * 2 == MILLI_SECS_PER_BLOCK, "block time must be an even number of ms");
+    // Time units must not be truncated.
+    assert!(
+        MINUTES as u64 * MILLI_SECS_PER_BLOCK == 60_000,
+        "block time must divide one minute"
+    );
+    assert!(DAYS as u64 * MILLI_SECS_PER_BLOCK == 86_400_000);
+};
+
 // ------------------------------ Misc constants ------------------------------
 pub const BLOCK_HASH_COUNT: BlockNumber = 2400;
 
@@ -110,12 +131,23 @@ construct_runtime!(
         TransactionPayment: pallet_transaction_payment,
         Sudo: pallet_sudo,
 
+        // ink! smart contracts (hosts the escrow contract under /contracts/escrow)
+        RandomnessCollectiveFlip: pallet_insecure_randomness_collective_flip,
+        Contracts: pallet_contracts,
+
         // Optional example
         Template: pallet_template,
 
         // Our PoBA pallet
         PoBA: pallet_poba,
         Escrow: pallet_escrow,
+
+        // Batching and key delegation for the backend signer
+        Utility: pallet_utility,
+        Proxy: pallet_proxy,
+
+        // Runs escrow timeouts at their deadline
+        Scheduler: pallet_scheduler,
     }
 );
 
@@ -132,7 +164,10 @@ pub type TxExtension = (
     frame_system::CheckEra<Runtime>,
     frame_system::CheckNonce<Runtime>,
     frame_system::CheckWeight<Runtime>,
-    pallet_transaction_payment::ChargeTransactionPayment<Runtime>,
+    // ChargeTransactionPayment, waived for couriers' own lifecycle calls.
+    delivery_fees::SkipFeeForDeliveryCalls,
+    // Pool priority for PoBA calls whose slot is about to close.
+    poba_priority::PrioritizePobaDeadline,
     frame_metadata_hash_extension::CheckMetadataHash<Runtime>,
     frame_system::WeightReclaim<Runtime>,
 );
@@ -144,8 +179,9 @@ pub type Block = generic::Block<Header, UncheckedExtrinsic>;
 pub type SignedBlock = generic::SignedBlock<Block>;
 pub type BlockId = generic::BlockId<Block>;
 
+/// Runtime upgrade migrations, run once by `Executive`.
 #[allow(unused_parens)]
-type Migrations = ();
+type Migrations = (pallet_escrow::migrations::v1::MigrateV0ToV1<Runtime>,);
 
 pub type Executive = frame_executive::Executive<
     Runtime,
@@ -157,8 +193,100 @@ pub type Executive = frame_executive::Executive<
 >;
 
 // ----------------------------- PoBA pallet Config ---------------------------
+/// Matches allowed in one PoBA proposal (also the `PobaApi` type parameter).
+pub type PoBAMaxMatches = frame_support::traits::ConstU32<256>;
+
 impl pallet_poba::Config for Runtime {
     type RuntimeEvent = RuntimeEvent;
+    type Currency = Balances;
+    type ProposalBond = frame_support::traits::ConstU128<{ 10 * MILLI_UNIT }>;
+    type MaxSlotLag = frame_support::traits::ConstU64<10>;
+    type MaxMatchesPerProposal = PoBAMaxMatches;
+    type MaxProposersPerSlot = frame_support::traits::ConstU32<16>;
+    type WinnerReward = frame_support::traits::ConstU128<{ 100 * MILLI_UNIT }>;
+    type RewardSource = PoBARewardPot;
+    type AdminOrigin = frame_system::EnsureRoot<AccountId>;
+    // Single-phase finalize until the backend calls `propose_finalization`.
+    type ChallengeWindowBlocks = frame_support::traits::ConstU32<0>;
+    // Root, or any authorized proposer (the backend's finalizer role).
+    type FinalizeOrigin = frame_support::traits::EitherOfDiverse<
+        frame_system::EnsureRoot<AccountId>,
+        pallet_poba::EnsureAuthorizedProposer<Runtime>,
+    >;
+    type MinProposalAge = frame_support::traits::ConstU32<1>;
+    type RetainFinalizedSlots = frame_support::traits::ConstU64<1_000>;
+    type AllowEmptySlots = frame_support::traits::ConstBool<true>;
+    type OnSlotFinalized = EscrowOnSlotFinalized;
+    // Drop stale matches rather than losing the whole proposal.
+    type FilterMatchedRequests = frame_support::traits::ConstBool<true>;
+    type MaxSubmissionsPerSlot = frame_support::traits::ConstU32<8>;
+    type MinImprovementDelta = frame_support::traits::ConstI64<1>;
+    // Bids live off-chain for now; the worker already clamps prices.
+    type PriceBands = ();
+    // Commit-reveal off until the backend sends commit/reveal pairs.
+    type CommitWindowBlocks = frame_support::traits::ConstU32<0>;
+    type RevealWindowBlocks = frame_support::traits::ConstU32<0>;
+    // One slot per block time; allow for inclusion delay and finalize lag.
+    type SlotTolerance = frame_support::traits::ConstU64<10>;
+    type Time = Timestamp;
+    type SlotDurationMs = frame_support::traits::ConstU64<MILLI_SECS_PER_BLOCK>;
+    type BlockTimeMs = frame_support::traits::ConstU64<MILLI_SECS_PER_BLOCK>;
+    type MinFinalizeScore = frame_support::traits::ConstI64<0>;
+    type MinFinalizeMatches = frame_support::traits::ConstU32<1>;
+    type AuthorityId = pallet_poba::crypto::AuthorityId;
+    type MaxAuthorities = frame_support::traits::ConstU32<32>;
+    // Unsigned proposals pay no fee to rank by; give them a fixed high priority.
+    type UnsignedPriority = frame_support::traits::ConstU64<{ u64::MAX / 2 }>;
+    type MaxSnapshotRequests = frame_support::traits::ConstU32<1_024>;
+    type MaxUnmatched = frame_support::traits::ConstU32<256>;
+    // Worker partial scores start at POBA_BASE_SCORE (1e6 by default).
+    type MaxPartialScore = frame_support::traits::ConstI64<1_000_000_000>;
+    // Off while the worker finalizes the current slot (POBA_FINALIZE_LAG_SLOTS=0);
+    // the lag must reach the window before this can be raised.
+    type SubmissionWindowSlots = frame_support::traits::ConstU64<0>;
+    // Off until the backend reclaims winner bonds once the window is over.
+    type ReportWindowBlocks = frame_support::traits::ConstU32<0>;
+    type SlashFraction = PoBASlashFraction;
+    type ReporterShare = PoBAReporterShare;
+}
+
+frame_support::parameter_types! {
+    /// Half of a proven-invalid winner's bond is slashed, half of that
+    /// going to the reporter.
+    pub const PoBASlashFraction: sp_runtime::Perbill = sp_runtime::Perbill::from_percent(50);
+    pub const PoBAReporterShare: sp_runtime::Perbill = sp_runtime::Perbill::from_percent(50);
+}
+
+/// Keyless account the PoBA winner rewards are paid from (funded at genesis).
+pub struct PoBARewardPot;
+impl Get<AccountId> for PoBARewardPot {
+    fn get() -> AccountId {
+        use sp_runtime::traits::AccountIdConversion;
+        frame_support::PalletId(*b"poba/pot").into_account_truncating()
+    }
+}
+
+/// Opens an escrow for every match of a finalized PoBA slot and flags them
+/// if the finalization is revoked.
+pub struct EscrowOnSlotFinalized;
+
+impl pallet_poba::OnSlotFinalized<AccountId> for EscrowOnSlotFinalized {
+    fn on_slot_finalized(slot: u64, matches: &[pallet_poba::Match]) {
+        for m in matches {
+            Escrow::create_for_match(
+                slot,
+                m.request_uuid,
+                m.offer_uuid,
+                m.agreed_price_cents.into(),
+            );
+        }
+    }
+
+    fn on_slot_revoked(slot: u64, matches: &[pallet_poba::Match]) {
+        for m in matches {
+            Escrow::flag_revoked_match(slot, m.request_uuid);
+        }
+    }
 }
 
 
@@ -174,4 +302,18 @@ impl pallet_escrow::Config for Runtime {
     type RuntimeEvent = RuntimeEvent;
     type Balance = Balance;
     type ConfirmationTimeoutBlocks = EscrowConfirmationTimeout;
+    // No on-chain bid markers to resolve driver/payer yet, so finalized
+    // matches are reported as skipped and the backend keeps calling
+    // `create_escrow` itself.
+    type MatchParties = ();
+    type MaxBatchSize = frame_support::traits::ConstU32<64>;
+    // Off until the backend collects the payer top-up at booking.
+    type EarlyBonusBps = frame_support::traits::ConstU32<0>;
+    type EarlyBonusMarginBlocks = frame_support::traits::ConstU32<{ 2 * HOURS }>;
+    type AdminOrigin = frame_system::EnsureRoot<AccountId>;
+    // `MatchParties` knows no market yet, so the check would reject every escrow.
+    type RequireMarketConsistency = frame_support::traits::ConstBool<false>;
+    type RuntimeCall = RuntimeCall;
+    type PalletsOrigin = OriginCaller;
+    type Scheduler = Scheduler;
 }
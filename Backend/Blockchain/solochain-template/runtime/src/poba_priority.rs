@@ -0,0 +1,121 @@
+//runtime/src/poba_priority.rs
+
+//! Deadline-aware pool priority for PoBA calls.
+//!
+//! `PrioritizePobaDeadline` adds `pallet_poba::Pallet::deadline_validity` to
+//! the validity of signed `submit_proposal` / `finalize_slot` calls from
+//! authorized proposers, so a proposal sent in the last block of its slot is
+//! not stuck behind ordinary transfers, and a submission leaves the pool once
+//! its slot closes. Other transactions are unaffected.
+
+use codec::{Decode, DecodeWithMemTracking, Encode};
+use frame_support::weights::Weight;
+use scale_info::TypeInfo;
+use sp_runtime::{
+    traits::{
+        AsSystemOriginSigner, DispatchInfoOf, Implication, TransactionExtension, ValidateResult,
+    },
+    transaction_validity::{TransactionSource, TransactionValidityError, ValidTransaction},
+};
+
+use crate::{Runtime, RuntimeCall, RuntimeOrigin};
+
+/// Raises the priority of PoBA calls as their slot nears closing.
+#[derive(Clone, Eq, PartialEq, Default, Encode, Decode, DecodeWithMemTracking, TypeInfo, Debug)]
+pub struct PrioritizePobaDeadline;
+
+impl TransactionExtension<RuntimeCall> for PrioritizePobaDeadline {
+    const IDENTIFIER: &'static str = "PrioritizePobaDeadline";
+    type Implicit = ();
+    type Val = ();
+    type Pre = ();
+
+    fn weight(&self, _call: &RuntimeCall) -> Weight {
+        Weight::zero()
+    }
+
+    fn validate(
+        &self,
+        origin: RuntimeOrigin,
+        call: &RuntimeCall,
+        _info: &DispatchInfoOf<RuntimeCall>,
+        _len: usize,
+        _self_implicit: Self::Implicit,
+        _inherited_implication: &impl Implication,
+        _source: TransactionSource,
+    ) -> ValidateResult<Self::Val, RuntimeCall> {
+        let valid = match (call, origin.as_system_origin_signer()) {
+            (RuntimeCall::PoBA(call), Some(who)) =>
+                pallet_poba::Pallet::<Runtime>::deadline_validity(who, call).unwrap_or_default(),
+            _ => ValidTransaction::default(),
+        };
+        Ok((valid, (), origin))
+    }
+
+    fn prepare(
+        self,
+        _val: Self::Val,
+        _origin: &RuntimeOrigin,
+        _call: &RuntimeCall,
+        _info: &DispatchInfoOf<RuntimeCall>,
+        _len: usize,
+    ) -> Result<Self::Pre, TransactionValidityError> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        test_utils::{new_test_ext, signed},
+        Executive, Runtime, RuntimeCall, System, UncheckedExtrinsic, MILLI_SECS_PER_BLOCK, UNIT,
+    };
+    use frame_support::traits::Get;
+    use sp_keyring::Sr25519Keyring::{Alice, Bob, Charlie};
+    use sp_runtime::transaction_validity::{TransactionSource, ValidTransaction};
+
+    fn validate(xt: UncheckedExtrinsic) -> ValidTransaction {
+        Executive::validate_transaction(TransactionSource::External, xt, Default::default())
+            .unwrap()
+    }
+
+    fn submit(slot: u64) -> RuntimeCall {
+        RuntimeCall::PoBA(pallet_poba::Call::submit_proposal {
+            slot,
+            snapshot_hash: Default::default(),
+            total_score: 10,
+            matches: vec![([1; 16], [1; 16], 1_500, 10)],
+        })
+    }
+
+    #[test]
+    fn late_submissions_outrank_early_ones_and_transfers() {
+        new_test_ext(vec![(Alice, UNIT), (Bob, UNIT), (Charlie, UNIT)]).execute_with(|| {
+            pallet_poba::AuthorizedProposers::<Runtime>::insert(Alice.to_account_id(), ());
+            // Validation runs as if in the next block: 21, timestamped in slot 21.
+            System::set_block_number(20);
+            pallet_timestamp::Now::<Runtime>::put(21 * MILLI_SECS_PER_BLOCK);
+            let tolerance: u64 = <Runtime as pallet_poba::Config>::SlotTolerance::get();
+
+            let early = validate(signed(Alice, submit(21)));
+            let late = validate(signed(Alice, submit(21 - tolerance)));
+            let transfer = validate(signed(
+                Bob,
+                RuntimeCall::Balances(pallet_balances::Call::transfer_keep_alive {
+                    dest: Charlie.to_account_id().into(),
+                    value: UNIT / 10,
+                }),
+            ));
+            // Same call, but not from an authorized proposer: fee priority only.
+            let outsider = validate(signed(Bob, submit(21 - tolerance)));
+
+            assert!(late.priority > early.priority);
+            assert!(early.priority > transfer.priority);
+            assert!(early.priority > outsider.priority);
+
+            // Submissions leave the pool once their slot closes.
+            assert_eq!(early.longevity, tolerance + 1);
+            assert_eq!(late.longevity, 1);
+        });
+    }
+}
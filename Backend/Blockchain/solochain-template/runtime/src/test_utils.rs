@@ -0,0 +1,79 @@
+//runtime/src/test_utils.rs
+
+//! Executive-level test helpers: genesis with funded dev accounts and
+//! extrinsics signed with the full `TxExtension`.
+
+use frame_support::{genesis_builder_helper::build_state, sp_io};
+use sp_keyring::Sr25519Keyring;
+use sp_runtime::{
+	generic::{Era, SignedPayload},
+	BuildStorage,
+};
+
+use crate::{
+	delivery_fees::SkipFeeForDeliveryCalls, poba_priority::PrioritizePobaDeadline, Balance,
+	Executive, Header, Runtime, RuntimeCall, RuntimeGenesisConfig, System, TxExtension,
+	UncheckedExtrinsic,
+};
+
+/// Genesis with `balances`, block 1 initialized.
+pub fn new_test_ext(balances: Vec<(Sr25519Keyring, Balance)>) -> sp_io::TestExternalities {
+	let mut t = frame_system::GenesisConfig::<Runtime>::default().build_storage().unwrap();
+	pallet_balances::GenesisConfig::<Runtime> {
+		balances: balances.into_iter().map(|(who, free)| (who.to_account_id(), free)).collect(),
+		..Default::default()
+	}
+	.assimilate_storage(&mut t)
+	.unwrap();
+	let mut ext = sp_io::TestExternalities::new(t);
+	ext.execute_with(initialize_block_one);
+	ext
+}
+
+/// Genesis of the preset `id` (as `get_preset` returns it), block 1 initialized.
+pub fn preset_test_ext(id: &str) -> sp_io::TestExternalities {
+	let preset = crate::genesis_config_presets::get_preset(&id.into()).expect("known preset");
+	let mut ext = sp_io::TestExternalities::default();
+	ext.execute_with(|| {
+		build_state::<RuntimeGenesisConfig>(preset).unwrap();
+		initialize_block_one();
+	});
+	ext
+}
+
+fn initialize_block_one() {
+	Executive::initialize_block(&Header::new(
+		1,
+		Default::default(),
+		Default::default(),
+		System::parent_hash(),
+		Default::default(),
+	));
+}
+
+/// `call` signed by `who` at its current nonce, immortal, without tip.
+pub fn signed(who: Sr25519Keyring, call: RuntimeCall) -> UncheckedExtrinsic {
+	let nonce = System::account_nonce(who.to_account_id());
+	let tx_ext: TxExtension = (
+		frame_system::CheckNonZeroSender::<Runtime>::new(),
+		frame_system::CheckSpecVersion::<Runtime>::new(),
+		frame_system::CheckTxVersion::<Runtime>::new(),
+		frame_system::CheckGenesis::<Runtime>::new(),
+		frame_system::CheckEra::<Runtime>::from(Era::Immortal),
+		frame_system::CheckNonce::<Runtime>::from(nonce),
+		frame_system::CheckWeight::<Runtime>::new(),
+		SkipFeeForDeliveryCalls::from_tip(0),
+		PrioritizePobaDeadline,
+		frame_metadata_hash_extension::CheckMetadataHash::<Runtime>::new(false),
+		frame_system::WeightReclaim::<Runtime>::new(),
+	);
+	let payload =
+		SignedPayload::<RuntimeCall, TxExtension>::new(call.clone(), tx_ext.clone()).unwrap();
+	let signature = payload.using_encoded(|p| who.sign(p));
+	UncheckedExtrinsic::new_signed(
+		call,
+		who.to_account_id().into(),
+		crate::Signature::Sr25519(signature),
+		tx_ext,
+	)
+}
@@ -13,12 +13,21 @@
 # - If POBA_ROLE=finalizer, the backend will finalize a slot as soon as it observes
 #   >= POBA_MIN_PROPOSALS successful submissions for that slot, or after a short timeout
 #   (POBA_FINALIZE_WAIT_MS). This gives "finalize immediately after both submit".
+#
+# Update (Market snapshot):
+# - With POBA_ROLE=finalizer, a background loop publishes the open market as the
+#   slot's on-chain MarketSnapshot (via Sudo) as soon as the slot opens, and keeps
+#   that market in memory. GET /poba/market/{slot} serves it with its hash.
+# - submit-proposal requires the snapshot_hash of the market the proposal was
+#   computed on; the pallet rejects it if it differs from the slot's snapshot.
+# - GET /poba/unmatched/{slot} lists the snapshot requests the finalized winner
+#   left unmatched (UnmatchedRequests), for notifying users or carrying them over.
 
 from fastapi import APIRouter, HTTPException, Depends, Body, Request, FastAPI
 from pydantic import BaseModel, Field, ConfigDict
 from typing import List, Tuple, Annotated, Iterable, Optional
 from uuid import UUID
-import os, logging, math
+import os, logging, math, json, hashlib
 from datetime import timezone
 import time
 from threading import Thread, Lock
@@ -123,6 +132,11 @@ def _min_proposals_required() -> int:
     return int(os.getenv("POBA_MIN_PROPOSALS", "2"))
 
 
+def _require_proposal_signature() -> bool:
+    # When set, submit-proposal rejects bodies without a proposer signature.
+    return os.getenv("POBA_REQUIRE_PROPOSAL_SIGNATURE", "0").lower() in {"1", "true", "yes"}
+
+
 def _finalize_wait_ms() -> int:
     # Total time we wait for enough proposals within the same slot before finalizing anyway.
     return int(os.getenv("POBA_FINALIZE_WAIT_MS", "1500"))
@@ -133,12 +147,20 @@ def _finalize_poll_ms() -> int:
     return int(os.getenv("POBA_FINALIZE_POLL_MS", "100"))
 
 
+def _finalize_empty_slots() -> bool:
+    # When the runtime allows empty slots, finalize_slot on a slot without proposals
+    # succeeds with SlotSkipped and advances LastFinalizedSlot.
+    return os.getenv("POBA_FINALIZE_EMPTY_SLOTS", "1").lower() in {"1", "true", "yes"}
+
+
 # ------------------------------ Barrier-based finalize state ------------------------------
 
 _finalize_lock = Lock()
 _slot_submit_counts: dict[int, int] = {}
 _slot_first_seen_ts: dict[int, float] = {}
 _slot_finalize_started: set[int] = set()
+# slot -> {(proposer_id, idempotency_key): extrinsic hash} of successful submits.
+_slot_submitted_keys: dict[int, dict[tuple[Optional[str], str], str]] = {}
 
 
 def _record_successful_submit(slot: int) -> int:
@@ -150,6 +172,20 @@ def _record_successful_submit(slot: int) -> int:
         return int(_slot_submit_counts[slot])
 
 
+def _record_submitted_key(slot: int, proposer_id: Optional[str], key: Optional[str], tx_hash: str):
+    if not key:
+        return
+    with _finalize_lock:
+        _slot_submitted_keys.setdefault(slot, {})[(proposer_id, key)] = tx_hash
+
+
+def _submitted_key_hash(slot: int, proposer_id: Optional[str], key: Optional[str]) -> Optional[str]:
+    if not key:
+        return None
+    with _finalize_lock:
+        return _slot_submitted_keys.get(slot, {}).get((proposer_id, key))
+
+
 def _get_submit_count(slot: int) -> int:
     with _finalize_lock:
         return int(_slot_submit_counts.get(slot, 0))
@@ -175,6 +211,7 @@ def _cleanup_slot_state(slot: int):
         _slot_submit_counts.pop(slot, None)
         _slot_first_seen_ts.pop(slot, None)
         _slot_finalize_started.discard(slot)
+        _slot_submitted_keys.pop(slot, None)
 
 
 def _maybe_finalize_slot_barrier(slot: int):
@@ -185,7 +222,8 @@ def _maybe_finalize_slot_barrier(slot: int):
       - Then call finalize_slot(slot) immediately
 
     Notes:
-      - We still rely on chain guard inside _finalize_slot_impl (BestProposal exists with matches)
+      - Empty slots are finalized as SlotSkipped unless POBA_FINALIZE_EMPTY_SLOTS=0
+        (then _finalize_slot_impl skips slots without a BestProposal locally)
       - DB apply is done by the background slot listener (LastFinalizedSlot polling)
     """
     if _poba_role() != "finalizer":
@@ -246,6 +284,8 @@ def _maybe_finalize_slot_barrier(slot: int):
 # ------------------------------ Types & utils ------------------------------
 
 Hex32 = Annotated[str, Field(min_length=32, max_length=32, pattern=r"^[0-9a-fA-F]{32}$")]
+# 0x-prefixed 32-byte hash.
+Hash0x = Annotated[str, Field(pattern=r"^0x[0-9a-fA-F]{64}$")]
 
 
 def uuid_to_16_hex(u: UUID) -> str:
@@ -364,7 +404,8 @@ class MarketRequest(BaseModel):
     to_lat: int
     to_lon: int
     max_price_cents: int
-    kind: int
+    # 0 = package, 1 = passenger; the worker drops anything else.
+    kind: Annotated[int, Field(ge=0, le=1)]
     window_start: int
     window_end: int
 
@@ -379,7 +420,10 @@ class MarketOffer(BaseModel):
     to_lon: int
     window_start: int
     window_end: int
-    types_mask: int
+    # Bits of compute_types_mask only (package=1, passenger=2).
+    types_mask: Annotated[int, Field(ge=0, le=3)]
+    # How many requests the offer can serve in one assignment.
+    capacity: int = 1
 
 
 class MatchItem(BaseModel):
@@ -396,6 +440,50 @@ class SubmitProposalBody(BaseModel):
     # If client does not send total_score, default to 0 (we do not fabricate any other value).
     total_score: int = 0
     matches: List[MatchItem]
+    # Hash of the market the proposal was computed on, as served by
+    # GET /poba/market/{slot}. Required: the pallet checks it against the
+    # slot's MarketSnapshot.
+    snapshot_hash: Hash0x
+    # Deterministic key of (slot, matches) from the worker. A retried call with a key
+    # already submitted for this slot and proposer is answered without a new extrinsic.
+    idempotency_key: Optional[str] = None
+    # 0x-hex sr25519 signature over _proposal_payload(body) by the proposer's key.
+    signature: Optional[str] = None
+
+
+def _proposal_payload(body: SubmitProposalBody) -> str:
+    # Must match the worker's proposal_payload: slot, then the sorted
+    # "request:offer:price:score" items, joined by "|".
+    items = sorted(
+        f"{m.request_uuid}:{m.offer_uuid}:{m.agreed_price_cents}:{m.partial_score}"
+        for m in body.matches
+    )
+    return f"{body.slot}|" + "|".join(items)
+
+
+def _verify_proposal_signature(body: SubmitProposalBody, signer: Keypair, proposer_id: Optional[str]):
+    """
+    Check that the proposal was signed by the key behind proposer_id, so a node
+    cannot submit under another proposer's identity.
+    """
+    if not body.signature:
+        if _require_proposal_signature():
+            raise HTTPException(status_code=401, detail={
+                "code": "missing_proposal_signature",
+                "proposer_id": proposer_id,
+                "hint": "Set POBA_PROPOSER_SURI on the worker to the proposer's key",
+            })
+        return
+    try:
+        valid = signer.verify(_proposal_payload(body), body.signature)
+    except Exception:
+        valid = False
+    if not valid:
+        raise HTTPException(status_code=401, detail={
+            "code": "bad_proposal_signature",
+            "proposer_id": proposer_id,
+            "hint": "The signature does not match this proposal and proposer's key",
+        })
 
 
 class FinalizeBody(BaseModel):
@@ -724,6 +812,184 @@ def _submit_with_wait(substrate, extrinsic, *, label: str):
             return substrate.submit_extrinsic(extrinsic, wait_for_inclusion=False)
 
 
+def _admin_signer() -> Keypair:
+    """
+    Signer for PoBA AdminOrigin calls, sent through Sudo.sudo.
+    SUBSTRATE_ADMIN_URI (e.g. //Alice on dev chains), else the default signer.
+    """
+    uri = os.getenv("SUBSTRATE_ADMIN_URI")
+    if uri:
+        return Keypair.create_from_uri(uri)
+    return get_signer()
+
+
+def _market_snapshot_hash(requests: List[MarketRequest], offers: List[MarketOffer]) -> str:
+    """blake2b-256 of the market as canonical JSON (sorted keys, no spaces)."""
+    payload = json.dumps(
+        {
+            "requests": [r.model_dump() for r in requests],
+            "offers": [o.model_dump() for o in offers],
+        },
+        sort_keys=True,
+        separators=(",", ":"),
+    )
+    return "0x" + hashlib.blake2b(payload.encode(), digest_size=32).hexdigest()
+
+
+# slot -> {"snapshot_hash", "requests", "offers"}: the market published as the
+# slot's MarketSnapshot by this backend when the slot opened.
+_market_lock = Lock()
+_slot_markets: dict[int, dict] = {}
+# Slot markets kept in memory; older ones are dropped.
+_SLOT_MARKETS_KEPT = 32
+
+
+def _current_slot(substrate) -> int:
+    """The PoBA slot at the best block, as the runtime derives it (PobaApi::current_slot)."""
+    res = substrate.rpc_request("state_call", ["PobaApi_current_slot", "0x"])
+    return int.from_bytes(bytes.fromhex(res["result"][2:]), "little")
+
+
+def _open_slot_market(substrate, slot: int) -> Optional[dict]:
+    """
+    Publish the current open market as the MarketSnapshot of `slot` and keep it
+    for GET /poba/market/{slot}. Returns the kept market, or None when the slot
+    already has a snapshot this backend did not publish (its market is unknown).
+    """
+    with _market_lock:
+        cached = _slot_markets.get(slot)
+    if cached is not None:
+        return cached
+
+    if substrate.query(_pallet_name(), "MarketSnapshot", [slot]).value:
+        log.warning("market snapshot for slot %s was published elsewhere; not serving it", slot)
+        return None
+
+    db = SessionLocal()
+    try:
+        requests = requests_open(db)
+        offers = _offers_active_impl(db)
+    finally:
+        db.close()
+    snapshot_hash = _market_snapshot_hash(requests, offers)
+
+    inner = substrate.compose_call(
+        call_module=_pallet_name(),
+        call_function="set_market_snapshot",
+        call_params={
+            "slot": slot,
+            "snapshot_hash": snapshot_hash,
+            "request_count": len(requests),
+            "offer_count": len(offers),
+            # Lets finalization record which requests the winner left unmatched.
+            "request_uuids": [r.uuid_16 for r in requests],
+        },
+    )
+    call = substrate.compose_call(call_module="Sudo", call_function="sudo", call_params={"call": inner})
+    extrinsic = substrate.create_signed_extrinsic(call=call, keypair=_admin_signer())
+    try:
+        _submit_with_wait(substrate, extrinsic, label="set_market_snapshot")
+    except SubstrateRequestException as e:
+        log.warning("set_market_snapshot for slot %s failed: %s", slot, e)
+
+    stored = substrate.query(_pallet_name(), "MarketSnapshot", [slot]).value
+    if not stored or stored["snapshot_hash"] != snapshot_hash:
+        raise HTTPException(status_code=502, detail={
+            "code": "market_snapshot_missing",
+            "slot": slot,
+            "hint": "set_market_snapshot needs AdminOrigin; check SUBSTRATE_ADMIN_URI is the sudo key",
+        })
+
+    market = {
+        "snapshot_hash": snapshot_hash,
+        "requests": [r.model_dump() for r in requests],
+        "offers": [o.model_dump() for o in offers],
+    }
+    with _market_lock:
+        _slot_markets[slot] = market
+        for old in [k for k in _slot_markets if k <= slot - _SLOT_MARKETS_KEPT]:
+            _slot_markets.pop(old, None)
+    log.info("market snapshot published: slot=%s hash=%s", slot, snapshot_hash)
+    return market
+
+
+def _snapshot_publisher_loop():
+    """
+    Background loop (POBA_ROLE=finalizer): publish the market snapshot of each
+    slot as soon as PobaApi::current_slot reaches it, before any proposer
+    computes on that slot.
+    """
+    poll_interval = _slot_poll_interval_sec()
+    last_opened: Optional[int] = None
+
+    log.warning("PoBA snapshot publisher starting (poll interval = %ss)", poll_interval)
+
+    while True:
+        try:
+            substrate = get_substrate()
+            while True:
+                try:
+                    slot = _current_slot(substrate)
+                    if last_opened is None or slot > last_opened:
+                        _open_slot_market(substrate, slot)
+                        last_opened = slot
+                except Exception as inner:
+                    log.warning("snapshot_publisher inner error: %s", inner)
+                time.sleep(poll_interval)
+        except Exception as outer:
+            log.error("snapshot_publisher outer error (reconnecting in 5s): %s", outer)
+            time.sleep(5)
+
+
+@router.get("/market/{slot}")
+def slot_market(slot: int):
+    """
+    The market published as the MarketSnapshot of `slot` at slot open, with its
+    hash. Proposals for the slot must be computed on it and carry that hash.
+    409 until the slot has opened on this backend.
+    """
+    with _market_lock:
+        market = _slot_markets.get(slot)
+    if market is None:
+        raise HTTPException(status_code=409, detail={
+            "code": "market_snapshot_pending",
+            "slot": slot,
+            "hint": "Snapshots are published at slot open by the POBA_ROLE=finalizer backend",
+        })
+    return {"slot": slot, **market}
+
+
+@router.post("/market-snapshot")
+def market_snapshot(body: FinalizeBody):
+    """
+    Publish the current market as the snapshot of `slot` now, if the slot opener
+    has not yet (idempotent). Proposals for the slot must reference the returned hash.
+    """
+    substrate = get_substrate()
+    market = _open_slot_market(substrate, int(body.slot))
+    if market is None:
+        raise HTTPException(status_code=409, detail={
+            "code": "market_snapshot_unavailable",
+            "slot": body.slot,
+            "hint": "The slot's snapshot was published by another backend",
+        })
+    return {"ok": True, "slot": body.slot, "snapshot_hash": market["snapshot_hash"]}
+
+
+@router.get("/unmatched/{slot}")
+def unmatched_requests(slot: int):
+    """
+    Snapshot requests the finalized winner of `slot` left unmatched (capped at
+    the runtime's MaxUnmatched). Empty until the slot is finalized.
+    """
+    substrate = get_substrate()
+    try:
+        uuids = substrate.query(_pallet_name(), "UnmatchedRequests", [slot]).value or []
+    except SubstrateRequestException as e:
+        raise HTTPException(status_code=502, detail={"code": "rpc_failed", "error": str(e)})
+    return {"slot": slot, "count": len(uuids), "request_uuids": uuids}
+
+
 @router.post("/submit-proposal")
 def submit_proposal(body: SubmitProposalBody, request: Request):
     """
@@ -739,7 +1005,9 @@ def submit_proposal(body: SubmitProposalBody, request: Request):
     proposer_id (optional, via query param):
       - /poba/submit-proposal?proposer_id=alice
       - /poba/submit-proposal?proposer_id=bob
-    Used to pick a per-proposer signer on chain.
+    Used to pick a per-proposer signer on chain. If the body carries a signature,
+    it must verify against that signer's key (401 bad_proposal_signature otherwise);
+    POBA_REQUIRE_PROPOSAL_SIGNATURE=1 also rejects unsigned bodies.
 
     Barrier finalize:
       - If POBA_ROLE=finalizer, after successful submit we record a count per slot.
@@ -760,8 +1028,21 @@ def submit_proposal(body: SubmitProposalBody, request: Request):
             "matches": [],
         }
 
-    substrate = get_substrate()
     signer = get_signer(proposer_id=proposer_id)
+    _verify_proposal_signature(body, signer, proposer_id)
+
+    # Retry of a call that already went through (e.g. the worker timed out waiting).
+    previous_hash = _submitted_key_hash(int(body.slot), proposer_id, body.idempotency_key)
+    if previous_hash is not None:
+        log.info(
+            "submit_proposal: duplicate idempotency_key=%s slot=%s proposer_id=%s, not resubmitting",
+            body.idempotency_key,
+            body.slot,
+            proposer_id,
+        )
+        return {"ok": True, "submitted": False, "duplicate": True, "hash": previous_hash}
+
+    substrate = get_substrate()
 
     # Quick health probe (helps with nicer error if WS is down)
     try:
@@ -795,6 +1076,7 @@ def submit_proposal(body: SubmitProposalBody, request: Request):
             call_function=_call_submit(),
             call_params={
                 "slot": int(body.slot),
+                "snapshot_hash": body.snapshot_hash,
                 "total_score": total_score,
                 matches_param_name: match_tuples,
             },
@@ -837,6 +1119,8 @@ def submit_proposal(body: SubmitProposalBody, request: Request):
                         "receipt": str(getattr(receipt, "error_message", "")),
                     })
 
+                _record_submitted_key(int(body.slot), proposer_id, body.idempotency_key, receipt.extrinsic_hash)
+
                 # Record successful submit for this slot and maybe trigger finalize (barrier)
                 cnt = _record_successful_submit(int(body.slot))
                 log.warning("barrier_finalize: observed submit slot=%s count_now=%s proposer_id=%s", body.slot, cnt, proposer_id)
@@ -900,7 +1184,7 @@ def _finalize_slot_impl(body: FinalizeBody, proposer_id: Optional[str] = None) -
         log.warning("finalize_slot: failed to query BestProposal for slot %s: %s", body.slot, e)
         bp = None
 
-    if not bp or not (bp.get("matches") or []):
+    if (not bp or not (bp.get("matches") or [])) and not _finalize_empty_slots():
         return {"ok": False, "skipped": True, "reason": "no_best_proposal_for_slot", "slot": body.slot}
 
     try:
@@ -1617,5 +1901,7 @@ def install_poba_slot_listener(app: FastAPI):
 
     t = Thread(target=_slot_listener_loop, daemon=True)
     t.start()
+    if _poba_role() == "finalizer":
+        Thread(target=_snapshot_publisher_loop, daemon=True).start()
     _listener_started = True
     log.warning("PoBA slot listener thread started (eager start)")
@@ -26,6 +26,33 @@ mod escrow {
         pub pair_score: u128,
     }
 
+    /// Errors returned by escrow messages that move funds.
+    #[derive(Encode, Decode, Clone, Copy, Debug, PartialEq, Eq)]
+    #[cfg_attr(feature = "std", derive(TypeInfo))]
+    pub enum Error {
+        /// The escrow is already being processed by an outer call (reentrancy).
+        EscrowLocked,
+        /// The balance transfer out of the contract failed; the call reverts,
+        /// leaving the escrow as it was, so it can be retried.
+        TransferFailed,
+        /// `open_and_deposit` was called without any value.
+        ZeroAmountNotAllowed,
+        /// The deposit is below the contract's `min_deposit`.
+        BelowMinDeposit,
+    }
+
+    /// Result type for fallible escrow messages.
+    pub type Result<T> = core::result::Result<T, Error>;
+
+    /// The sender moved an open escrow to another driver.
+    #[ink(event)]
+    pub struct DriverReassigned {
+        #[ink(topic)]
+        assignment_id: u128,
+        old_driver: AccountId,
+        new_driver: AccountId,
+    }
+
     // -------------------------------------------------------------------------
     // Simple status codes for escrow lifecycle (stored as u8)
     // -------------------------------------------------------------------------
@@ -36,6 +63,9 @@ mod escrow {
     const STATUS_CANCELLED: u8 = 3;
     const STATUS_REFUNDED: u8 = 4; // reserved for future use
 
+    /// Upper bound for `keeper_reward_bps` (10% of the released amount).
+    const MAX_KEEPER_REWARD_BPS: u16 = 1_000;
+
     /// Defines the storage of your contract.
     /// This is the original minimal storage created by `cargo contract new`,
     /// extended with escrow mappings.
@@ -65,6 +95,29 @@ mod escrow {
         /// Mapping from assignment_id (u128) to timeout in ms
         /// (how long after Delivered we allow auto-release).
         timeouts_ms: Mapping<u128, u64>,
+
+        /// Number of escrows indexed under each non-terminal status
+        /// (OPEN / DELIVERED). Used for enumeration by keepers.
+        status_lens: Mapping<u8, u32>,
+
+        /// (status, position) to assignment_id: the index of each
+        /// non-terminal status, positions `0..status_lens[status]`.
+        status_ids: Mapping<(u8, u32), u128>,
+
+        /// Mapping from assignment_id (u128) to its position in the index of
+        /// its status, while that status is non-terminal.
+        status_positions: Mapping<u128, u32>,
+
+        /// Per-assignment reentrancy guard: `true` while a message that
+        /// transfers funds for this assignment is executing.
+        locked: Mapping<u128, bool>,
+
+        /// Smallest deposit `open_and_deposit` accepts (fixed at instantiation).
+        min_deposit: Balance,
+
+        /// Share (basis points) of a timeout release paid to the caller who
+        /// triggers it, as gas compensation for keepers.
+        keeper_reward_bps: u16,
     }
 
     impl Escrow {
@@ -73,9 +126,33 @@ mod escrow {
         // ---------------------------------------------------------------------
 
         /// Constructor that initializes the `bool` value to the given `init_value`.
-        /// Also initializes all escrow mappings as empty.
+        /// Also initializes all escrow mappings as empty. Any non-zero deposit
+        /// is accepted.
         #[ink(constructor)]
         pub fn new(init_value: bool) -> Self {
+            Self::new_with_min_deposit(init_value, 0)
+        }
+
+        /// Like `new`, but `open_and_deposit` rejects deposits below `min_deposit`.
+        #[ink(constructor)]
+        pub fn new_with_min_deposit(init_value: bool, min_deposit: Balance) -> Self {
+            Self::new_with_keeper_reward(init_value, min_deposit, 0)
+        }
+
+        /// Like `new_with_min_deposit`, also paying `keeper_reward_bps` of each
+        /// timeout release to its caller.
+        ///
+        /// Panics if `keeper_reward_bps` exceeds `MAX_KEEPER_REWARD_BPS`.
+        #[ink(constructor)]
+        pub fn new_with_keeper_reward(
+            init_value: bool,
+            min_deposit: Balance,
+            keeper_reward_bps: u16,
+        ) -> Self {
+            assert!(
+                keeper_reward_bps <= MAX_KEEPER_REWARD_BPS,
+                "keeper_reward_bps must not exceed MAX_KEEPER_REWARD_BPS"
+            );
             Self {
                 value: init_value,
                 senders: Mapping::default(),
@@ -85,6 +162,12 @@ mod escrow {
                 created_ats: Mapping::default(),
                 delivered_ats: Mapping::default(),
                 timeouts_ms: Mapping::default(),
+                status_lens: Mapping::default(),
+                status_ids: Mapping::default(),
+                status_positions: Mapping::default(),
+                locked: Mapping::default(),
+                min_deposit,
+                keeper_reward_bps,
             }
         }
 
@@ -156,6 +239,95 @@ mod escrow {
             (sender, driver, amount, status, delivered_at, timeout_ms)
         }
 
+        /// Returns true if the status is terminal (no further transitions).
+        fn is_terminal(status: u8) -> bool {
+            status == STATUS_COMPLETED
+                || status == STATUS_CANCELLED
+                || status == STATUS_REFUNDED
+        }
+
+        /// Helper: write a new status and keep the status index in sync.
+        ///
+        /// Terminal statuses remove the id from the index.
+        fn set_status(&mut self, assignment_id: u128, status: u8) {
+            if let Some(prev) = self.statuses.get(assignment_id) {
+                self.unindex(assignment_id, prev);
+            }
+            self.statuses.insert(assignment_id, &status);
+            if !Self::is_terminal(status) {
+                self.index(assignment_id, status);
+            }
+        }
+
+        /// Helper: append `assignment_id` to the index of `status`.
+        fn index(&mut self, assignment_id: u128, status: u8) {
+            let len = self.status_lens.get(status).unwrap_or(0);
+            self.status_ids.insert((status, len), &assignment_id);
+            self.status_positions.insert(assignment_id, &len);
+            self.status_lens.insert(status, &len.saturating_add(1));
+        }
+
+        /// Helper: drop `assignment_id` from the index of `status`, moving the
+        /// last entry into its place. No-op for ids that are not indexed.
+        fn unindex(&mut self, assignment_id: u128, status: u8) {
+            let Some(pos) = self.status_positions.take(assignment_id) else {
+                return;
+            };
+            let last = self.status_lens.get(status).unwrap_or(1).saturating_sub(1);
+            if pos != last {
+                if let Some(moved) = self.status_ids.get((status, last)) {
+                    self.status_ids.insert((status, pos), &moved);
+                    self.status_positions.insert(moved, &pos);
+                }
+            }
+            self.status_ids.remove((status, last));
+            self.status_lens.insert(status, &last);
+        }
+
+        /// Helper: acquire the per-assignment reentrancy lock.
+        fn lock(&mut self, assignment_id: u128) -> Result<()> {
+            if self.locked.get(assignment_id).unwrap_or(false) {
+                return Err(Error::EscrowLocked);
+            }
+            self.locked.insert(assignment_id, &true);
+            Ok(())
+        }
+
+        /// Helper: release the per-assignment reentrancy lock.
+        fn unlock(&mut self, assignment_id: u128) {
+            self.locked.remove(assignment_id);
+        }
+
+        /// Helper: move the escrow to a terminal status and pay out `amount`,
+        /// less the `keeper` cut (paid to the keeper account) if any.
+        ///
+        /// Follows checks-effects-interactions: the terminal status is written
+        /// *before* the transfer. If a transfer fails, `Error::TransferFailed`
+        /// is returned; the message returning it reverts all its storage
+        /// writes and any payout that already went through.
+        fn settle(
+            &mut self,
+            assignment_id: u128,
+            terminal_status: u8,
+            to: AccountId,
+            amount: Balance,
+            keeper: Option<(AccountId, Balance)>,
+        ) -> Result<()> {
+            // Effects
+            self.set_status(assignment_id, terminal_status);
+
+            // Interaction
+            let (keeper, cut) = keeper.unwrap_or((to, 0));
+            self.env()
+                .transfer(to, amount.saturating_sub(cut))
+                .map_err(|_| Error::TransferFailed)?;
+            if cut > 0 {
+                self.env().transfer(keeper, cut).map_err(|_| Error::TransferFailed)?;
+            }
+
+            Ok(())
+        }
+
         // ---------------------------------------------------------------------
         // Escrow messages (payment & timeout logic)
         // ---------------------------------------------------------------------
@@ -174,13 +346,17 @@ mod escrow {
         ///
         /// Fails (panics) if:
         /// - There is already an escrow for this assignment_id.
+        ///
+        /// Returns (reverting the deposit):
+        /// - `Error::ZeroAmountNotAllowed` if nothing was transferred.
+        /// - `Error::BelowMinDeposit` if less than `min_deposit` was transferred.
         #[ink(message, payable)]
         pub fn open_and_deposit(
             &mut self,
             assignment_id: u128,
             driver: AccountId,
             timeout_ms: u64,
-        ) {
+        ) -> Result<()> {
             // Do not override an existing escrow.
             let existing_sender: Option<AccountId> = self.senders.get(assignment_id);
             assert!(
@@ -190,16 +366,23 @@ mod escrow {
 
             let sender = self.env().caller();
             let amount = self.env().transferred_value();
+            if amount == 0 {
+                return Err(Error::ZeroAmountNotAllowed);
+            }
+            if amount < self.min_deposit {
+                return Err(Error::BelowMinDeposit);
+            }
             let now = self.now();
 
             // Store all fields in their respective mappings.
             self.senders.insert(assignment_id, &sender);
             self.drivers.insert(assignment_id, &driver);
             self.amounts.insert(assignment_id, &amount);
-            self.statuses.insert(assignment_id, &STATUS_OPEN);
+            self.set_status(assignment_id, STATUS_OPEN);
             self.created_ats.insert(assignment_id, &now);
             self.delivered_ats.insert(assignment_id, &0);
             self.timeouts_ms.insert(assignment_id, &timeout_ms);
+            Ok(())
         }
 
         /// Driver marks the assignment as delivered / completed.
@@ -229,7 +412,7 @@ mod escrow {
             );
 
             let now = self.now();
-            self.statuses.insert(assignment_id, &STATUS_DELIVERED);
+            self.set_status(assignment_id, STATUS_DELIVERED);
             self.delivered_ats.insert(assignment_id, &now);
         }
 
@@ -241,10 +424,12 @@ mod escrow {
         /// - Escrow status must be Delivered.
         ///
         /// Effects:
-        /// - Transfers funds from the contract to the driver.
         /// - Status moves to Completed.
+        /// - Transfers funds from the contract to the driver.
+        ///
+        /// Returns `Error::TransferFailed` (status unchanged) if the payout fails.
         #[ink(message)]
-        pub fn receiver_confirm(&mut self, assignment_id: u128) {
+        pub fn receiver_confirm(&mut self, assignment_id: u128) -> Result<()> {
             let caller = self.env().caller();
 
             let (sender, driver, amount, status, _delivered_at, _timeout_ms) =
@@ -259,11 +444,10 @@ mod escrow {
                 "Escrow must be in DELIVERED status to confirm"
             );
 
-            // Transfer funds to the driver.
-            let transfer_result = self.env().transfer(driver, amount);
-            assert!(transfer_result.is_ok(), "Transfer to driver failed");
-
-            self.statuses.insert(assignment_id, &STATUS_COMPLETED);
+            self.lock(assignment_id)?;
+            let result = self.settle(assignment_id, STATUS_COMPLETED, driver, amount, None);
+            self.unlock(assignment_id);
+            result
         }
 
         /// Auto-release function for timeout handling.
@@ -275,10 +459,13 @@ mod escrow {
         /// - now >= delivered_at + timeout_ms.
         ///
         /// Effects:
-        /// - Transfers funds to the driver.
         /// - Status moves to Completed.
+        /// - Transfers `keeper_reward_bps` of the funds to the caller (unless
+        ///   the caller is the driver) and the rest to the driver.
+        ///
+        /// Returns `Error::TransferFailed` (status unchanged) if the payout fails.
         #[ink(message)]
-        pub fn auto_release_if_timeout(&mut self, assignment_id: u128) {
+        pub fn auto_release_if_timeout(&mut self, assignment_id: u128) -> Result<()> {
             let (_sender, driver, amount, status, delivered_at, timeout_ms) =
                 self.load_escrow(assignment_id);
 
@@ -295,10 +482,16 @@ mod escrow {
                 "Too early for auto-release, timeout not reached yet"
             );
 
-            let transfer_result = self.env().transfer(driver, amount);
-            assert!(transfer_result.is_ok(), "Transfer to driver failed");
-
-            self.statuses.insert(assignment_id, &STATUS_COMPLETED);
+            let caller = self.env().caller();
+            let keeper = (caller != driver && self.keeper_reward_bps > 0).then(|| {
+                let cut = amount.saturating_mul(self.keeper_reward_bps as Balance) / 10_000;
+                (caller, cut)
+            });
+
+            self.lock(assignment_id)?;
+            let result = self.settle(assignment_id, STATUS_COMPLETED, driver, amount, keeper);
+            self.unlock(assignment_id);
+            result
         }
 
         /// Sender cancels the assignment before the driver marks it as delivered.
@@ -308,10 +501,12 @@ mod escrow {
         /// - Status == Open.
         ///
         /// Effects:
-        /// - Refunds funds back to the sender.
         /// - Status moves to Cancelled.
+        /// - Refunds funds back to the sender.
+        ///
+        /// Returns `Error::TransferFailed` (status unchanged) if the refund fails.
         #[ink(message)]
-        pub fn cancel_before_delivered(&mut self, assignment_id: u128) {
+        pub fn cancel_before_delivered(&mut self, assignment_id: u128) -> Result<()> {
             let caller = self.env().caller();
 
             let (sender, _driver, amount, status, _delivered_at, _timeout_ms) =
@@ -326,10 +521,44 @@ mod escrow {
                 "Escrow must be in OPEN status to cancel"
             );
 
-            let transfer_result = self.env().transfer(sender, amount);
-            assert!(transfer_result.is_ok(), "Refund to sender failed");
+            self.lock(assignment_id)?;
+            let result = self.settle(assignment_id, STATUS_CANCELLED, sender, amount, None);
+            self.unlock(assignment_id);
+            result
+        }
+
+        /// Sender hands the assignment to another driver, e.g. because the
+        /// original driver became unavailable.
+        ///
+        /// Only the sender can call this.
+        /// Allowed only when:
+        /// - Status == Open (a delivered escrow keeps its driver).
+        ///
+        /// Effects:
+        /// - `new_driver` becomes the driver who can mark delivery and is paid.
+        /// - Emits `DriverReassigned`.
+        #[ink(message)]
+        pub fn reassign_driver(&mut self, assignment_id: u128, new_driver: AccountId) {
+            let caller = self.env().caller();
+
+            let (sender, old_driver, _amount, status, _delivered_at, _timeout_ms) =
+                self.load_escrow(assignment_id);
 
-            self.statuses.insert(assignment_id, &STATUS_CANCELLED);
+            assert!(
+                caller == sender,
+                "Only the sender (payer) can reassign the driver"
+            );
+            assert!(
+                status == STATUS_OPEN,
+                "Escrow must be in OPEN status to reassign the driver"
+            );
+
+            self.drivers.insert(assignment_id, &new_driver);
+            self.env().emit_event(DriverReassigned {
+                assignment_id,
+                old_driver,
+                new_driver,
+            });
         }
 
         /// Read-only helper to inspect the status of an escrow as a raw u8.
@@ -342,6 +571,28 @@ mod escrow {
             self.statuses.get(assignment_id)
         }
 
+        /// Batch form of `get_status`: each id paired with its status, in the
+        /// order given, so a keeper can read a page of escrows in one call.
+        /// Unknown ids map to `None`.
+        #[ink(message)]
+        pub fn get_statuses(&self, ids: Vec<u128>) -> Vec<(u128, Option<u8>)> {
+            ids.into_iter().map(|id| (id, self.statuses.get(id))).collect()
+        }
+
+        /// List the assignment ids of all *active* escrows with the given status.
+        ///
+        /// Only non-terminal escrows are indexed, so this is meant for
+        /// `STATUS_OPEN` / `STATUS_DELIVERED` (e.g. a keeper bot looking for
+        /// DELIVERED escrows past their timeout). Terminal statuses always
+        /// return an empty list. The order is not stable across status changes.
+        #[ink(message)]
+        pub fn ids_by_status(&self, status: u8) -> Vec<u128> {
+            let len = self.status_lens.get(status).unwrap_or(0);
+            (0..len)
+                .filter_map(|pos| self.status_ids.get((status, pos)))
+                .collect()
+        }
+
         // ---------------------------------------------------------------------
         // PoBA winner validation API (no storage changes, pure logic)
         // ---------------------------------------------------------------------
@@ -432,6 +683,270 @@ mod escrow {
             escrow.flip();
             assert_eq!(escrow.get(), true);
         }
+
+        // ---------------------------------------------------------------------
+        // Escrow helpers for tests
+        // ---------------------------------------------------------------------
+
+        type Env = ink::env::DefaultEnvironment;
+
+        fn accounts() -> ink::env::test::DefaultAccounts<Env> {
+            ink::env::test::default_accounts::<Env>()
+        }
+
+        fn set_caller(who: AccountId) {
+            ink::env::test::set_caller::<Env>(who);
+        }
+
+        /// Open an escrow as `sender` for `driver`, depositing `value`.
+        fn open(escrow: &mut Escrow, id: u128, sender: AccountId, driver: AccountId, value: Balance) {
+            set_caller(sender);
+            ink::env::test::set_value_transferred::<Env>(value);
+            assert_eq!(escrow.open_and_deposit(id, driver, 1_000), Ok(()));
+            ink::env::test::set_value_transferred::<Env>(0);
+        }
+
+        /// Give the contract account enough balance to pay out transfers.
+        fn fund_contract(amount: Balance) {
+            let contract = ink::env::test::callee::<Env>();
+            ink::env::test::set_account_balance::<Env>(contract, amount);
+        }
+
+        /// Enumerating active escrows across mixed statuses.
+        #[ink::test]
+        fn ids_by_status_enumerates_active_escrows() {
+            let acc = accounts();
+            let mut escrow = Escrow::default();
+            fund_contract(1_000_000);
+
+            open(&mut escrow, 1, acc.alice, acc.bob, 100);
+            open(&mut escrow, 2, acc.alice, acc.bob, 100);
+            open(&mut escrow, 3, acc.alice, acc.charlie, 100);
+
+            // 2 -> DELIVERED
+            set_caller(acc.bob);
+            escrow.driver_mark_delivered(2);
+
+            // 3 -> CANCELLED (terminal, pruned from the index)
+            set_caller(acc.alice);
+            assert_eq!(escrow.cancel_before_delivered(3), Ok(()));
+
+            assert_eq!(escrow.ids_by_status(STATUS_OPEN), vec![1]);
+            assert_eq!(escrow.ids_by_status(STATUS_DELIVERED), vec![2]);
+            assert!(escrow.ids_by_status(STATUS_CANCELLED).is_empty());
+            assert_eq!(escrow.get_status(3), Some(STATUS_CANCELLED));
+
+            // 2 -> COMPLETED (terminal, pruned from the index)
+            assert_eq!(escrow.receiver_confirm(2), Ok(()));
+            assert!(escrow.ids_by_status(STATUS_DELIVERED).is_empty());
+            assert!(escrow.ids_by_status(STATUS_COMPLETED).is_empty());
+            assert_eq!(escrow.ids_by_status(STATUS_OPEN), vec![1]);
+        }
+
+        /// Leaving a status moves the last indexed id into the freed place.
+        #[ink::test]
+        fn ids_by_status_fills_gaps_from_the_end() {
+            let acc = accounts();
+            let mut escrow = Escrow::default();
+
+            for id in 1..=3 {
+                open(&mut escrow, id, acc.alice, acc.bob, 100);
+            }
+            set_caller(acc.bob);
+            escrow.driver_mark_delivered(1);
+
+            assert_eq!(escrow.ids_by_status(STATUS_OPEN), vec![3, 2]);
+            assert_eq!(escrow.ids_by_status(STATUS_DELIVERED), vec![1]);
+            assert_eq!(escrow.status_positions.get(3), Some(0));
+            assert_eq!(escrow.status_ids.get((STATUS_OPEN, 2)), None);
+        }
+
+        fn balance_of(who: AccountId) -> Balance {
+            ink::env::test::get_account_balance::<Env>(who).unwrap_or_default()
+        }
+
+        /// A keeper triggering a timeout release earns its cut; the driver
+        /// gets the rest.
+        #[ink::test]
+        fn timeout_release_pays_keeper_reward() {
+            let acc = accounts();
+            let mut escrow = Escrow::new_with_keeper_reward(false, 0, 250);
+            fund_contract(1_000_000);
+
+            open(&mut escrow, 3, acc.alice, acc.bob, 10_000);
+            ink::env::test::set_block_timestamp::<Env>(1);
+            set_caller(acc.bob);
+            escrow.driver_mark_delivered(3);
+
+            let (driver_before, keeper_before) = (balance_of(acc.bob), balance_of(acc.charlie));
+            ink::env::test::set_block_timestamp::<Env>(1_001);
+            set_caller(acc.charlie);
+            assert_eq!(escrow.auto_release_if_timeout(3), Ok(()));
+
+            assert_eq!(escrow.get_status(3), Some(STATUS_COMPLETED));
+            assert_eq!(balance_of(acc.charlie) - keeper_before, 250);
+            assert_eq!(balance_of(acc.bob) - driver_before, 9_750);
+        }
+
+        /// A driver releasing their own escrow gets the full amount.
+        #[ink::test]
+        fn driver_releasing_own_escrow_gets_everything() {
+            let acc = accounts();
+            let mut escrow = Escrow::new_with_keeper_reward(false, 0, 250);
+            fund_contract(1_000_000);
+
+            open(&mut escrow, 4, acc.alice, acc.bob, 10_000);
+            ink::env::test::set_block_timestamp::<Env>(1);
+            set_caller(acc.bob);
+            escrow.driver_mark_delivered(4);
+
+            let before = balance_of(acc.bob);
+            ink::env::test::set_block_timestamp::<Env>(1_001);
+            assert_eq!(escrow.auto_release_if_timeout(4), Ok(()));
+            assert_eq!(balance_of(acc.bob) - before, 10_000);
+        }
+
+        #[ink::test]
+        #[should_panic(expected = "keeper_reward_bps must not exceed MAX_KEEPER_REWARD_BPS")]
+        fn keeper_reward_is_bounded() {
+            Escrow::new_with_keeper_reward(false, 0, MAX_KEEPER_REWARD_BPS + 1);
+        }
+
+        /// Deposits must be non-zero and at least the configured minimum.
+        #[ink::test]
+        fn open_enforces_min_deposit() {
+            let acc = accounts();
+            let mut escrow = Escrow::new_with_min_deposit(false, 50);
+            set_caller(acc.alice);
+
+            ink::env::test::set_value_transferred::<Env>(0);
+            assert_eq!(escrow.open_and_deposit(1, acc.bob, 1_000), Err(Error::ZeroAmountNotAllowed));
+            ink::env::test::set_value_transferred::<Env>(49);
+            assert_eq!(escrow.open_and_deposit(1, acc.bob, 1_000), Err(Error::BelowMinDeposit));
+            assert_eq!(escrow.get_status(1), None);
+
+            open(&mut escrow, 1, acc.alice, acc.bob, 50);
+            assert_eq!(escrow.get_status(1), Some(STATUS_OPEN));
+            assert_eq!(escrow.amounts.get(1), Some(50));
+        }
+
+        /// Without a configured minimum only an empty deposit is rejected.
+        #[ink::test]
+        fn default_min_deposit_rejects_only_zero() {
+            let acc = accounts();
+            let mut escrow = Escrow::default();
+            set_caller(acc.alice);
+
+            ink::env::test::set_value_transferred::<Env>(0);
+            assert_eq!(escrow.open_and_deposit(2, acc.bob, 1_000), Err(Error::ZeroAmountNotAllowed));
+            open(&mut escrow, 2, acc.alice, acc.bob, 1);
+            assert_eq!(escrow.get_status(2), Some(STATUS_OPEN));
+        }
+
+        /// Batch status reads keep per-id semantics, including unknown ids.
+        #[ink::test]
+        fn get_statuses_reads_mixed_ids() {
+            let acc = accounts();
+            let mut escrow = Escrow::default();
+            fund_contract(1_000_000);
+
+            open(&mut escrow, 1, acc.alice, acc.bob, 100);
+            open(&mut escrow, 2, acc.alice, acc.bob, 100);
+            set_caller(acc.bob);
+            escrow.driver_mark_delivered(2);
+
+            assert_eq!(
+                escrow.get_statuses(vec![2, 7, 1, 2]),
+                vec![
+                    (2, Some(STATUS_DELIVERED)),
+                    (7, None),
+                    (1, Some(STATUS_OPEN)),
+                    (2, Some(STATUS_DELIVERED)),
+                ]
+            );
+            assert!(escrow.get_statuses(Vec::new()).is_empty());
+        }
+
+        /// An open escrow can be handed to another driver, who is then paid.
+        #[ink::test]
+        fn reassign_driver_while_open() {
+            let acc = accounts();
+            let mut escrow = Escrow::default();
+            fund_contract(1_000);
+
+            open(&mut escrow, 4, acc.alice, acc.bob, 100);
+            escrow.reassign_driver(4, acc.charlie);
+            assert_eq!(escrow.drivers.get(4), Some(acc.charlie));
+            assert_eq!(ink::env::test::recorded_events().count(), 1);
+
+            set_caller(acc.charlie);
+            escrow.driver_mark_delivered(4);
+            set_caller(acc.alice);
+            assert_eq!(escrow.receiver_confirm(4), Ok(()));
+            assert_eq!(escrow.get_status(4), Some(STATUS_COMPLETED));
+        }
+
+        /// Once delivered, the escrow keeps its driver.
+        #[ink::test]
+        #[should_panic(expected = "Escrow must be in OPEN status to reassign the driver")]
+        fn reassign_driver_after_delivery_is_rejected() {
+            let acc = accounts();
+            let mut escrow = Escrow::default();
+
+            open(&mut escrow, 5, acc.alice, acc.bob, 100);
+            set_caller(acc.bob);
+            escrow.driver_mark_delivered(5);
+
+            set_caller(acc.alice);
+            escrow.reassign_driver(5, acc.charlie);
+        }
+
+        /// Only the sender may pick a new driver.
+        #[ink::test]
+        #[should_panic(expected = "Only the sender (payer) can reassign the driver")]
+        fn reassign_driver_requires_sender() {
+            let acc = accounts();
+            let mut escrow = Escrow::default();
+
+            open(&mut escrow, 6, acc.alice, acc.bob, 100);
+            set_caller(acc.bob);
+            escrow.reassign_driver(6, acc.bob);
+        }
+
+        /// A failed payout is reported as an error, which reverts the call
+        /// on-chain (the off-chain test env keeps the writes), and still
+        /// releases the reentrancy lock.
+        #[ink::test]
+        fn failed_transfer_returns_an_error() {
+            let acc = accounts();
+            let mut escrow = Escrow::default();
+            // Contract holds nothing, so the payout transfer fails.
+            fund_contract(0);
+
+            open(&mut escrow, 7, acc.alice, acc.bob, 500);
+            set_caller(acc.bob);
+            escrow.driver_mark_delivered(7);
+
+            set_caller(acc.alice);
+            assert_eq!(escrow.receiver_confirm(7), Err(Error::TransferFailed));
+            assert_eq!(escrow.locked.get(7), None);
+        }
+
+        /// A call for an assignment that is already locked is rejected.
+        #[ink::test]
+        fn locked_escrow_rejects_reentrant_call() {
+            let acc = accounts();
+            let mut escrow = Escrow::default();
+            fund_contract(1_000);
+
+            open(&mut escrow, 9, acc.alice, acc.bob, 100);
+            // Simulate being inside an outer call for the same assignment.
+            escrow.locked.insert(9, &true);
+
+            set_caller(acc.alice);
+            assert_eq!(escrow.cancel_before_delivered(9), Err(Error::EscrowLocked));
+            assert_eq!(escrow.get_status(9), Some(STATUS_OPEN));
+        }
     }
 
     // -------------------------------------------------------------------------